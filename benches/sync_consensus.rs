@@ -0,0 +1,215 @@
+//! Compares latency of executing a block with many `DeliverTx` requests on consensus connection of a synchronous ABCI
+//! application using different [`ExecutionMode`]s.
+use std::{
+    io::{Read, Result, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use abci::{
+    sync_api::{Consensus, ExecutionMode, Info, Mempool, Server, Snapshot},
+    types::*,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use integer_encoding::VarInt;
+use prost::Message;
+use tendermint_proto::abci::{request::Value as RequestValue, Request, Response};
+
+const TXS_PER_BLOCK: u64 = 10_000;
+
+struct ConsensusConnection;
+
+impl Consensus for ConsensusConnection {
+    fn init_chain(&self, _init_chain_request: RequestInitChain) -> ResponseInitChain {
+        Default::default()
+    }
+
+    fn begin_block(&self, _begin_block_request: RequestBeginBlock) -> ResponseBeginBlock {
+        Default::default()
+    }
+
+    fn deliver_tx(&self, _deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx {
+        Default::default()
+    }
+
+    fn end_block(&self, _end_block_request: RequestEndBlock) -> ResponseEndBlock {
+        Default::default()
+    }
+
+    fn commit(&self, _commit_request: RequestCommit) -> ResponseCommit {
+        Default::default()
+    }
+}
+
+struct MempoolConnection;
+
+impl Mempool for MempoolConnection {
+    fn check_tx(&self, _check_tx_request: RequestCheckTx) -> ResponseCheckTx {
+        Default::default()
+    }
+}
+
+struct InfoConnection;
+
+impl Info for InfoConnection {
+    fn info(&self, _info_request: RequestInfo) -> ResponseInfo {
+        Default::default()
+    }
+}
+
+struct SnapshotConnection;
+
+impl Snapshot for SnapshotConnection {}
+
+fn start_server(execution_mode: ExecutionMode) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "abci-bench-{:?}-{}.sock",
+        execution_mode,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+
+    let server_path = path.clone();
+
+    thread::spawn(move || {
+        Server::with_execution_mode(
+            ConsensusConnection,
+            MempoolConnection,
+            InfoConnection,
+            SnapshotConnection,
+            execution_mode,
+        )
+        .run(server_path)
+        .expect("Unable to start ABCI server")
+    });
+
+    while !path.exists() {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    path
+}
+
+fn encode(request: RequestValue, buf: &mut Vec<u8>) {
+    let request = Request {
+        value: Some(request),
+    };
+
+    buf.extend_from_slice(&(request.encoded_len() as i64).encode_var_vec());
+    request.encode(buf).unwrap();
+}
+
+fn read_responses(stream: &mut UnixStream, count: u64) -> Result<()> {
+    let mut buf = Vec::new();
+    let mut read_buf = [0; 4096];
+    let mut remaining = count;
+
+    while remaining > 0 {
+        let bytes_read = stream.read(&mut read_buf)?;
+        buf.extend_from_slice(&read_buf[..bytes_read]);
+
+        while let Some((len, advance)) = i64::decode_var(&buf) {
+            let len = len as usize;
+
+            if buf.len() < advance + len {
+                break;
+            }
+
+            let response = Response::decode(&buf[advance..advance + len]).unwrap();
+            assert!(response.value.is_some());
+
+            buf.drain(..advance + len);
+            remaining -= 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends all the requests of a block on consensus connection and waits for all the responses
+fn execute_block(stream: &mut UnixStream, block_height: i64) -> Result<()> {
+    let mut buf = Vec::new();
+
+    encode(
+        RequestValue::BeginBlock(RequestBeginBlock {
+            header: Some(Header {
+                height: block_height,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        &mut buf,
+    );
+
+    for tx in 0..TXS_PER_BLOCK {
+        encode(
+            RequestValue::DeliverTx(RequestDeliverTx {
+                tx: tx.to_be_bytes().to_vec(),
+            }),
+            &mut buf,
+        );
+    }
+
+    encode(
+        RequestValue::EndBlock(RequestEndBlock {
+            height: block_height,
+        }),
+        &mut buf,
+    );
+    encode(RequestValue::Commit(Default::default()), &mut buf);
+
+    let mut reader = stream.try_clone()?;
+
+    thread::scope(|scope| {
+        let handle = scope.spawn(move || read_responses(&mut reader, TXS_PER_BLOCK + 3));
+        stream.write_all(&buf)?;
+        handle.join().unwrap()
+    })
+}
+
+fn connect(path: &Path) -> Result<UnixStream> {
+    let mut info_stream = UnixStream::connect(path)?;
+    let mut buf = Vec::new();
+    encode(RequestValue::Info(Default::default()), &mut buf);
+    info_stream.write_all(&buf)?;
+    read_responses(&mut info_stream, 1)?;
+
+    let mut consensus_stream = UnixStream::connect(path)?;
+    buf.clear();
+    encode(RequestValue::InitChain(Default::default()), &mut buf);
+    consensus_stream.write_all(&buf)?;
+    read_responses(&mut consensus_stream, 1)?;
+
+    Ok(consensus_stream)
+}
+
+fn deliver_tx(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deliver_tx");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(TXS_PER_BLOCK));
+
+    for execution_mode in [ExecutionMode::ThreadPool, ExecutionMode::DedicatedThread] {
+        let path = start_server(execution_mode);
+        let mut stream = connect(&path).unwrap();
+        let mut block_height = 0;
+
+        group.bench_function(
+            BenchmarkId::from_parameter(format!("{:?}", execution_mode)),
+            |b| {
+                b.iter(|| {
+                    block_height += 1;
+                    execute_block(&mut stream, block_height).unwrap();
+                })
+            },
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, deliver_tx);
+criterion_main!(benches);
@@ -9,6 +9,28 @@ use tokio::{sync::Mutex, time::sleep};
 use tracing::{subscriber::set_global_default, Level};
 use tracing_subscriber::FmtSubscriber;
 
+/// Returned when a transaction is not a big-endian encoding of 64-bit integer
+const ERR_PARSING: ErrorCode = ErrorCode::new("counter", 1, "Transaction should be 8 bytes long");
+/// Returned when numbers are not supplied in increasing order of consecutive integers starting from 1
+const ERR_VALIDATION: ErrorCode =
+    ErrorCode::new("counter", 2, "Only consecutive integers are allowed");
+
+/// Error codes of counter application (registered in an `ErrorRegistry` at startup)
+#[derive(Debug, Clone, Copy)]
+pub struct CounterErrors {
+    parsing: RegisteredErrorCode,
+    validation: RegisteredErrorCode,
+}
+
+impl CounterErrors {
+    pub fn register(registry: &mut ErrorRegistry) -> Result<Self, RegistryError> {
+        Ok(Self {
+            parsing: registry.register(ERR_PARSING)?,
+            validation: registry.register(ERR_VALIDATION)?,
+        })
+    }
+}
+
 /// Simple counter
 #[derive(Debug, Default, Clone)]
 pub struct CounterState {
@@ -21,16 +43,19 @@ pub struct CounterState {
 pub struct ConsensusConnection {
     committed_state: Arc<Mutex<CounterState>>,
     current_state: Arc<Mutex<Option<CounterState>>>,
+    errors: CounterErrors,
 }
 
 impl ConsensusConnection {
     pub fn new(
         committed_state: Arc<Mutex<CounterState>>,
         current_state: Arc<Mutex<Option<CounterState>>>,
+        errors: CounterErrors,
     ) -> Self {
         Self {
             committed_state,
             current_state,
+            errors,
         }
     }
 }
@@ -54,28 +79,20 @@ impl Consensus for ConsensusConnection {
         let new_counter = parse_bytes_to_counter(&deliver_tx_request.tx);
 
         if new_counter.is_err() {
-            return ResponseDeliverTx {
-                code: 1,
-                codespace: "Parsing error".to_owned(),
-                log: "Transaction should be 8 bytes long".to_owned(),
-                info: "Transaction is big-endian encoding of 64-bit integer".to_owned(),
-                ..Default::default()
-            };
+            return self.errors.parsing.into();
         }
 
         let new_counter = new_counter.unwrap();
 
         let mut current_state_lock = self.current_state.lock().await;
-        let mut current_state = current_state_lock.as_mut().unwrap();
+        let current_state = current_state_lock.as_mut().unwrap();
 
         if current_state.counter + 1 != new_counter {
-            return ResponseDeliverTx {
-                code: 2,
-                codespace: "Validation error".to_owned(),
-                log: "Only consecutive integers are allowed".to_owned(),
-                info: "Numbers to counter app should be supplied in increasing order of consecutive integers staring from 1".to_owned(),
-                ..Default::default()
-            };
+            return self
+                .errors
+                .validation
+                .with_details(format!("Expected counter {}", current_state.counter + 1))
+                .into();
         }
 
         current_state.counter = new_counter;
@@ -85,7 +102,7 @@ impl Consensus for ConsensusConnection {
 
     async fn end_block(&self, end_block_request: RequestEndBlock) -> ResponseEndBlock {
         let mut current_state_lock = self.current_state.lock().await;
-        let mut current_state = current_state_lock.as_mut().unwrap();
+        let current_state = current_state_lock.as_mut().unwrap();
 
         current_state.block_height = end_block_request.height;
         current_state.app_hash = current_state.counter.to_be_bytes().to_vec();
@@ -99,7 +116,7 @@ impl Consensus for ConsensusConnection {
         *committed_state = current_state;
 
         ResponseCommit {
-            data: (*committed_state).app_hash.clone(),
+            data: committed_state.app_hash.clone(),
             retain_height: 0,
         }
     }
@@ -143,8 +160,8 @@ impl Info for InfoConnection {
             data: Default::default(),
             version: Default::default(),
             app_version: Default::default(),
-            last_block_height: (*state).block_height,
-            last_block_app_hash: (*state).app_hash.clone(),
+            last_block_height: state.block_height,
+            last_block_app_hash: state.app_hash.clone(),
         }
     }
 }
@@ -183,7 +200,10 @@ pub fn server() -> Server<ConsensusConnection, MempoolConnection, InfoConnection
     let committed_state: Arc<Mutex<CounterState>> = Default::default();
     let current_state: Arc<Mutex<Option<CounterState>>> = Default::default();
 
-    let consensus = ConsensusConnection::new(committed_state.clone(), current_state);
+    let errors =
+        CounterErrors::register(&mut ErrorRegistry::new()).expect("Error codes must be unique");
+
+    let consensus = ConsensusConnection::new(committed_state.clone(), current_state, errors);
     let mempool = MempoolConnection;
     let info = InfoConnection::new(committed_state);
     let snapshot = SnapshotConnection;
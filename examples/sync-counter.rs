@@ -5,12 +5,34 @@ use std::{
 };
 
 use abci::{
-    sync_api::{Consensus, Info, Mempool, Server, Snapshot},
+    sync_api::{ConsensusMut, Info, Mempool, Server, Snapshot},
     types::*,
 };
 use tracing::{subscriber::set_global_default, Level};
 use tracing_subscriber::FmtSubscriber;
 
+/// Returned when a transaction is not a big-endian encoding of 64-bit integer
+const ERR_PARSING: ErrorCode = ErrorCode::new("counter", 1, "Transaction should be 8 bytes long");
+/// Returned when numbers are not supplied in increasing order of consecutive integers starting from 1
+const ERR_VALIDATION: ErrorCode =
+    ErrorCode::new("counter", 2, "Only consecutive integers are allowed");
+
+/// Error codes of counter application (registered in an `ErrorRegistry` at startup)
+#[derive(Debug, Clone, Copy)]
+pub struct CounterErrors {
+    parsing: RegisteredErrorCode,
+    validation: RegisteredErrorCode,
+}
+
+impl CounterErrors {
+    pub fn register(registry: &mut ErrorRegistry) -> Result<Self, RegistryError> {
+        Ok(Self {
+            parsing: registry.register(ERR_PARSING)?,
+            validation: registry.register(ERR_VALIDATION)?,
+        })
+    }
+}
+
 /// Simple counter
 #[derive(Debug, Default, Clone)]
 pub struct CounterState {
@@ -22,61 +44,48 @@ pub struct CounterState {
 #[derive(Debug)]
 pub struct ConsensusConnection {
     committed_state: Arc<Mutex<CounterState>>,
-    current_state: Arc<Mutex<Option<CounterState>>>,
+    current_state: Option<CounterState>,
+    errors: CounterErrors,
 }
 
 impl ConsensusConnection {
-    pub fn new(
-        committed_state: Arc<Mutex<CounterState>>,
-        current_state: Arc<Mutex<Option<CounterState>>>,
-    ) -> Self {
+    pub fn new(committed_state: Arc<Mutex<CounterState>>, errors: CounterErrors) -> Self {
         Self {
             committed_state,
-            current_state,
+            current_state: None,
+            errors,
         }
     }
 }
 
-impl Consensus for ConsensusConnection {
-    fn init_chain(&self, _init_chain_request: RequestInitChain) -> ResponseInitChain {
+impl ConsensusMut for ConsensusConnection {
+    fn init_chain(&mut self, _init_chain_request: RequestInitChain) -> ResponseInitChain {
         Default::default()
     }
 
-    fn begin_block(&self, _begin_block_request: RequestBeginBlock) -> ResponseBeginBlock {
-        let committed_state = self.committed_state.lock().unwrap().clone();
-
-        let mut current_state = self.current_state.lock().unwrap();
-        *current_state = Some(committed_state);
+    fn begin_block(&mut self, _begin_block_request: RequestBeginBlock) -> ResponseBeginBlock {
+        self.current_state = Some(self.committed_state.lock().unwrap().clone());
 
         Default::default()
     }
 
-    fn deliver_tx(&self, deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx {
+    fn deliver_tx(&mut self, deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx {
         let new_counter = parse_bytes_to_counter(&deliver_tx_request.tx);
 
         if new_counter.is_err() {
-            return ResponseDeliverTx {
-                code: 1,
-                codespace: "Parsing error".to_owned(),
-                log: "Transaction should be 8 bytes long".to_owned(),
-                info: "Transaction is big-endian encoding of 64-bit integer".to_owned(),
-                ..Default::default()
-            };
+            return self.errors.parsing.into();
         }
 
         let new_counter = new_counter.unwrap();
 
-        let mut current_state_lock = self.current_state.lock().unwrap();
-        let mut current_state = current_state_lock.as_mut().unwrap();
+        let current_state = self.current_state.as_mut().unwrap();
 
         if current_state.counter + 1 != new_counter {
-            return ResponseDeliverTx {
-                code: 2,
-                codespace: "Validation error".to_owned(),
-                log: "Only consecutive integers are allowed".to_owned(),
-                info: "Numbers to counter app should be supplied in increasing order of consecutive integers staring from 1".to_owned(),
-                ..Default::default()
-            };
+            return self
+                .errors
+                .validation
+                .with_details(format!("Expected counter {}", current_state.counter + 1))
+                .into();
         }
 
         current_state.counter = new_counter;
@@ -84,9 +93,8 @@ impl Consensus for ConsensusConnection {
         Default::default()
     }
 
-    fn end_block(&self, end_block_request: RequestEndBlock) -> ResponseEndBlock {
-        let mut current_state_lock = self.current_state.lock().unwrap();
-        let mut current_state = current_state_lock.as_mut().unwrap();
+    fn end_block(&mut self, end_block_request: RequestEndBlock) -> ResponseEndBlock {
+        let current_state = self.current_state.as_mut().unwrap();
 
         current_state.block_height = end_block_request.height;
         current_state.app_hash = current_state.counter.to_be_bytes().to_vec();
@@ -94,13 +102,13 @@ impl Consensus for ConsensusConnection {
         Default::default()
     }
 
-    fn commit(&self, _commit_request: RequestCommit) -> ResponseCommit {
-        let current_state = self.current_state.lock().unwrap().as_ref().unwrap().clone();
+    fn commit(&mut self, _commit_request: RequestCommit) -> ResponseCommit {
+        let current_state = self.current_state.take().unwrap();
         let mut committed_state = self.committed_state.lock().unwrap();
         *committed_state = current_state;
 
         ResponseCommit {
-            data: (*committed_state).app_hash.clone(),
+            data: committed_state.app_hash.clone(),
             retain_height: 0,
         }
     }
@@ -142,8 +150,8 @@ impl Info for InfoConnection {
             data: Default::default(),
             version: Default::default(),
             app_version: Default::default(),
-            last_block_height: (*state).block_height,
-            last_block_app_hash: (*state).app_hash.clone(),
+            last_block_height: state.block_height,
+            last_block_app_hash: state.app_hash.clone(),
         }
     }
 }
@@ -176,9 +184,11 @@ fn main() -> std::io::Result<()> {
 pub fn server() -> Server<ConsensusConnection, MempoolConnection, InfoConnection, SnapshotConnection>
 {
     let committed_state: Arc<Mutex<CounterState>> = Default::default();
-    let current_state: Arc<Mutex<Option<CounterState>>> = Default::default();
 
-    let consensus = ConsensusConnection::new(committed_state.clone(), current_state);
+    let errors =
+        CounterErrors::register(&mut ErrorRegistry::new()).expect("Error codes must be unique");
+
+    let consensus = ConsensusConnection::new(committed_state.clone(), errors);
     let mempool = MempoolConnection;
     let info = InfoConnection::new(committed_state);
     let snapshot = SnapshotConnection;
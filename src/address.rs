@@ -1,19 +1,55 @@
-use std::net::SocketAddr;
 #[cfg(unix)]
-use std::path::PathBuf;
+use std::{
+    env,
+    os::{
+        fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+        unix::net::UnixListener,
+    },
+    path::PathBuf,
+    process,
+};
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    net::{SocketAddr, TcpListener},
+    str::FromStr,
+};
 
 #[cfg(test)]
 use mock_io::tokio::MockListener;
 
 /// Address of ABCI Server
+///
+/// An address can be parsed from the syntax used by Tendermint for `proxy_app` (e.g., `tcp://127.0.0.1:26658`,
+/// `tcp://[::1]:26658`, `tcp://localhost:26658` or `unix:///path/to/app.sock`). Addresses without a scheme are treated
+/// as TCP addresses. On Linux, `unix://@name` is parsed as an abstract-namespace socket. With `serde` feature enabled,
+/// addresses are serialized and deserialized as strings in the same syntax.
+///
+/// Server can also run on an already bound listener (e.g., one inherited from parent process during a zero-downtime
+/// restart or passed by systemd with socket activation, see [`from_listen_fds`](Self::from_listen_fds)).
 #[derive(Debug)]
 pub enum Address {
     /// TCP Address
     Tcp(SocketAddr),
+    /// TCP Address with a host name (and port) which is resolved when server binds to it
+    Host(String, u16),
     /// UDS Address
     #[cfg(unix)]
     #[cfg_attr(feature = "doc", doc(cfg(unix)))]
     Uds(PathBuf),
+    /// Linux abstract-namespace UDS Address (name without the leading `@`)
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[cfg_attr(
+        feature = "doc",
+        doc(cfg(any(target_os = "linux", target_os = "android")))
+    )]
+    AbstractUds(String),
+    /// Already bound TCP listener
+    TcpListener(TcpListener),
+    /// Already bound UDS listener
+    #[cfg(unix)]
+    #[cfg_attr(feature = "doc", doc(cfg(unix)))]
+    UnixListener(UnixListener),
     /// Mock Address
     #[cfg(test)]
     Mock(MockListener),
@@ -32,9 +68,380 @@ impl From<PathBuf> for Address {
     }
 }
 
+impl From<TcpListener> for Address {
+    fn from(listener: TcpListener) -> Self {
+        Self::TcpListener(listener)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixListener> for Address {
+    fn from(listener: UnixListener) -> Self {
+        Self::UnixListener(listener)
+    }
+}
+
 #[cfg(test)]
 impl From<MockListener> for Address {
     fn from(listener: MockListener) -> Self {
         Self::Mock(listener)
     }
 }
+
+impl Address {
+    /// Returns the listeners passed to current process using systemd socket activation protocol (i.e., `LISTEN_PID`,
+    /// `LISTEN_FDS` environment variables and file descriptors starting from `3`)
+    ///
+    /// Returns an empty list if no listener is passed to current process. The environment variables are removed so
+    /// that they are not inherited by child processes. This function should be called at most once because it takes
+    /// ownership of the passed file descriptors.
+    ///
+    /// Returns an error if `LISTEN_FDS` is invalid or if a passed file descriptor is not a listening TCP or UDS socket. In
+    /// that case, none of the passed file descriptors is taken (or closed).
+    #[cfg(unix)]
+    #[cfg_attr(feature = "doc", doc(cfg(unix)))]
+    pub fn from_listen_fds() -> Result<Vec<Self>> {
+        let listen_pid = env::var("LISTEN_PID").ok();
+        let listen_fds = env::var("LISTEN_FDS").ok();
+
+        env::remove_var("LISTEN_PID");
+        env::remove_var("LISTEN_FDS");
+        env::remove_var("LISTEN_FDNAMES");
+
+        Self::from_listen_env(listen_pid, listen_fds)
+    }
+
+    /// Parses values of `LISTEN_PID` and `LISTEN_FDS` environment variables and takes the passed file descriptors
+    #[cfg(unix)]
+    fn from_listen_env(
+        listen_pid: Option<String>,
+        listen_fds: Option<String>,
+    ) -> Result<Vec<Self>> {
+        const LISTEN_FDS_START: RawFd = 3;
+
+        let (listen_pid, listen_fds) = match (listen_pid, listen_fds) {
+            (Some(listen_pid), Some(listen_fds)) => (listen_pid, listen_fds),
+            _ => return Ok(Vec::new()),
+        };
+
+        let invalid = |name: &str, value: &str| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid value of `{}`: {}", name, value),
+            )
+        };
+
+        let pid: u32 = listen_pid
+            .parse()
+            .map_err(|_| invalid("LISTEN_PID", &listen_pid))?;
+
+        if pid != process::id() {
+            return Ok(Vec::new());
+        }
+
+        let count: RawFd = listen_fds
+            .parse()
+            .ok()
+            .filter(|count| *count >= 0)
+            .ok_or_else(|| invalid("LISTEN_FDS", &listen_fds))?;
+
+        let fds: Vec<RawFd> = (LISTEN_FDS_START..LISTEN_FDS_START.saturating_add(count)).collect();
+
+        // All the descriptors are validated before taking any of them, so that no passed descriptor is closed when
+        // another one is invalid
+        let families = fds
+            .iter()
+            .map(|fd| {
+                // SAFETY: File descriptors starting from `LISTEN_FDS_START` are passed to current process (as
+                // `LISTEN_PID` matches) and are not owned by anything else. They are only borrowed while validating.
+                #[allow(unsafe_code)]
+                let fd = unsafe { BorrowedFd::borrow_raw(*fd) };
+
+                listener_family(fd)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(fds
+            .into_iter()
+            .zip(families)
+            .map(|(fd, family)| {
+                // SAFETY: See above. Environment variables are removed before calling this function so that the
+                // descriptors are not taken again.
+                #[allow(unsafe_code)]
+                let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+                Self::from_listener_fd(fd, family)
+            })
+            .collect())
+    }
+
+    /// Wraps a listening stream socket in a TCP or UDS listener depending on its address family
+    #[cfg(unix)]
+    fn from_listener_fd(fd: OwnedFd, family: ListenerFamily) -> Self {
+        match family {
+            ListenerFamily::Tcp => Self::TcpListener(fd.into()),
+            ListenerFamily::Unix => Self::UnixListener(fd.into()),
+        }
+    }
+}
+
+/// Address family of a listener passed to current process
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListenerFamily {
+    Tcp,
+    Unix,
+}
+
+/// Returns the address family of given file descriptor or an error if it is not a listening TCP or UDS socket
+#[cfg(unix)]
+fn listener_family(fd: BorrowedFd<'_>) -> Result<ListenerFamily> {
+    use rustix::net::{getsockname, sockopt, SocketAddrAny, SocketType};
+
+    let invalid = |reason: &str| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid listener file descriptor `{}`: {}",
+                fd.as_raw_fd(),
+                reason
+            ),
+        )
+    };
+
+    if sockopt::get_socket_type(fd)? != SocketType::STREAM {
+        return Err(invalid("not a stream socket"));
+    }
+
+    // Apple platforms do not implement `SO_ACCEPTCONN`
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "tvos",
+        target_os = "watchos"
+    )))]
+    if !sockopt::get_socket_acceptconn(fd)? {
+        return Err(invalid("socket is not listening"));
+    }
+
+    match getsockname(fd)? {
+        SocketAddrAny::V4(_) | SocketAddrAny::V6(_) => Ok(ListenerFamily::Tcp),
+        SocketAddrAny::Unix(_) => Ok(ListenerFamily::Unix),
+        _ => Err(invalid("unsupported address family")),
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid address `{}`: {}", s, reason),
+            )
+        };
+
+        let (scheme, addr) = s.split_once("://").unwrap_or(("tcp", s));
+
+        match scheme {
+            "tcp" => {
+                if let Ok(addr) = addr.parse::<SocketAddr>() {
+                    return Ok(Self::Tcp(addr));
+                }
+
+                let (host, port) = addr
+                    .rsplit_once(':')
+                    .ok_or_else(|| invalid("missing port"))?;
+
+                if host.is_empty() || host.contains([':', '[', ']']) {
+                    return Err(invalid("invalid host"));
+                }
+
+                let port = port.parse().map_err(|_| invalid("invalid port"))?;
+
+                Ok(Self::Host(host.to_string(), port))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            "unix" if addr.starts_with('@') && addr.len() > 1 => {
+                Ok(Self::AbstractUds(addr[1..].to_string()))
+            }
+            #[cfg(unix)]
+            "unix" if !addr.is_empty() => Ok(Self::Uds(PathBuf::from(addr))),
+            #[cfg(unix)]
+            "unix" => Err(invalid("missing path")),
+            _ => Err(invalid("unsupported scheme")),
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "tcp://{}", addr),
+            Self::Host(host, port) => write!(f, "tcp://{}:{}", host, port),
+            #[cfg(unix)]
+            Self::Uds(path) => write!(f, "unix://{}", path.display()),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Self::AbstractUds(name) => write!(f, "unix://@{}", name),
+            Self::TcpListener(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "tcp://{}", addr),
+                Err(_) => write!(f, "tcp://"),
+            },
+            #[cfg(unix)]
+            Self::UnixListener(listener) => match listener
+                .local_addr()
+                .ok()
+                .and_then(|addr| addr.as_pathname().map(|path| path.display().to_string()))
+            {
+                Some(path) => write!(f, "unix://{}", path),
+                None => write!(f, "unix://"),
+            },
+            #[cfg(test)]
+            Self::Mock(_) => write!(f, "mock://"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc", doc(cfg(feature = "serde")))]
+impl serde::Serialize for Address {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc", doc(cfg(feature = "serde")))]
+impl<'de> serde::Deserialize<'de> for Address {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let addr = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        addr.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::{
+        net::{TcpStream, UdpSocket},
+        os::fd::AsFd,
+    };
+
+    use super::*;
+
+    #[test]
+    fn check_address_from_str() {
+        let cases = [
+            ("tcp://127.0.0.1:26658", "tcp://127.0.0.1:26658"),
+            ("127.0.0.1:26658", "tcp://127.0.0.1:26658"),
+            ("tcp://[::1]:26658", "tcp://[::1]:26658"),
+            ("tcp://localhost:26658", "tcp://localhost:26658"),
+            #[cfg(unix)]
+            ("unix:///path/to/app.sock", "unix:///path/to/app.sock"),
+            #[cfg(unix)]
+            ("unix://app.sock", "unix://app.sock"),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            ("unix://@abci", "unix://@abci"),
+        ];
+
+        for (input, output) in cases {
+            assert_eq!(output, input.parse::<Address>().unwrap().to_string());
+        }
+
+        assert!(matches!(
+            "tcp://localhost:26658".parse::<Address>().unwrap(),
+            Address::Host(host, 26658) if host == "localhost"
+        ));
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert!(matches!(
+            "unix://@abci".parse::<Address>().unwrap(),
+            Address::AbstractUds(name) if name == "abci"
+        ));
+
+        for input in [
+            "tcp://localhost",
+            "tcp://localhost:port",
+            "tcp://:26658",
+            "tcp://::1:26658",
+            "unix://",
+            "grpc://127.0.0.1:26658",
+        ] {
+            assert!(input.parse::<Address>().is_err(), "{}", input);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn check_address_serde() {
+        let addr: Address = serde_json::from_str("\"tcp://localhost:26658\"").unwrap();
+        assert!(matches!(addr, Address::Host(ref host, 26658) if host == "localhost"));
+        assert_eq!(
+            "\"tcp://localhost:26658\"",
+            serde_json::to_string(&addr).unwrap()
+        );
+
+        assert!(serde_json::from_str::<Address>("\"localhost\"").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_address_from_listen_env() {
+        let pid = || Some(process::id().to_string());
+        let other_pid = Some((process::id() + 1).to_string());
+
+        assert!(Address::from_listen_env(None, None).unwrap().is_empty());
+        assert!(Address::from_listen_env(other_pid, Some("1".to_owned()))
+            .unwrap()
+            .is_empty());
+        assert!(Address::from_listen_env(pid(), Some("0".to_owned()))
+            .unwrap()
+            .is_empty());
+        assert!(Address::from_listen_env(Some("pid".to_owned()), Some("1".to_owned())).is_err());
+        assert!(Address::from_listen_env(pid(), Some("fds".to_owned())).is_err());
+        assert!(Address::from_listen_env(pid(), Some("-1".to_owned())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_listener_family() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert_eq!(
+            ListenerFamily::Tcp,
+            listener_family(listener.as_fd()).unwrap()
+        );
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let err = listener_family(stream.as_fd()).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let err = listener_family(socket.as_fd()).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+        let path = std::env::temp_dir().join(format!("abci-listen-fds-{}.sock", process::id()));
+        let _ = std::fs::remove_file(&path);
+        let unix_listener = UnixListener::bind(&path).unwrap();
+        assert_eq!(
+            ListenerFamily::Unix,
+            listener_family(unix_listener.as_fd()).unwrap()
+        );
+
+        let address = Address::from_listener_fd(unix_listener.into(), ListenerFamily::Unix);
+        assert!(matches!(address, Address::UnixListener(_)));
+        let _ = std::fs::remove_file(&path);
+
+        let address = Address::from_listener_fd(listener.into(), ListenerFamily::Tcp);
+        assert!(
+            matches!(address, Address::TcpListener(ref listener) if listener.local_addr().unwrap() == addr)
+        );
+    }
+}
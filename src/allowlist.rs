@@ -0,0 +1,285 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    net::IpAddr,
+    str::FromStr,
+};
+
+use crate::{runtime::Connection, utils::ConnectionType};
+
+/// Access control for incoming connections
+///
+/// By default, all the peers are allowed. Once an IP range (or UID) is added, only TCP (or Unix socket) peers matching
+/// one of the added ranges (or UIDs) are allowed. Rejected peers are logged and their connections are closed before
+/// any request is read.
+#[derive(Debug, Clone, Default)]
+pub struct PeerAllowlist {
+    ip_ranges: Vec<IpRange>,
+    uids: Vec<u32>,
+    duplicate_connection_policies: [DuplicateConnectionPolicy; 4],
+}
+
+/// Policy applied to a new connection when a connection of same type (consensus, mempool, info or snapshot) is already
+/// established
+///
+/// Type of a connection is determined by its first request and the policy is applied before executing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateConnectionPolicy {
+    /// Accepts the new connection alongside established ones
+    #[default]
+    Allow,
+    /// Rejects (i.e., logs and closes) the new connection
+    Reject,
+    /// Accepts the new connection and closes established ones
+    ///
+    /// Established connections stop reading new requests immediately. Their in-flight requests still complete and
+    /// their responses are written before the connections are closed. This is useful when Tendermint reconnects before
+    /// its old connection is detected as closed.
+    ///
+    /// With runtimes which block on socket reads (e.g., `StdRuntime`, see
+    /// [`Runtime::is_thread_per_task`](crate::runtime::Runtime::is_thread_per_task)), a pending read cannot be
+    /// interrupted. So, a replaced connection is only closed once it receives more data (which is discarded without
+    /// executing its requests) or its peer closes it, and it is counted as established until then.
+    Replace,
+}
+
+impl PeerAllowlist {
+    /// Creates a new instance of [`PeerAllowlist`] which allows all the peers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows TCP peers with an IP address in given range
+    pub fn allow_ip_range(mut self, ip_range: IpRange) -> Self {
+        self.ip_ranges.push(ip_range);
+        self
+    }
+
+    /// Allows Unix socket peers running as given user
+    ///
+    /// Peer credentials are only available on Linux and Android (see [`peer_uid`](crate::runtime::peer_uid)). On other
+    /// platforms, all the Unix socket peers are rejected once a UID is added.
+    pub fn allow_uid(mut self, uid: u32) -> Self {
+        self.uids.push(uid);
+        self
+    }
+
+    /// Allows at most one established connection of each type (consensus, mempool, info and snapshot) at once
+    /// (default: `false`)
+    ///
+    /// Type of a connection is determined by its first request. When a connection of same type is already
+    /// established, the new connection is rejected before executing its first request. This is a shorthand for setting
+    /// [`DuplicateConnectionPolicy::Reject`] (or [`DuplicateConnectionPolicy::Allow`], when disabled) for all the
+    /// connection types.
+    pub fn with_single_connection_per_type(mut self, enabled: bool) -> Self {
+        let policy = if enabled {
+            DuplicateConnectionPolicy::Reject
+        } else {
+            DuplicateConnectionPolicy::Allow
+        };
+
+        self.duplicate_connection_policies = [policy; 4];
+        self
+    }
+
+    /// Sets the policy for new connections of given type when a connection of same type is already established
+    /// (default: [`DuplicateConnectionPolicy::Allow`])
+    ///
+    /// Connections of [`ConnectionType::Unknown`] are never considered duplicates, so, their policy cannot be set and
+    /// is ignored.
+    pub fn with_duplicate_connection_policy(
+        mut self,
+        connection_type: ConnectionType,
+        policy: DuplicateConnectionPolicy,
+    ) -> Self {
+        if let Some(index) = connection_type.index() {
+            self.duplicate_connection_policies[index] = policy;
+        }
+
+        self
+    }
+
+    /// Returns the policy for new connections of given type
+    pub(crate) fn duplicate_connection_policy(
+        &self,
+        connection_type: ConnectionType,
+    ) -> DuplicateConnectionPolicy {
+        connection_type
+            .index()
+            .map(|index| self.duplicate_connection_policies[index])
+            .unwrap_or_default()
+    }
+
+    /// Checks if a TCP peer is allowed. Returns the reason of rejection otherwise.
+    pub(crate) fn check_tcp(&self, connection: &Connection) -> std::result::Result<(), String> {
+        if self.ip_ranges.is_empty() {
+            return Ok(());
+        }
+
+        match connection.peer_ip {
+            Some(ip) if self.ip_ranges.iter().any(|range| range.contains(ip)) => Ok(()),
+            Some(ip) => Err(format!("IP address {} is not allowed", ip)),
+            None => Err("IP address of peer is not available".to_string()),
+        }
+    }
+
+    /// Checks if a Unix socket peer is allowed. Returns the reason of rejection otherwise.
+    pub(crate) fn check_unix(&self, connection: &Connection) -> std::result::Result<(), String> {
+        if self.uids.is_empty() {
+            return Ok(());
+        }
+
+        match connection.peer_uid {
+            Some(uid) if self.uids.contains(&uid) => Ok(()),
+            Some(uid) => Err(format!("UID {} is not allowed", uid)),
+            None => Err("Credentials of peer are not available".to_string()),
+        }
+    }
+}
+
+/// A range of IP addresses in CIDR notation (e.g., `10.0.0.0/8` or `fd00::/8`)
+///
+/// IPv4 ranges also match IPv4-mapped IPv6 addresses (e.g., `::ffff:10.0.0.1`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Creates a new instance of [`IpRange`] from network address and prefix length. Host bits of `addr` are ignored.
+    ///
+    /// Returns an error if `prefix_len` is longer than the address (32 for IPv4 and 128 for IPv6).
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self> {
+        if prefix_len > max_prefix_len(addr) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid prefix length for {}: {}", addr, prefix_len),
+            ));
+        }
+
+        Ok(Self { addr, prefix_len })
+    }
+
+    /// Returns `true` if given IP address is in this range
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let addr = match addr {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(addr),
+            IpAddr::V4(_) => addr,
+        };
+
+        let (network, addr) = match (self.addr, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                (u32::from(network) as u128, u32::from(addr) as u128)
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => (u128::from(network), u128::from(addr)),
+            _ => return false,
+        };
+
+        let shift = u32::from(max_prefix_len(self.addr) - self.prefix_len);
+
+        network.checked_shr(shift).unwrap_or_default()
+            == addr.checked_shr(shift).unwrap_or_default()
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    /// Creates a range containing only given address
+    fn from(addr: IpAddr) -> Self {
+        Self {
+            addr,
+            prefix_len: max_prefix_len(addr),
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = Error;
+
+    /// Parses an IP range in CIDR notation (e.g., `10.0.0.0/8`) or a single IP address (e.g., `127.0.0.1`)
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("Invalid IP range: {}", s));
+
+        match s.split_once('/') {
+            Some((addr, prefix_len)) => Self::new(
+                addr.parse().map_err(|_| invalid())?,
+                prefix_len.parse().map_err(|_| invalid())?,
+            ),
+            None => s.parse::<IpAddr>().map(Into::into).map_err(|_| invalid()),
+        }
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+fn max_prefix_len(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_duplicate_connection_policy() {
+        let allowlist = PeerAllowlist::new()
+            .with_single_connection_per_type(true)
+            .with_duplicate_connection_policy(
+                ConnectionType::Info,
+                DuplicateConnectionPolicy::Replace,
+            )
+            .with_duplicate_connection_policy(
+                ConnectionType::Unknown,
+                DuplicateConnectionPolicy::Reject,
+            );
+
+        assert_eq!(
+            DuplicateConnectionPolicy::Replace,
+            allowlist.duplicate_connection_policy(ConnectionType::Info)
+        );
+        assert_eq!(
+            DuplicateConnectionPolicy::Reject,
+            allowlist.duplicate_connection_policy(ConnectionType::Mempool)
+        );
+        assert_eq!(
+            DuplicateConnectionPolicy::Allow,
+            allowlist.duplicate_connection_policy(ConnectionType::Unknown)
+        );
+    }
+
+    #[test]
+    fn check_ip_range() {
+        let range: IpRange = "10.1.0.0/16".parse().unwrap();
+
+        assert!(range.contains("10.1.2.3".parse().unwrap()));
+        assert!(range.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!range.contains("10.2.0.1".parse().unwrap()));
+        assert!(!range.contains("::1".parse().unwrap()));
+
+        let range: IpRange = "fd00::/8".parse().unwrap();
+
+        assert!(range.contains("fd12::1".parse().unwrap()));
+        assert!(!range.contains("fe80::1".parse().unwrap()));
+
+        let range: IpRange = "127.0.0.1".parse().unwrap();
+
+        assert_eq!("127.0.0.1/32", range.to_string());
+        assert!(range.contains("127.0.0.1".parse().unwrap()));
+        assert!(!range.contains("127.0.0.2".parse().unwrap()));
+
+        let range: IpRange = "0.0.0.0/0".parse().unwrap();
+
+        assert!(range.contains("192.168.1.1".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("10.0.0.0/".parse::<IpRange>().is_err());
+        assert!("localhost".parse::<IpRange>().is_err());
+    }
+}
@@ -2,7 +2,12 @@
 mod application;
 mod server;
 
+#[cfg(feature = "async-api")]
+pub use self::server::{
+    DEFAULT_MAX_IN_FLIGHT_CHECK_TX, DEFAULT_MAX_IN_FLIGHT_LOAD_SNAPSHOT_CHUNK,
+    DEFAULT_MAX_IN_FLIGHT_QUERY, DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX,
+};
 pub use self::{
     application::{Consensus, Info, Mempool, Snapshot},
-    server::Server,
+    server::{BoundServer, Server},
 };
@@ -135,6 +135,19 @@ pub trait Consensus: Send + Sync {
     /// ```
     async fn deliver_tx(&self, deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx;
 
+    /// Returns the set of keys read and written by a transaction, or `None` if it is not known.
+    ///
+    /// Only used when parallel execution of transactions is enabled on server (see
+    /// [`Server::with_parallel_deliver_tx`](super::Server::with_parallel_deliver_tx)). Transactions which do not conflict
+    /// with each other are executed in parallel while a transaction without an access set is executed only after all
+    /// the transactions before it and before all the transactions after it. Default implementation returns `None`,
+    /// i.e., all the transactions are executed sequentially.
+    ///
+    /// The returned access set must only depend on the transaction so that it is the same on every node.
+    fn deliver_tx_access(&self, _deliver_tx_request: &RequestDeliverTx) -> Option<TxAccess> {
+        None
+    }
+
     /// Signals the end of a block. Called after all transactions, prior to each [`commit`](trait.Commit.html#tymethod.commit).
     ///
     /// # Equivalent to
@@ -241,6 +254,80 @@ pub trait Mempool: Send + Sync {
     /// ```
     async fn check_tx(&self, check_tx_request: RequestCheckTx) -> ResponseCheckTx;
 
+    /// Checks a batch of transactions at once (e.g., to verify their signatures in a single batch). Responses should
+    /// be returned in the same order as requests.
+    ///
+    /// This is only called when batching is enabled on server (see
+    /// [`Server::with_check_tx_batch_limit`](super::Server::with_check_tx_batch_limit)). Default implementation calls
+    /// [`check_tx`](self::Mempool::check_tx) for each request.
+    ///
+    /// # Equivalent to
+    ///
+    /// ```rust,ignore
+    /// async fn check_tx_batch(&self, check_tx_requests: Vec<RequestCheckTx>) -> Vec<ResponseCheckTx>
+    /// ```
+    async fn check_tx_batch(&self, check_tx_requests: Vec<RequestCheckTx>) -> Vec<ResponseCheckTx> {
+        let mut responses = Vec::with_capacity(check_tx_requests.len());
+
+        for check_tx_request in check_tx_requests {
+            responses.push(self.check_tx(check_tx_request).await);
+        }
+
+        responses
+    }
+
+    /// Returns a fingerprint of the state relevant for validating a transaction (e.g., nonce and balance of its sender),
+    /// or `None` if the response of transaction should not be cached.
+    ///
+    /// This is only called when recheck cache is enabled on server (see
+    /// [`Server::with_recheck_cache`](super::Server::with_recheck_cache)). When a transaction is rechecked and its
+    /// fingerprint is the same as the one returned when it was last checked, server responds with the cached response
+    /// without calling [`check_tx`](self::Mempool::check_tx).
+    ///
+    /// Because [`check_tx`](self::Mempool::check_tx) is skipped on a cache hit, any changes it makes to
+    /// `mempool_state` (e.g., incrementing nonce of sender) are lost unless the fingerprint covers all the state which
+    /// `check_tx` mutates (so that a changed state always causes a cache miss), or those changes are replayed in
+    /// [`on_cached_recheck`](self::Mempool::on_cached_recheck).
+    ///
+    /// # Equivalent to
+    ///
+    /// ```rust,ignore
+    /// async fn recheck_fingerprint(&self, check_tx_request: &RequestCheckTx) -> Option<Vec<u8>>
+    /// ```
+    async fn recheck_fingerprint(&self, _check_tx_request: &RequestCheckTx) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Called when a rechecked transaction is answered from recheck cache instead of calling
+    /// [`check_tx`](self::Mempool::check_tx) (see [`recheck_fingerprint`](self::Mempool::recheck_fingerprint)). Can be
+    /// used to replay the changes `check_tx` makes to `mempool_state` for a transaction with given cached response.
+    ///
+    /// # Equivalent to
+    ///
+    /// ```rust,ignore
+    /// async fn on_cached_recheck(&self, check_tx_request: &RequestCheckTx, check_tx_response: &ResponseCheckTx)
+    /// ```
+    async fn on_cached_recheck(
+        &self,
+        _check_tx_request: &RequestCheckTx,
+        _check_tx_response: &ResponseCheckTx,
+    ) {
+    }
+
+    /// Called after each successful [`commit`](self::Consensus::commit). Can be used to reset `mempool_state` to the
+    /// **latest committed state**.
+    ///
+    /// When commit barrier is enabled on server (see
+    /// [`Server::with_commit_barrier`](super::Server::with_commit_barrier)), execution of `CheckTx` requests is paused
+    /// until this hook completes, so the reset is atomic with the commit.
+    ///
+    /// # Equivalent to
+    ///
+    /// ```rust,ignore
+    /// async fn on_commit(&self, commit_response: &ResponseCommit)
+    /// ```
+    async fn on_commit(&self, _commit_response: &ResponseCommit) {}
+
     /// Signals that messages queued on the client should be flushed to the server.
     ///
     /// # Equivalent to
@@ -1,59 +1,77 @@
-use std::{io::Result, sync::Arc};
-
-#[cfg(all(unix, feature = "use-async-std"))]
-use async_std::os::unix::net::UnixListener;
-#[cfg(feature = "use-async-std")]
-use async_std::{
-    io::{Read, Write},
-    net::TcpListener,
-    sync::Mutex,
-    task::spawn,
+use std::{
+    future::Future,
+    io::{Error, ErrorKind, Result},
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::Poll,
+    time::Duration,
 };
-#[cfg(all(unix, feature = "use-smol"))]
-use smol::net::unix::UnixListener;
-#[cfg(feature = "use-smol")]
-use smol::{
+
+use async_lock::Mutex;
+#[cfg(feature = "tls")]
+use futures_lite::future::or;
+use futures_lite::{
+    future::{poll_fn, ready},
     io::{AsyncRead as Read, AsyncWrite as Write},
-    lock::Mutex,
-    net::TcpListener,
-    spawn,
 };
+#[cfg(feature = "tls")]
+use futures_rustls::TlsAcceptor;
+#[cfg(test)]
+use mock_io::tokio::MockListener;
 use tendermint_proto::abci::{Request, Response};
-#[cfg(all(unix, feature = "use-tokio"))]
-use tokio::net::UnixListener;
-#[cfg(feature = "use-tokio")]
-use tokio::{
-    io::{AsyncRead as Read, AsyncWrite as Write},
-    net::TcpListener,
-    spawn,
-    sync::Mutex,
-};
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
 
+#[cfg(all(
+    feature = "async-api",
+    any(
+        feature = "use-async-std",
+        feature = "use-smol",
+        feature = "use-std",
+        feature = "use-tokio"
+    )
+))]
+use crate::runtime::DefaultRuntime;
+#[cfg(feature = "async-api")]
+use crate::runtime::JoinHandle;
+#[cfg(feature = "tls")]
+use crate::TlsConfig;
 use crate::{
     address::Address,
+    allowlist::{DuplicateConnectionPolicy, PeerAllowlist},
     async_api::{Consensus, Info, Mempool, Snapshot},
     handler::*,
-    state::ConsensusStateValidator,
+    metrics::Metrics,
+    runtime::{BoxFuture, Connection, Listener, Runtime},
+    state::{ConsensusStateValidator, StateSnapshotCell},
     stream_split::StreamSplit,
     tasks::*,
-    utils::{get_stream_pair, ConnectionType, StreamReader, StreamWriter},
+    utils::{
+        get_stream_pair, serve_http, CommitBarrier, ConnectionGuard, ConnectionTracker,
+        ConnectionType, Health, HttpHandler, HttpResponse, InFlightLimiter, QueueMetrics,
+        RecheckStats, RecheckTracker, StreamReader, StreamWriter,
+    },
+    watchdog::Watchdog,
+    DeadlineAction, Method,
 };
+#[cfg(unix)]
+use crate::{unix_socket::SocketFileGuard, UnixSocketConfig};
 
-macro_rules! spawn {
-    ($expr: expr) => {
-        cfg_if::cfg_if! {
-            if #[cfg(any(feature = "use-async-std", feature = "use-tokio"))] {
-                spawn($expr)
-            } else if #[cfg(feature = "use-smol")] {
-                spawn($expr)
-                    .detach()
-            } else {
-                unreachable!()
-            }
-        }
-    };
-}
+/// Default maximum number of in-flight `CheckTx` requests (see
+/// [`Server::with_max_in_flight_check_tx`](self::Server::with_max_in_flight_check_tx))
+pub const DEFAULT_MAX_IN_FLIGHT_CHECK_TX: usize = 1024;
+
+/// Default maximum number of in-flight `CheckTx` requests for runtimes which spawn an OS thread for every task, e.g.,
+/// `StdRuntime` (see [`Runtime::is_thread_per_task`](crate::runtime::Runtime::is_thread_per_task))
+pub const DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX: usize = 64;
+
+/// Default maximum number of in-flight `Query` requests (see
+/// [`Server::with_max_in_flight_query`](self::Server::with_max_in_flight_query))
+pub const DEFAULT_MAX_IN_FLIGHT_QUERY: usize = 64;
+
+/// Default maximum number of in-flight `LoadSnapshotChunk` requests (see
+/// [`Server::with_max_in_flight_load_snapshot_chunk`](self::Server::with_max_in_flight_load_snapshot_chunk))
+pub const DEFAULT_MAX_IN_FLIGHT_LOAD_SNAPSHOT_CHUNK: usize = 4;
 
 /// ABCI Server
 pub struct Server<C, M, I, S>
@@ -75,65 +93,553 @@ where
     I: Info + 'static,
     S: Snapshot + 'static,
 {
-    /// Creates a new instance of [`Server`](self::Server)
+    /// Creates a new instance of [`Server`](self::Server) which uses [`DefaultRuntime`](crate::runtime::DefaultRuntime)
+    #[cfg(all(
+        feature = "async-api",
+        any(
+            feature = "use-async-std",
+            feature = "use-smol",
+            feature = "use-std",
+            feature = "use-tokio"
+        )
+    ))]
     pub fn new(consensus: C, mempool: M, info: I, snapshot: S) -> Self {
+        Self::with_runtime(
+            consensus,
+            mempool,
+            info,
+            snapshot,
+            DefaultRuntime::default(),
+        )
+    }
+
+    /// Creates a new instance of [`Server`](self::Server) which uses given [`Runtime`](crate::runtime::Runtime)
+    pub fn with_runtime<R>(consensus: C, mempool: M, info: I, snapshot: S, runtime: R) -> Self
+    where
+        R: Runtime,
+    {
         Self {
-            inner: Arc::new(Inner::new(consensus, mempool, info, snapshot)),
+            inner: Arc::new(Inner::new(
+                consensus,
+                mempool,
+                info,
+                snapshot,
+                Arc::new(runtime),
+            )),
         }
     }
 
+    /// Enables parallel execution of non-conflicting transactions in a block (default: `false`)
+    ///
+    /// When enabled, `DeliverTx` requests on consensus connection are buffered until a request other than `DeliverTx`
+    /// (e.g., `EndBlock` or `Flush`) is received. Buffered transactions are then grouped using their access sets (see
+    /// [`Consensus::deliver_tx_access`](super::Consensus::deliver_tx_access)) and executed in parallel, group by group.
+    /// Grouping is deterministic and responses are written in the order in which requests were received.
+    pub fn with_parallel_deliver_tx(mut self, enabled: bool) -> Self {
+        Arc::make_mut(&mut self.inner).parallel_deliver_tx = enabled;
+        self
+    }
+
+    /// Enables coordination of `CheckTx` execution on mempool connection with `Commit` on consensus connection (default:
+    /// `false`)
+    ///
+    /// When enabled:
+    ///
+    /// - `Commit` waits for running `CheckTx` requests to complete and no new `CheckTx` request is executed until the
+    ///   commit and [`Mempool::on_commit`](super::Mempool::on_commit) hook complete. So, the application can swap its
+    ///   `mempool_state` with the **latest committed state** atomically.
+    /// - After a commit, new `CheckTx` requests are executed only after all the rechecks received before them complete.
+    pub fn with_commit_barrier(mut self, enabled: bool) -> Self {
+        Arc::make_mut(&mut self.inner).commit_barrier =
+            enabled.then(|| Arc::new(CommitBarrier::default()));
+        self
+    }
+
+    /// Sets a soft deadline for given ABCI method (e.g., [`Method::Commit`](crate::Method::Commit))
+    ///
+    /// When a request takes longer than its soft deadline, a warning is logged with the details of request (connection
+    /// type, method, block height, transaction hash, etc.) and the current consensus state. Request execution is not
+    /// affected.
+    pub fn with_soft_deadline(mut self, method: Method, deadline: Duration) -> Self {
+        Arc::make_mut(&mut self.inner)
+            .watchdog
+            .set_soft_deadline(method, deadline);
+        self
+    }
+
+    /// Sets a hard deadline for given ABCI method (e.g., [`Method::Commit`](crate::Method::Commit)) and the action
+    /// to take when a request exceeds it (see [`DeadlineAction`](crate::DeadlineAction))
+    ///
+    /// Deadlines are measured from the time server starts executing a request. For `CheckTx` batches (see
+    /// [`with_check_tx_batch_limit`](Self::with_check_tx_batch_limit)), deadline of `check_tx` applies to the whole
+    /// batch.
+    pub fn with_hard_deadline(
+        mut self,
+        method: Method,
+        deadline: Duration,
+        action: DeadlineAction,
+    ) -> Self {
+        Arc::make_mut(&mut self.inner)
+            .watchdog
+            .set_hard_deadline(method, deadline, action);
+        self
+    }
+
+    /// Enables TLS on TCP connections with given configuration (default: disabled)
+    ///
+    /// TLS handshake of each accepted connection is performed in background and the connection is handled as usual
+    /// once the handshake succeeds. Connections which fail the handshake (e.g., because of a missing or invalid client
+    /// certificate when [`TlsConfig::with_client_ca`](crate::TlsConfig::with_client_ca) is used) or do not complete it
+    /// within [`TlsConfig::with_handshake_timeout`](crate::TlsConfig::with_handshake_timeout) are logged and dropped.
+    /// UDS connections are not affected.
+    #[cfg(feature = "tls")]
+    #[cfg_attr(feature = "doc", doc(cfg(feature = "tls")))]
+    pub fn with_tls(mut self, config: TlsConfig) -> Self {
+        Arc::make_mut(&mut self.inner).tls = Some(config);
+        self
+    }
+
+    /// Restricts the peers which can connect to server (default: all the peers are allowed, see
+    /// [`PeerAllowlist`](crate::PeerAllowlist))
+    pub fn with_peer_allowlist(mut self, allowlist: PeerAllowlist) -> Self {
+        Arc::make_mut(&mut self.inner).allowlist = allowlist;
+        self
+    }
+
+    /// Configures the lifecycle of socket files created for [`Address::Uds`](crate::Address::Uds), e.g., removal of
+    /// stale sockets and permissions (default: see [`UnixSocketConfig`](crate::UnixSocketConfig))
+    #[cfg(unix)]
+    #[cfg_attr(feature = "doc", doc(cfg(unix)))]
+    pub fn with_unix_socket_config(mut self, config: UnixSocketConfig) -> Self {
+        Arc::make_mut(&mut self.inner).unix_socket = config;
+        self
+    }
+
+    /// Sets the maximum number of `CheckTx` requests which can be in-flight at once (default:
+    /// [`DEFAULT_MAX_IN_FLIGHT_CHECK_TX`](self::DEFAULT_MAX_IN_FLIGHT_CHECK_TX), or
+    /// [`DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX`](self::DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX) for
+    /// runtimes which spawn an OS thread for every task)
+    ///
+    /// `CheckTx` requests on mempool connection are executed concurrently and their responses are written in the
+    /// order in which requests were received. Once the limit is reached, server stops reading new requests from the
+    /// mempool connection until responses of earlier requests are written (backpressure).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_max_in_flight_check_tx(mut self, limit: usize) -> Self {
+        Arc::make_mut(&mut self.inner).check_tx_limiter = Arc::new(InFlightLimiter::new(limit));
+        self
+    }
+
+    /// Returns the current state of in-flight `CheckTx` requests
+    pub fn check_tx_queue_metrics(&self) -> QueueMetrics {
+        self.inner.check_tx_limiter.metrics()
+    }
+
+    /// Sets the maximum number of `CheckTx` requests which are passed to
+    /// [`Mempool::check_tx_batch`](super::Mempool::check_tx_batch) at once (default: `1`, i.e., batching is disabled)
+    ///
+    /// When batching is enabled, all the `CheckTx` requests which are already buffered by mempool connection (up to
+    /// `limit`) are executed as a single batch. Server never waits for more requests to fill a batch and still writes
+    /// one response per request, in the order in which requests were received. Each request in a batch counts towards
+    /// the limit of in-flight `CheckTx` requests.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_check_tx_batch_limit(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "Limit of CheckTx batch size should be non-zero");
+
+        Arc::make_mut(&mut self.inner).check_tx_batch_limit = limit;
+        self
+    }
+
+    /// Enables caching of `CheckTx` responses with given capacity (number of transactions) so that rechecked
+    /// transactions whose state fingerprint (see
+    /// [`Mempool::recheck_fingerprint`](super::Mempool::recheck_fingerprint)) is unchanged are not validated again
+    ///
+    /// Cached responses of transactions which are not rechecked after a commit (i.e., which are not in mempool anymore)
+    /// are removed from cache.
+    pub fn with_recheck_cache(mut self, capacity: usize) -> Self {
+        Arc::make_mut(&mut self.inner).recheck_tracker =
+            Arc::new(RecheckTracker::with_cache(capacity));
+        self
+    }
+
+    /// Returns statistics of recheck rounds on mempool connection
+    ///
+    /// Each completed recheck round is also logged (at `INFO` level) with its number of rechecks, cache hits and
+    /// duration.
+    pub fn recheck_stats(&self) -> RecheckStats {
+        self.inner.recheck_tracker.stats()
+    }
+
+    /// Sets the maximum number of `Query` requests which can be in-flight at once (default:
+    /// [`DEFAULT_MAX_IN_FLIGHT_QUERY`](self::DEFAULT_MAX_IN_FLIGHT_QUERY))
+    ///
+    /// `Query` requests on info connection are executed concurrently while all the other requests (`Info`,
+    /// `SetOption`, etc.) are executed in order. Responses are written in the order in which requests were received.
+    /// Once the limit is reached, server stops reading new requests from the info connection until responses of
+    /// earlier requests are written (backpressure).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_max_in_flight_query(mut self, limit: usize) -> Self {
+        Arc::make_mut(&mut self.inner).query_limiter = Arc::new(InFlightLimiter::new(limit));
+        self
+    }
+
+    /// Returns the current state of in-flight `Query` requests
+    pub fn query_queue_metrics(&self) -> QueueMetrics {
+        self.inner.query_limiter.metrics()
+    }
+
+    /// Sets the maximum number of `LoadSnapshotChunk` requests which can be in-flight at once, i.e., the number of
+    /// snapshot chunks loaded in parallel (default:
+    /// [`DEFAULT_MAX_IN_FLIGHT_LOAD_SNAPSHOT_CHUNK`](self::DEFAULT_MAX_IN_FLIGHT_LOAD_SNAPSHOT_CHUNK))
+    ///
+    /// `LoadSnapshotChunk` requests on snapshot connection are executed concurrently while all the other requests are
+    /// executed in order. Responses are written in the order in which requests were received. Once the limit is
+    /// reached, server stops reading new requests from the snapshot connection until responses of earlier requests
+    /// are written (backpressure).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_max_in_flight_load_snapshot_chunk(mut self, limit: usize) -> Self {
+        Arc::make_mut(&mut self.inner).load_snapshot_chunk_limiter =
+            Arc::new(InFlightLimiter::new(limit));
+        self
+    }
+
+    /// Returns the current state of in-flight `LoadSnapshotChunk` requests
+    pub fn load_snapshot_chunk_queue_metrics(&self) -> QueueMetrics {
+        self.inner.load_snapshot_chunk_limiter.metrics()
+    }
+
+    /// Starts serving Prometheus metrics of ABCI traffic over HTTP at `GET /metrics` on given address
+    ///
+    /// Exposed metrics include number of requests, number of exceptions and request latency histograms (per connection
+    /// type and method), number of in-flight `CheckTx` requests, current block height and total encoded size of requests
+    /// and responses (per connection type, including length prefixes but not transport overhead, e.g., TLS). Metrics are recorded per server, so, multiple servers in the same process do not
+    /// share their counters. This function returns once the listener is bound. Connections to metrics endpoint are
+    /// handled in background using server's [`Runtime`](crate::runtime::Runtime).
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(feature = "doc", doc(cfg(feature = "metrics")))]
+    pub async fn serve_metrics(&self, addr: SocketAddr) -> Result<()> {
+        let listener = self.inner.runtime.bind_tcp(addr).await?;
+        info!(message = "Serving metrics at", %addr);
+
+        let runtime = self.inner.runtime.clone();
+        let metrics = self.inner.metrics.clone();
+        let check_tx_limiter = self.inner.check_tx_limiter.clone();
+
+        self.inner.runtime.spawn(Box::pin(async move {
+            crate::metrics::serve(runtime, listener, metrics, check_tx_limiter).await
+        }));
+
+        Ok(())
+    }
+
+    /// Returns the current health of server, i.e., established connections, consensus state, time of last commit and
+    /// the last protocol violation (if any)
+    ///
+    /// Consensus state is read from a snapshot which is updated by every consensus request, so, this does not wait for
+    /// the consensus request being executed (e.g., a long running commit) to complete.
+    pub fn health(&self) -> Health {
+        Health::new(&self.inner.connections, self.inner.state_snapshot.get())
+    }
+
+    /// Starts serving health and readiness of server over HTTP at `GET /health` and `GET /ready` on given address
+    ///
+    /// Both endpoints respond with a JSON report of [`health`](Self::health). `/health` responds with `503 Service
+    /// Unavailable` once a protocol violation is seen and `/ready` responds with `503 Service Unavailable` until
+    /// consensus, mempool and info connections are established. This function returns once the listener is bound.
+    /// Connections to health endpoint are handled in background using server's [`Runtime`](crate::runtime::Runtime).
+    pub async fn serve_health(&self, addr: SocketAddr) -> Result<()> {
+        let listener = self.inner.runtime.bind_tcp(addr).await?;
+        info!(message = "Serving health at", %addr);
+
+        let runtime = self.inner.runtime.clone();
+        let connections = self.inner.connections.clone();
+        let state_snapshot = self.inner.state_snapshot.clone();
+
+        let handler: HttpHandler = Arc::new(move |path| {
+            let check: fn(&Health) -> bool = match path {
+                "/health" => Health::is_healthy,
+                "/ready" => Health::is_ready,
+                _ => return Box::pin(ready(HttpResponse::not_found())),
+            };
+
+            let health = Health::new(&connections, state_snapshot.get());
+
+            Box::pin(ready(HttpResponse {
+                status: if check(&health) {
+                    "200 OK"
+                } else {
+                    "503 Service Unavailable"
+                },
+                content_type: "application/json",
+                body: health.to_json(),
+            }))
+        });
+
+        self.inner.runtime.spawn(Box::pin(async move {
+            serve_http(runtime, listener, handler).await
+        }));
+
+        Ok(())
+    }
+
     /// Starts ABCI server
     ///
     /// # Note
     ///
     /// This is an `async` function and returns a `Future`. So, you'll need an executor to drive the `Future` returned
-    /// from this function. `async-std` and `tokio` are two popular options.
+    /// from this function. The executor should be compatible with the [`Runtime`](crate::runtime::Runtime) of server.
     pub async fn run<T>(&self, addr: T) -> Result<()>
     where
         T: Into<Address>,
     {
-        let addr = addr.into();
+        self.run_all([addr]).await
+    }
 
-        match addr {
-            Address::Tcp(addr) => {
-                let listener = TcpListener::bind(addr).await?;
-                info!(message = "Started ABCI server at", %addr);
+    /// Starts ABCI server listening on all the given addresses at once (e.g., a UDS for local Tendermint node and a TCP
+    /// port for a debugging client)
+    ///
+    /// All the listeners are bound before any connection is accepted, so, this function fails without serving if any
+    /// of the addresses cannot be bound. Connections accepted on all the listeners share the same application
+    /// instances and consensus state. Returns once accepting connections fails on any of the listeners.
+    ///
+    /// On runtimes which spawn an OS thread for every task (e.g., `StdRuntime`), every listener is served on its own
+    /// thread. As a blocked `accept` cannot be cancelled, the other listeners keep accepting connections after this
+    /// function returns.
+    ///
+    /// # Note
+    ///
+    /// This is an `async` function and returns a `Future`. So, you'll need an executor to drive the `Future` returned
+    /// from this function. The executor should be compatible with the [`Runtime`](crate::runtime::Runtime) of server.
+    pub async fn run_all<A, T>(&self, addrs: A) -> Result<()>
+    where
+        A: IntoIterator<Item = T>,
+        T: Into<Address>,
+    {
+        self.bind_all(addrs).await?.serve().await
+    }
 
-                loop {
-                    let (stream, peer_addr) = listener.accept().await?;
-                    self.handle_connection(stream, peer_addr.to_string());
-                }
+    /// Binds ABCI server to given address without accepting any connection
+    ///
+    /// Returned [`BoundServer`](self::BoundServer) reports the actual address of listener (e.g., the port assigned when
+    /// binding to port `0`) and starts accepting connections once [`serve`](self::BoundServer::serve) or
+    /// [`spawn`](self::BoundServer::spawn) is called.
+    #[cfg(feature = "async-api")]
+    pub async fn bind<T>(&self, addr: T) -> Result<BoundServer<C, M, I, S>>
+    where
+        T: Into<Address>,
+    {
+        self.bind_all([addr]).await
+    }
+
+    /// Binds ABCI server to all the given addresses without accepting any connection (see [`bind`](Self::bind) and
+    /// [`run_all`](Self::run_all))
+    pub async fn bind_all<A, T>(&self, addrs: A) -> Result<BoundServer<C, M, I, S>>
+    where
+        A: IntoIterator<Item = T>,
+        T: Into<Address>,
+    {
+        let mut listeners = Vec::new();
+
+        for addr in addrs {
+            listeners.push(self.bind_listener(addr.into()).await?);
+        }
+
+        if listeners.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "No address to listen on",
+            ));
+        }
+
+        Ok(BoundServer {
+            server: Self {
+                inner: self.inner.clone(),
+            },
+            listeners,
+        })
+    }
+
+    /// Binds a listener for given address
+    async fn bind_listener(&self, addr: Address) -> Result<BoundListener> {
+        let check_peer = match addr {
+            Address::Tcp(_) | Address::Host(..) | Address::TcpListener(_) => {
+                PeerAllowlist::check_tcp
+            }
+            #[allow(unreachable_patterns)]
+            _ => PeerAllowlist::check_unix,
+        };
+
+        #[cfg(feature = "tls")]
+        let tls = match addr {
+            Address::Tcp(_) | Address::Host(..) | Address::TcpListener(_) => self
+                .inner
+                .tls
+                .as_ref()
+                .map(|config| (config.acceptor(), config.handshake_timeout())),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        };
+
+        #[cfg(unix)]
+        let mut socket_file = None;
+
+        let (listener, local_addr) = match addr {
+            Address::Tcp(addr) => {
+                let listener = self.inner.runtime.bind_tcp(addr).await?;
+                let addr = listener.local_addr().unwrap_or(addr);
+                (listener, Address::Tcp(addr))
+            }
+            Address::Host(host, port) => {
+                let (listener, addr) = self.inner.runtime.bind_tcp_host(host, port).await?;
+                let addr = listener.local_addr().unwrap_or(addr);
+                (listener, Address::Tcp(addr))
             }
             #[cfg(unix)]
             Address::Uds(path) => {
-                cfg_if::cfg_if! {
-                    if #[cfg(feature = "use-async-std")] {
-                        let listener = UnixListener::bind(&path).await?;
-                    } else if #[cfg(any(feature = "use-smol", feature = "use-tokio"))] {
-                        let listener = UnixListener::bind(&path)?;
-                    } else {
-                        unreachable!()
-                    }
-                }
-
-                info!(message = "Started ABCI server at", path = %path.display());
+                self.inner.unix_socket.before_bind(&path)?;
+                let listener = self.inner.runtime.bind_unix(path.clone()).await?;
+                socket_file = Some(self.inner.unix_socket.after_bind(&path)?);
+                (listener, Address::Uds(path))
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Address::AbstractUds(name) => {
+                #[cfg(target_os = "android")]
+                use std::os::android::net::SocketAddrExt;
+                #[cfg(target_os = "linux")]
+                use std::os::linux::net::SocketAddrExt;
 
-                loop {
-                    let (stream, peer_addr) = listener.accept().await?;
-                    self.handle_connection(stream, format!("{:?}", peer_addr));
-                }
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+                let listener = std::os::unix::net::UnixListener::bind_addr(&addr)?;
+                let listener = self.inner.runtime.wrap_unix(listener)?;
+                (listener, Address::AbstractUds(name))
+            }
+            Address::TcpListener(listener) => {
+                let addr = listener.local_addr()?;
+                let listener = self.inner.runtime.wrap_tcp(listener)?;
+                (listener, Address::Tcp(addr))
+            }
+            #[cfg(unix)]
+            Address::UnixListener(listener) => {
+                let local_addr = unix_local_addr(&listener.local_addr()?);
+                let listener = self.inner.runtime.wrap_unix(listener)?;
+                (listener, local_addr)
             }
             #[cfg(test)]
-            Address::Mock(mut listener) => {
-                while let Ok(stream) = listener.accept().await {
-                    self.handle_connection(stream, "test_peer".to_string());
-                }
+            Address::Mock(listener) => {
+                // Mock listeners do not have an address, so, a placeholder is reported instead
+                (
+                    Box::new(listener) as _,
+                    Address::Mock(MockListener::new().0),
+                )
+            }
+        };
+
+        info!(message = "Started ABCI server at", addr = %local_addr);
+
+        Ok(BoundListener {
+            listener,
+            local_addr,
+            check_peer,
+            #[cfg(feature = "tls")]
+            tls,
+            #[cfg(unix)]
+            _socket_file: socket_file,
+        })
+    }
+
+    /// Accepts and handles connections on given listener until accepting a connection fails
+    async fn accept_connections(&self, mut bound: BoundListener) -> Result<()> {
+        loop {
+            let connection = bound.listener.accept().await?;
+            let peer_addr = connection.peer_addr.clone();
 
-                Ok(())
+            if let Err(reason) = (bound.check_peer)(&self.inner.allowlist, &connection) {
+                warn!(message = "Rejected peer connection", %peer_addr, %reason);
+                continue;
             }
+
+            #[cfg(feature = "tls")]
+            if let Some((ref tls_acceptor, handshake_timeout)) = bound.tls {
+                self.handle_tls_connection(
+                    tls_acceptor.clone(),
+                    handshake_timeout,
+                    connection,
+                    peer_addr,
+                );
+                continue;
+            }
+
+            self.handle_connection(connection, peer_addr);
         }
     }
 
+    #[cfg(feature = "tls")]
+    #[instrument(skip(self, tls_acceptor, connection))]
+    fn handle_tls_connection(
+        &self,
+        tls_acceptor: TlsAcceptor,
+        handshake_timeout: Duration,
+        mut connection: Connection,
+        peer_addr: String,
+    ) {
+        info!("New peer connection");
+
+        let inner = self.inner.clone();
+        let sleep = self.inner.runtime.sleep(handshake_timeout);
+
+        self.inner.runtime.spawn(Box::pin(async move {
+            // Blocking sockets cannot be interrupted by the timeout below, so, their reads time out instead
+            let read_timeout_setter = connection.read_timeout_setter.take();
+
+            if let Some(ref set_read_timeout) = read_timeout_setter {
+                if let Err(err) = set_read_timeout(Some(handshake_timeout)) {
+                    error!(message = "Unable to set timeout of TLS handshake", %peer_addr, %err);
+                    return;
+                }
+            }
+
+            let handshake = or(async { Some(tls_acceptor.accept(connection).await) }, async {
+                sleep.await;
+                None
+            });
+
+            match handshake.await {
+                Some(Ok(stream)) => {
+                    debug!("TLS handshake completed");
+
+                    if let Some(ref set_read_timeout) = read_timeout_setter {
+                        if let Err(err) = set_read_timeout(None) {
+                            error!(message = "Unable to reset read timeout after TLS handshake", %peer_addr, %err);
+                            return;
+                        }
+                    }
+
+                    let (stream_reader, stream_writer) = get_stream_pair(stream);
+                    inner
+                        .handle_connection(stream_reader, stream_writer, peer_addr)
+                        .await
+                }
+                Some(Err(err)) => error!(message = "TLS handshake failed", %peer_addr, %err),
+                None => warn!(
+                    message = "TLS handshake timed out",
+                    %peer_addr,
+                    timeout = ?handshake_timeout
+                ),
+            }
+        }));
+    }
+
     #[instrument(skip(self, stream))]
     pub(crate) fn handle_connection<D>(&self, stream: D, peer_addr: String)
     where
@@ -144,14 +650,145 @@ where
         let inner = self.inner.clone();
         let (stream_reader, stream_writer) = get_stream_pair(stream);
 
-        spawn!(async move {
+        self.inner.runtime.spawn(Box::pin(async move {
             inner
                 .handle_connection(stream_reader, stream_writer, peer_addr)
                 .await
-        });
+        }));
+    }
+}
+
+/// ABCI server bound to its listeners (see [`Server::bind`](self::Server::bind))
+pub struct BoundServer<C, M, I, S>
+where
+    C: Consensus + 'static,
+    M: Mempool + 'static,
+    I: Info + 'static,
+    S: Snapshot + 'static,
+{
+    server: Server<C, M, I, S>,
+    listeners: Vec<BoundListener>,
+}
+
+impl<C, M, I, S> BoundServer<C, M, I, S>
+where
+    C: Consensus + 'static,
+    M: Mempool + 'static,
+    I: Info + 'static,
+    S: Snapshot + 'static,
+{
+    /// Returns the local address of server (the first one, when bound to multiple addresses)
+    ///
+    /// For TCP listeners, this is the actual address of listener (e.g., with the port assigned when binding to port
+    /// `0`), as long as the [`Runtime`](crate::runtime::Runtime) of server reports it (see
+    /// [`Listener::local_addr`](crate::runtime::Listener::local_addr)).
+    pub fn local_addr(&self) -> &Address {
+        &self.listeners[0].local_addr
+    }
+
+    /// Returns the local addresses of server in the order in which they were given to
+    /// [`Server::bind_all`](self::Server::bind_all)
+    pub fn local_addrs(&self) -> impl Iterator<Item = &Address> {
+        self.listeners.iter().map(|listener| &listener.local_addr)
+    }
+
+    /// Starts accepting connections on all the listeners (see [`Server::run_all`](self::Server::run_all))
+    ///
+    /// # Note
+    ///
+    /// This is an `async` function and returns a `Future`. So, you'll need an executor to drive the `Future` returned
+    /// from this function. The executor should be compatible with the [`Runtime`](crate::runtime::Runtime) of server.
+    pub async fn serve(self) -> Result<()> {
+        let Self { server, listeners } = self;
+
+        // Accepting a connection blocks current thread on runtimes which spawn an OS thread for every task. So, the
+        // listeners are served on their own tasks so that a blocked listener does not starve the others.
+        if server.inner.runtime.is_thread_per_task() && listeners.len() > 1 {
+            let runtime = server.inner.runtime.clone();
+
+            let mut handles: Vec<_> = listeners
+                .into_iter()
+                .map(|listener| {
+                    let server = Server {
+                        inner: server.inner.clone(),
+                    };
+
+                    runtime
+                        .spawn_with_handle(async move { server.accept_connections(listener).await })
+                })
+                .collect();
+
+            return poll_fn(|cx| {
+                for handle in handles.iter_mut() {
+                    if let Poll::Ready(result) = Pin::new(handle).poll(cx) {
+                        return Poll::Ready(result.unwrap_or_else(|| {
+                            Err(Error::other("Accept loop stopped without a result"))
+                        }));
+                    }
+                }
+
+                Poll::Pending
+            })
+            .await;
+        }
+
+        let mut accept_loops: Vec<BoxFuture<'_, Result<()>>> = listeners
+            .into_iter()
+            .map(|listener| Box::pin(server.accept_connections(listener)) as BoxFuture<'_, _>)
+            .collect();
+
+        poll_fn(|cx| {
+            for accept_loop in accept_loops.iter_mut() {
+                if let Poll::Ready(result) = accept_loop.as_mut().poll(cx) {
+                    return Poll::Ready(result);
+                }
+            }
+
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Starts accepting connections on all the listeners in background on the [`Runtime`](crate::runtime::Runtime) of
+    /// server. Returned handle resolves to the result of [`serve`](Self::serve).
+    #[cfg(feature = "async-api")]
+    pub fn spawn(self) -> JoinHandle<Result<()>> {
+        let runtime = self.server.inner.runtime.clone();
+        runtime.spawn_with_handle(self.serve())
     }
 }
 
+/// A listener bound by [`Server`](self::Server) along with the checks applied to its connections
+struct BoundListener {
+    listener: Box<dyn Listener>,
+    local_addr: Address,
+    check_peer: fn(&PeerAllowlist, &Connection) -> std::result::Result<(), String>,
+    /// TLS acceptor and timeout of TLS handshake
+    #[cfg(feature = "tls")]
+    tls: Option<(TlsAcceptor, Duration)>,
+    /// Removes socket file (if enabled) when dropped
+    #[cfg(unix)]
+    _socket_file: Option<SocketFileGuard>,
+}
+
+/// Returns the address of a bound UDS listener
+#[cfg(unix)]
+fn unix_local_addr(addr: &std::os::unix::net::SocketAddr) -> Address {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        #[cfg(target_os = "android")]
+        use std::os::android::net::SocketAddrExt;
+        #[cfg(target_os = "linux")]
+        use std::os::linux::net::SocketAddrExt;
+
+        if let Some(name) = addr.as_abstract_name() {
+            return Address::AbstractUds(String::from_utf8_lossy(name).into_owned());
+        }
+    }
+
+    Address::Uds(addr.as_pathname().map(Into::into).unwrap_or_default())
+}
+
 /// Inner type that contains all the trait implementations
 struct Inner<C, M, I, S>
 where
@@ -165,6 +802,59 @@ where
     info: Arc<I>,
     snapshot: Arc<S>,
     validator: Arc<Mutex<ConsensusStateValidator>>,
+    /// Snapshot of consensus state which can be read while validator is locked (e.g., during a long running commit)
+    state_snapshot: Arc<StateSnapshotCell>,
+    runtime: Arc<dyn Runtime>,
+    parallel_deliver_tx: bool,
+    commit_barrier: Option<Arc<CommitBarrier>>,
+    check_tx_limiter: Arc<InFlightLimiter>,
+    check_tx_batch_limit: usize,
+    recheck_tracker: Arc<RecheckTracker>,
+    query_limiter: Arc<InFlightLimiter>,
+    load_snapshot_chunk_limiter: Arc<InFlightLimiter>,
+    watchdog: Watchdog,
+    metrics: Arc<Metrics>,
+    connections: Arc<ConnectionTracker>,
+    allowlist: PeerAllowlist,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsConfig>,
+    #[cfg(unix)]
+    unix_socket: UnixSocketConfig,
+}
+
+impl<C, M, I, S> Clone for Inner<C, M, I, S>
+where
+    C: Consensus + 'static,
+    M: Mempool + 'static,
+    I: Info + 'static,
+    S: Snapshot + 'static,
+{
+    fn clone(&self) -> Self {
+        Self {
+            consensus: self.consensus.clone(),
+            mempool: self.mempool.clone(),
+            info: self.info.clone(),
+            snapshot: self.snapshot.clone(),
+            validator: self.validator.clone(),
+            state_snapshot: self.state_snapshot.clone(),
+            runtime: self.runtime.clone(),
+            parallel_deliver_tx: self.parallel_deliver_tx,
+            commit_barrier: self.commit_barrier.clone(),
+            check_tx_limiter: self.check_tx_limiter.clone(),
+            check_tx_batch_limit: self.check_tx_batch_limit,
+            recheck_tracker: self.recheck_tracker.clone(),
+            query_limiter: self.query_limiter.clone(),
+            load_snapshot_chunk_limiter: self.load_snapshot_chunk_limiter.clone(),
+            watchdog: self.watchdog.clone(),
+            metrics: self.metrics.clone(),
+            connections: self.connections.clone(),
+            allowlist: self.allowlist.clone(),
+            #[cfg(feature = "tls")]
+            tls: self.tls.clone(),
+            #[cfg(unix)]
+            unix_socket: self.unix_socket.clone(),
+        }
+    }
 }
 
 impl<C, M, I, S> Inner<C, M, I, S>
@@ -174,13 +864,41 @@ where
     I: Info + 'static,
     S: Snapshot + 'static,
 {
-    pub fn new(consensus: C, mempool: M, info: I, snapshot: S) -> Self {
+    pub fn new(consensus: C, mempool: M, info: I, snapshot: S, runtime: Arc<dyn Runtime>) -> Self {
+        let validator = ConsensusStateValidator::default();
+        let state_snapshot = validator.snapshot();
+        let watchdog = Watchdog::new(runtime.clone(), state_snapshot.clone());
+        let max_in_flight_check_tx = if runtime.is_thread_per_task() {
+            DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX
+        } else {
+            DEFAULT_MAX_IN_FLIGHT_CHECK_TX
+        };
+
         Self {
             consensus: Arc::new(consensus),
             mempool: Arc::new(mempool),
             info: Arc::new(info),
             snapshot: Arc::new(snapshot),
-            validator: Default::default(),
+            validator: Arc::new(Mutex::new(validator)),
+            state_snapshot,
+            watchdog,
+            metrics: Default::default(),
+            runtime,
+            parallel_deliver_tx: false,
+            commit_barrier: None,
+            check_tx_limiter: Arc::new(InFlightLimiter::new(max_in_flight_check_tx)),
+            check_tx_batch_limit: 1,
+            recheck_tracker: Default::default(),
+            query_limiter: Arc::new(InFlightLimiter::new(DEFAULT_MAX_IN_FLIGHT_QUERY)),
+            load_snapshot_chunk_limiter: Arc::new(InFlightLimiter::new(
+                DEFAULT_MAX_IN_FLIGHT_LOAD_SNAPSHOT_CHUNK,
+            )),
+            connections: Default::default(),
+            allowlist: Default::default(),
+            #[cfg(feature = "tls")]
+            tls: None,
+            #[cfg(unix)]
+            unix_socket: Default::default(),
         }
     }
 
@@ -197,27 +915,62 @@ where
         info!(message = "In handle_connection");
 
         loop {
-            match stream_reader.read().await {
+            match stream_reader.read::<Request>().await {
                 Ok(request) => match request {
                     Some(request) => {
-                        let (response, connection_type) = self.process(request).await;
+                        let connection_type = request
+                            .value
+                            .as_ref()
+                            .map(ConnectionType::from)
+                            .unwrap_or_default();
 
-                        if let Err(err) = stream_writer.write(response).await {
-                            error!(message = "Error while writing to stream", %err);
+                        let guard = match self.connect(connection_type) {
+                            Some(guard) => guard,
+                            None => {
+                                warn!(
+                                    message = "Rejected peer connection",
+                                    reason = "Connection of same type is already established",
+                                    connection_type = connection_type.as_str()
+                                );
+                                break;
+                            }
+                        };
+
+                        // Requests which do not belong to any connection type (e.g., `Echo` and `Flush`) are
+                        // answered before connection type is known
+                        if matches!(connection_type, ConnectionType::Unknown) {
+                            let response = self.process(request);
+
+                            if let Err(err) = stream_writer.write(response).await {
+                                error!(message = "Error while writing to stream", %err);
+                            }
+
+                            continue;
                         }
 
-                        if !matches!(connection_type, ConnectionType::Unknown) {
-                            self.spawn_connection(
-                                stream_reader,
-                                stream_writer,
-                                peer_addr,
-                                connection_type,
-                            );
+                        // First request of connection is handled by connection task so that it goes through the same
+                        // checks (e.g., commit barrier and in-flight limits) as the following requests
+                        if let Err(err) = stream_reader.unread(request) {
+                            error!(message = "Error while buffering first request of connection", %err);
                             break;
                         }
+
+                        stream_reader.set_guard(guard);
+
+                        self.spawn_connection(
+                            stream_reader,
+                            stream_writer,
+                            peer_addr,
+                            connection_type,
+                        );
+                        break;
                     }
                     None => debug!(message = "Received empty request"),
                 },
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                    info!("Peer connection closed");
+                    break;
+                }
                 Err(err) => {
                     error!(message = "Error while receiving ABCI request from socket", %err);
                     break;
@@ -226,6 +979,25 @@ where
         }
     }
 
+    /// Records a new connection of given type after applying the policy for duplicate connections (see
+    /// [`DuplicateConnectionPolicy`]). Returns `None` if the connection is rejected.
+    fn connect(&self, connection_type: ConnectionType) -> Option<ConnectionGuard> {
+        match self.allowlist.duplicate_connection_policy(connection_type) {
+            DuplicateConnectionPolicy::Allow => Some(self.connections.connect(connection_type)),
+            DuplicateConnectionPolicy::Reject => self.connections.try_connect(connection_type, 1),
+            DuplicateConnectionPolicy::Replace => {
+                if self.connections.count(connection_type) > 0 {
+                    warn!(
+                        message = "Replacing established connection with new connection",
+                        connection_type = connection_type.as_str()
+                    );
+                }
+
+                Some(self.connections.replace(connection_type))
+            }
+        }
+    }
+
     #[instrument(skip(self, stream_reader, stream_writer))]
     fn spawn_connection<R, W>(
         &self,
@@ -244,73 +1016,63 @@ where
                 "Connection type cannot be unknown when spawning a task for a connection type"
             ),
             ConnectionType::Consensus => spawn_consensus_task(
+                self.runtime.clone(),
                 stream_reader,
                 stream_writer,
                 peer_addr,
                 self.consensus.clone(),
                 self.validator.clone(),
+                self.parallel_deliver_tx,
+                self.mempool.clone(),
+                self.commit_barrier.clone(),
+                self.watchdog.clone(),
+                self.metrics.clone(),
             ),
             ConnectionType::Mempool => spawn_mempool_task(
+                self.runtime.clone(),
                 stream_reader,
                 stream_writer,
                 peer_addr,
                 self.mempool.clone(),
+                self.check_tx_limiter.clone(),
+                self.check_tx_batch_limit,
+                self.commit_barrier.clone(),
+                self.recheck_tracker.clone(),
+                self.watchdog.clone(),
+                self.metrics.clone(),
             ),
             ConnectionType::Info => spawn_info_task(
+                self.runtime.clone(),
                 stream_reader,
                 stream_writer,
                 peer_addr,
                 self.info.clone(),
                 self.validator.clone(),
+                self.query_limiter.clone(),
+                self.watchdog.clone(),
+                self.metrics.clone(),
             ),
             ConnectionType::Snapshot => spawn_snapshot_task(
+                self.runtime.clone(),
                 stream_reader,
                 stream_writer,
                 peer_addr,
                 self.snapshot.clone(),
+                self.load_snapshot_chunk_limiter.clone(),
+                self.watchdog.clone(),
+                self.metrics.clone(),
             ),
         }
     }
 
-    #[instrument(skip(self))]
-    async fn process(&self, request: Request) -> (Response, ConnectionType) {
+    #[instrument(skip_all)]
+    fn process(&self, request: Request) -> Response {
         match request.value {
             None => {
                 debug!(message = "Received empty value in request", ?request);
-
-                (Response::default(), ConnectionType::default())
-            }
-            Some(request_value) => {
-                let connection_type = ConnectionType::from(&request_value);
-
-                let response = match connection_type {
-                    ConnectionType::Unknown => handle_unknown_request(request_value),
-                    ConnectionType::Consensus => {
-                        handle_consensus_request(
-                            self.consensus.as_ref(),
-                            self.validator.clone(),
-                            request_value,
-                        )
-                        .await
-                    }
-                    ConnectionType::Mempool => {
-                        handle_mempool_request(self.mempool.as_ref(), request_value).await
-                    }
-                    ConnectionType::Info => {
-                        handle_info_request(
-                            self.info.as_ref(),
-                            self.validator.clone(),
-                            request_value,
-                        )
-                        .await
-                    }
-                    ConnectionType::Snapshot => {
-                        handle_snapshot_request(self.snapshot.as_ref(), request_value).await
-                    }
-                };
-
-                (response, connection_type)
+                Response::default()
             }
+            Some(request_value) => handle_unknown_request(&self.metrics, request_value),
         }
     }
 }
@@ -1,24 +1,25 @@
 use std::sync::Arc;
 
-#[cfg(feature = "use-async-std")]
-use async_std::sync::Mutex;
-#[cfg(feature = "use-smol")]
-use smol::lock::Mutex;
+use async_lock::Mutex;
 use tendermint_proto::abci::{
     request::Value as RequestValue, response::Value as ResponseValue, Response, ResponseException,
 };
-#[cfg(feature = "use-tokio")]
-use tokio::sync::Mutex;
-use tracing::{debug, instrument};
+use tracing::{debug, error, field::Empty, info_span, instrument, Instrument, Span};
 
 use crate::{
     async_api::{Consensus, Info, Mempool, Snapshot},
+    metrics::{Metrics, RequestTimer},
+    runtime::Runtime,
     state::ConsensusStateValidator,
-    types::ResponseEcho,
+    types::{CheckTxType, RequestCheckTx, RequestDeliverTx, ResponseCheckTx, ResponseEcho},
+    utils::{record_response, record_tx_hash, schedule, ConnectionType, Method, RecheckTracker},
+    watchdog::Watchdog,
 };
 
-#[instrument]
-pub fn handle_unknown_request(request_value: RequestValue) -> Response {
+#[instrument(skip_all, fields(connection = "unknown", method = Method::from(&request_value).as_str()))]
+pub fn handle_unknown_request(metrics: &Metrics, request_value: RequestValue) -> Response {
+    let timer = RequestTimer::start(metrics, ConnectionType::Unknown, &request_value);
+
     let response_value = match request_value {
         RequestValue::Echo(request) => ResponseValue::Echo(ResponseEcho {
             message: request.message,
@@ -27,154 +28,563 @@ pub fn handle_unknown_request(request_value: RequestValue) -> Response {
         _ => unreachable!("handle_unknown_request cannot handle known requests"),
     };
 
-    Response {
+    let response = Response {
         value: Some(response_value),
-    }
+    };
+
+    timer.finish(&response);
+
+    response
 }
 
-#[instrument(skip(consensus))]
+#[instrument(
+    skip_all,
+    fields(
+        connection = "consensus",
+        method = Method::from(&request_value).as_str(),
+        block_height,
+        tx_index,
+        tx_hash,
+        code
+    )
+)]
 pub async fn handle_consensus_request<C: Consensus>(
     consensus: &C,
     validator: Arc<Mutex<ConsensusStateValidator>>,
+    watchdog: &Watchdog,
+    metrics: &Metrics,
     request_value: RequestValue,
 ) -> Response {
-    let response_value = match request_value {
-        RequestValue::Echo(request) => ResponseValue::Echo(consensus.echo(request).await),
-        RequestValue::Flush(request) => ResponseValue::Flush(consensus.flush(request).await),
-        RequestValue::InitChain(request) => match validator.lock().await.on_init_chain_request() {
-            Ok(_) => ResponseValue::InitChain(consensus.init_chain(request).await),
-            Err(error) => ResponseValue::Exception(ResponseException { error }),
-        },
-        RequestValue::BeginBlock(request) => {
-            match validator.lock().await.on_begin_block_request(&request) {
-                Ok(_) => ResponseValue::BeginBlock(consensus.begin_block(request).await),
-                Err(error) => ResponseValue::Exception(ResponseException { error }),
-            }
-        }
-        RequestValue::DeliverTx(request) => match validator.lock().await.on_deliver_tx_request() {
-            Ok(_) => ResponseValue::DeliverTx(consensus.deliver_tx(request).await),
-            Err(error) => ResponseValue::Exception(ResponseException { error }),
-        },
-        RequestValue::EndBlock(request) => {
-            match validator.lock().await.on_end_block_request(&request) {
-                Ok(_) => ResponseValue::EndBlock(consensus.end_block(request).await),
-                Err(error) => ResponseValue::Exception(ResponseException { error }),
-            }
-        }
-        RequestValue::Commit(request) => {
-            let mut validator_locked = validator.lock().await;
+    let timer = RequestTimer::start(metrics, ConnectionType::Consensus, &request_value);
+    let span = Span::current();
 
-            match validator_locked.on_commit_request() {
-                Ok(_) => {
-                    let response = consensus.commit(request).await;
+    let method = Method::from(&request_value);
 
-                    match validator_locked.on_commit_response(&response) {
-                        Ok(_) => ResponseValue::Commit(response),
+    let response_value = watchdog
+        .watch(method, async {
+            match request_value {
+                RequestValue::Echo(request) => ResponseValue::Echo(consensus.echo(request).await),
+                RequestValue::Flush(request) => {
+                    ResponseValue::Flush(consensus.flush(request).await)
+                }
+                RequestValue::InitChain(request) => {
+                    match validator.lock().await.on_init_chain_request() {
+                        Ok(_) => ResponseValue::InitChain(consensus.init_chain(request).await),
                         Err(error) => ResponseValue::Exception(ResponseException { error }),
                     }
                 }
-                Err(error) => ResponseValue::Exception(ResponseException { error }),
+                RequestValue::BeginBlock(request) => {
+                    match validator.lock().await.on_begin_block_request(&request) {
+                        Ok(_) => {
+                            if let Some(ref header) = request.header {
+                                span.record("block_height", header.height);
+                                metrics.set_block_height(header.height);
+                            }
+
+                            ResponseValue::BeginBlock(consensus.begin_block(request).await)
+                        }
+                        Err(error) => ResponseValue::Exception(ResponseException { error }),
+                    }
+                }
+                RequestValue::DeliverTx(request) => {
+                    let mut validator_locked = validator.lock().await;
+                    record_tx_hash(&span, &request.tx);
+
+                    match validator_locked.on_deliver_tx_request() {
+                        Ok(tx_index) => {
+                            if let Some(block_height) = validator_locked.block_height() {
+                                span.record("block_height", block_height);
+                            }
+                            span.record("tx_index", tx_index);
+
+                            ResponseValue::DeliverTx(consensus.deliver_tx(request).await)
+                        }
+                        Err(error) => ResponseValue::Exception(ResponseException { error }),
+                    }
+                }
+                RequestValue::EndBlock(request) => {
+                    span.record("block_height", request.height);
+
+                    match validator.lock().await.on_end_block_request(&request) {
+                        Ok(_) => ResponseValue::EndBlock(consensus.end_block(request).await),
+                        Err(error) => ResponseValue::Exception(ResponseException { error }),
+                    }
+                }
+                RequestValue::Commit(request) => {
+                    let mut validator_locked = validator.lock().await;
+
+                    if let Some(block_height) = validator_locked.block_height() {
+                        span.record("block_height", block_height);
+                    }
+
+                    match validator_locked.on_commit_request() {
+                        Ok(_) => {
+                            let response = consensus.commit(request).await;
+
+                            match validator_locked.on_commit_response(&response) {
+                                Ok(_) => ResponseValue::Commit(response),
+                                Err(error) => ResponseValue::Exception(ResponseException { error }),
+                            }
+                        }
+                        Err(error) => ResponseValue::Exception(ResponseException { error }),
+                    }
+                }
+                _ => ResponseValue::Exception(ResponseException {
+                    error: "Non-consensus request on consensus connection".to_string(),
+                }),
             }
-        }
-        _ => ResponseValue::Exception(ResponseException {
-            error: "Non-consensus request on consensus connection".to_string(),
-        }),
-    };
+        })
+        .await
+        .unwrap_or_else(|error| ResponseValue::Exception(ResponseException { error }));
 
     let response = Response {
         value: Some(response_value),
     };
 
-    debug!(message = "Sending response", ?response);
+    timer.finish(&response);
+    record_response(&span, &response);
+
+    debug!(message = "Sending response");
 
     response
 }
 
-#[instrument(skip(mempool))]
+/// Executes a batch of `DeliverTx` requests, running non-conflicting transactions (see
+/// [`Consensus::deliver_tx_access`](crate::async_api::Consensus::deliver_tx_access)) in parallel. Responses are
+/// returned in the order of requests.
+#[instrument(skip_all, fields(connection = "consensus", batch_size = deliver_tx_requests.len()))]
+pub async fn handle_deliver_tx_batch<C: Consensus + 'static>(
+    runtime: &dyn Runtime,
+    consensus: &Arc<C>,
+    validator: Arc<Mutex<ConsensusStateValidator>>,
+    watchdog: &Watchdog,
+    metrics: &Metrics,
+    deliver_tx_requests: Vec<RequestDeliverTx>,
+) -> Vec<Response> {
+    let timers: Vec<_> = deliver_tx_requests
+        .iter()
+        .map(|request| RequestTimer::start_deliver_tx(metrics, request))
+        .collect();
+
+    let mut responses: Vec<Option<ResponseValue>> = Vec::with_capacity(deliver_tx_requests.len());
+    let mut spans = Vec::with_capacity(deliver_tx_requests.len());
+    let mut requests = Vec::with_capacity(deliver_tx_requests.len());
+
+    {
+        let mut validator_locked = validator.lock().await;
+        let block_height = validator_locked.block_height();
+
+        for request in deliver_tx_requests {
+            let span = info_span!(
+                "handle_deliver_tx",
+                connection = "consensus",
+                method = "deliver_tx",
+                block_height,
+                tx_index = Empty,
+                tx_hash = Empty,
+                code = Empty
+            );
+            record_tx_hash(&span, &request.tx);
+
+            match validator_locked.on_deliver_tx_request() {
+                Ok(tx_index) => {
+                    span.record("tx_index", tx_index);
+                    requests.push((responses.len(), Some(request)));
+                    responses.push(None);
+                }
+                Err(error) => {
+                    responses.push(Some(ResponseValue::Exception(ResponseException { error })))
+                }
+            }
+
+            spans.push(span);
+        }
+    }
+
+    let accesses: Vec<_> = requests
+        .iter()
+        .map(|(_, request)| consensus.deliver_tx_access(request.as_ref().unwrap()))
+        .collect();
+
+    for wave in schedule(&accesses) {
+        debug!(message = "Executing DeliverTx wave", wave_size = wave.len());
+
+        let handles: Vec<_> = wave
+            .into_iter()
+            .map(|index| {
+                let (position, request) = &mut requests[index];
+                let request = request.take().unwrap();
+                let consensus = consensus.clone();
+                let watchdog = watchdog.clone();
+
+                let handle = runtime.spawn_with_handle(
+                    async move {
+                        watchdog
+                            .watch(Method::DeliverTx, consensus.deliver_tx(request))
+                            .await
+                    }
+                    .instrument(spans[*position].clone()),
+                );
+
+                (*position, handle)
+            })
+            .collect();
+
+        for (position, handle) in handles {
+            responses[position] = Some(match handle.await {
+                Some(Ok(response)) => ResponseValue::DeliverTx(response),
+                Some(Err(error)) => ResponseValue::Exception(ResponseException { error }),
+                None => ResponseValue::Exception(ResponseException {
+                    error: "DeliverTx execution not completed".to_string(),
+                }),
+            });
+        }
+    }
+
+    let responses: Vec<Response> = responses
+        .into_iter()
+        .map(|value| Response { value })
+        .collect();
+
+    for ((timer, span), response) in timers.into_iter().zip(spans.iter()).zip(responses.iter()) {
+        timer.finish(response);
+        record_response(span, response);
+    }
+
+    debug!(message = "Sending responses");
+
+    responses
+}
+
+#[instrument(
+    skip_all,
+    fields(
+        connection = "mempool",
+        method = Method::from(&request_value).as_str(),
+        tx_hash,
+        code
+    )
+)]
 pub async fn handle_mempool_request<M: Mempool>(
     mempool: &M,
+    recheck_tracker: &RecheckTracker,
+    watchdog: &Watchdog,
+    metrics: &Metrics,
     request_value: RequestValue,
 ) -> Response {
-    let response_value = match request_value {
-        RequestValue::Echo(request) => ResponseValue::Echo(mempool.echo(request).await),
-        RequestValue::Flush(request) => ResponseValue::Flush(mempool.flush(request).await),
-        RequestValue::CheckTx(request) => ResponseValue::CheckTx(mempool.check_tx(request).await),
-        _ => ResponseValue::Exception(ResponseException {
-            error: "Non-mempool request on mempool connection".to_string(),
-        }),
-    };
+    let timer = RequestTimer::start(metrics, ConnectionType::Mempool, &request_value);
+    let span = Span::current();
+
+    let method = Method::from(&request_value);
+
+    let response_value = watchdog
+        .watch(method, async {
+            match request_value {
+                RequestValue::Echo(request) => ResponseValue::Echo(mempool.echo(request).await),
+                RequestValue::Flush(request) => ResponseValue::Flush(mempool.flush(request).await),
+                RequestValue::CheckTx(request) => {
+                    record_tx_hash(&span, &request.tx);
+                    ResponseValue::CheckTx(check_tx(mempool, recheck_tracker, request).await)
+                }
+                _ => ResponseValue::Exception(ResponseException {
+                    error: "Non-mempool request on mempool connection".to_string(),
+                }),
+            }
+        })
+        .await
+        .unwrap_or_else(|error| ResponseValue::Exception(ResponseException { error }));
 
     let response = Response {
         value: Some(response_value),
     };
 
-    debug!(message = "Sending response", ?response);
+    timer.finish(&response);
+    record_response(&span, &response);
+
+    debug!(message = "Sending response");
+
+    response
+}
+
+#[instrument(skip_all, fields(connection = "mempool", batch_size = check_tx_requests.len()))]
+pub async fn handle_check_tx_batch<M: Mempool>(
+    mempool: &M,
+    recheck_tracker: &RecheckTracker,
+    watchdog: &Watchdog,
+    metrics: &Metrics,
+    check_tx_requests: Vec<RequestCheckTx>,
+) -> Vec<Response> {
+    let batch_size = check_tx_requests.len();
+    let timers: Vec<_> = check_tx_requests
+        .iter()
+        .map(|request| RequestTimer::start_check_tx(metrics, request))
+        .collect();
+    let spans: Vec<_> = check_tx_requests
+        .iter()
+        .map(|request| {
+            let span = info_span!(
+                "handle_check_tx",
+                connection = "mempool",
+                method = "check_tx",
+                tx_hash = Empty,
+                code = Empty
+            );
+            record_tx_hash(&span, &request.tx);
+            span
+        })
+        .collect();
+    let mut responses: Vec<Option<ResponseValue>> = vec![None; batch_size];
+
+    // Positions (and cache keys) of requests which are not answered from recheck cache
+    let mut pending = Vec::with_capacity(batch_size);
+    let mut pending_requests = Vec::with_capacity(batch_size);
+
+    for (position, request) in check_tx_requests.into_iter().enumerate() {
+        let recheck = request.r#type() == CheckTxType::Recheck;
+        let fingerprint = recheck_fingerprint(mempool, recheck_tracker, &request).await;
+
+        if let (true, Some(ref fingerprint)) = (recheck, &fingerprint) {
+            if let Some(response) = recheck_tracker.cached(&request.tx, fingerprint) {
+                mempool.on_cached_recheck(&request, &response).await;
+                recheck_tracker.on_recheck_complete(true);
+                responses[position] = Some(ResponseValue::CheckTx(response));
+                continue;
+            }
+        }
+
+        pending.push((
+            position,
+            recheck,
+            fingerprint.map(|fingerprint| (request.tx.clone(), fingerprint)),
+        ));
+        pending_requests.push(request);
+    }
+
+    // Set when `check_tx_batch` exceeds its hard deadline
+    let mut deadline_error = None;
+
+    let mut pending_responses = if pending_requests.is_empty() {
+        Vec::new()
+    } else {
+        match watchdog
+            .watch(Method::CheckTx, mempool.check_tx_batch(pending_requests))
+            .await
+        {
+            Ok(responses) => responses,
+            Err(error) => {
+                deadline_error = Some(error);
+                Vec::new()
+            }
+        }
+    };
+
+    if deadline_error.is_none() && pending_responses.len() != pending.len() {
+        error!(
+            message = "Number of responses returned by `check_tx_batch` does not match number of requests",
+            requests = pending.len(),
+            responses = pending_responses.len()
+        );
+
+        pending_responses.truncate(pending.len());
+    }
+
+    let mut pending_responses = pending_responses.into_iter();
+
+    for (position, recheck, cache_key) in pending {
+        let response = pending_responses.next();
+
+        if let (Some((tx, fingerprint)), Some(ref response)) = (cache_key, &response) {
+            recheck_tracker.store(tx, fingerprint, response.clone(), recheck);
+        }
+
+        if recheck {
+            recheck_tracker.on_recheck_complete(false);
+        }
+
+        responses[position] = Some(match response {
+            Some(response) => ResponseValue::CheckTx(response),
+            None => ResponseValue::Exception(ResponseException {
+                error: deadline_error
+                    .clone()
+                    .unwrap_or_else(|| "Response not returned by `check_tx_batch`".to_string()),
+            }),
+        });
+    }
+
+    let responses: Vec<Response> = responses
+        .into_iter()
+        .map(|value| Response { value })
+        .collect();
+
+    for ((timer, span), response) in timers.into_iter().zip(spans.iter()).zip(responses.iter()) {
+        timer.finish(response);
+        record_response(span, response);
+    }
+
+    debug!(message = "Sending responses");
+
+    responses
+}
+
+/// Checks a transaction, skipping validation of rechecked transactions whose state fingerprint (see
+/// [`Mempool::recheck_fingerprint`](crate::async_api::Mempool::recheck_fingerprint)) is unchanged
+async fn check_tx<M: Mempool>(
+    mempool: &M,
+    recheck_tracker: &RecheckTracker,
+    check_tx_request: RequestCheckTx,
+) -> ResponseCheckTx {
+    let recheck = check_tx_request.r#type() == CheckTxType::Recheck;
+    let fingerprint = recheck_fingerprint(mempool, recheck_tracker, &check_tx_request).await;
+
+    if let (true, Some(ref fingerprint)) = (recheck, &fingerprint) {
+        if let Some(response) = recheck_tracker.cached(&check_tx_request.tx, fingerprint) {
+            debug!(message = "Using cached response for recheck");
+
+            mempool
+                .on_cached_recheck(&check_tx_request, &response)
+                .await;
+            recheck_tracker.on_recheck_complete(true);
+            return response;
+        }
+    }
+
+    let tx = fingerprint.as_ref().map(|_| check_tx_request.tx.clone());
+    let response = mempool.check_tx(check_tx_request).await;
+
+    if let (Some(tx), Some(fingerprint)) = (tx, fingerprint) {
+        recheck_tracker.store(tx, fingerprint, response.clone(), recheck);
+    }
+
+    if recheck {
+        recheck_tracker.on_recheck_complete(false);
+    }
 
     response
 }
 
-#[instrument(skip(info))]
+async fn recheck_fingerprint<M: Mempool>(
+    mempool: &M,
+    recheck_tracker: &RecheckTracker,
+    check_tx_request: &RequestCheckTx,
+) -> Option<Vec<u8>> {
+    if recheck_tracker.is_caching() {
+        mempool.recheck_fingerprint(check_tx_request).await
+    } else {
+        None
+    }
+}
+
+#[instrument(
+    skip_all,
+    fields(
+        connection = "info",
+        method = Method::from(&request_value).as_str(),
+        block_height,
+        code
+    )
+)]
 pub async fn handle_info_request<I: Info>(
     info: &I,
     validator: Arc<Mutex<ConsensusStateValidator>>,
+    watchdog: &Watchdog,
+    metrics: &Metrics,
     request_value: RequestValue,
 ) -> Response {
-    let response_value = match request_value {
-        RequestValue::Echo(request) => ResponseValue::Echo(info.echo(request).await),
-        RequestValue::Flush(request) => ResponseValue::Flush(info.flush(request).await),
-        RequestValue::Info(request) => {
-            let info_response = info.info(request).await;
-            validator.lock().await.on_info_response(&info_response);
-            ResponseValue::Info(info_response)
-        }
-        RequestValue::SetOption(request) => {
-            ResponseValue::SetOption(info.set_option(request).await)
-        }
-        RequestValue::Query(request) => ResponseValue::Query(info.query(request).await),
-        _ => ResponseValue::Exception(ResponseException {
-            error: "Non-info request on info connection".to_string(),
-        }),
-    };
+    let timer = RequestTimer::start(metrics, ConnectionType::Info, &request_value);
+    let span = Span::current();
+
+    let method = Method::from(&request_value);
+
+    let response_value = watchdog
+        .watch(method, async {
+            match request_value {
+                RequestValue::Echo(request) => ResponseValue::Echo(info.echo(request).await),
+                RequestValue::Flush(request) => ResponseValue::Flush(info.flush(request).await),
+                RequestValue::Info(request) => {
+                    let info_response = info.info(request).await;
+                    validator.lock().await.on_info_response(&info_response);
+                    ResponseValue::Info(info_response)
+                }
+                RequestValue::SetOption(request) => {
+                    ResponseValue::SetOption(info.set_option(request).await)
+                }
+                RequestValue::Query(request) => {
+                    span.record("block_height", request.height);
+                    ResponseValue::Query(info.query(request).await)
+                }
+                _ => ResponseValue::Exception(ResponseException {
+                    error: "Non-info request on info connection".to_string(),
+                }),
+            }
+        })
+        .await
+        .unwrap_or_else(|error| ResponseValue::Exception(ResponseException { error }));
 
     let response = Response {
         value: Some(response_value),
     };
 
-    debug!(message = "Sending response", ?response);
+    timer.finish(&response);
+    record_response(&span, &response);
+
+    debug!(message = "Sending response");
 
     response
 }
 
-#[instrument(skip(snapshot))]
+#[instrument(
+    skip_all,
+    fields(
+        connection = "snapshot",
+        method = Method::from(&request_value).as_str(),
+        block_height
+    )
+)]
 pub async fn handle_snapshot_request<S: Snapshot>(
     snapshot: &S,
+    watchdog: &Watchdog,
+    metrics: &Metrics,
     request_value: RequestValue,
 ) -> Response {
-    let response_value = match request_value {
-        RequestValue::Echo(request) => ResponseValue::Echo(snapshot.echo(request).await),
-        RequestValue::Flush(request) => ResponseValue::Flush(snapshot.flush(request).await),
-        RequestValue::ListSnapshots(request) => {
-            ResponseValue::ListSnapshots(snapshot.list_snapshots(request).await)
-        }
-        RequestValue::OfferSnapshot(request) => {
-            ResponseValue::OfferSnapshot(snapshot.offer_snapshot(request).await)
-        }
-        RequestValue::LoadSnapshotChunk(request) => {
-            ResponseValue::LoadSnapshotChunk(snapshot.load_snapshot_chunk(request).await)
-        }
-        RequestValue::ApplySnapshotChunk(request) => {
-            ResponseValue::ApplySnapshotChunk(snapshot.apply_snapshot_chunk(request).await)
-        }
-        _ => ResponseValue::Exception(ResponseException {
-            error: "Non-snapshot request on snapshot connection".to_string(),
-        }),
-    };
+    let timer = RequestTimer::start(metrics, ConnectionType::Snapshot, &request_value);
+    let span = Span::current();
+
+    let method = Method::from(&request_value);
+
+    let response_value = watchdog
+        .watch(method, async {
+            match request_value {
+                RequestValue::Echo(request) => ResponseValue::Echo(snapshot.echo(request).await),
+                RequestValue::Flush(request) => ResponseValue::Flush(snapshot.flush(request).await),
+                RequestValue::ListSnapshots(request) => {
+                    ResponseValue::ListSnapshots(snapshot.list_snapshots(request).await)
+                }
+                RequestValue::OfferSnapshot(request) => {
+                    ResponseValue::OfferSnapshot(snapshot.offer_snapshot(request).await)
+                }
+                RequestValue::LoadSnapshotChunk(request) => {
+                    span.record("block_height", request.height);
+                    ResponseValue::LoadSnapshotChunk(snapshot.load_snapshot_chunk(request).await)
+                }
+                RequestValue::ApplySnapshotChunk(request) => {
+                    ResponseValue::ApplySnapshotChunk(snapshot.apply_snapshot_chunk(request).await)
+                }
+                _ => ResponseValue::Exception(ResponseException {
+                    error: "Non-snapshot request on snapshot connection".to_string(),
+                }),
+            }
+        })
+        .await
+        .unwrap_or_else(|error| ResponseValue::Exception(ResponseException { error }));
 
     let response = Response {
         value: Some(response_value),
     };
 
-    debug!(message = "Sending response", ?response);
+    timer.finish(&response);
+    record_response(&span, &response);
+
+    debug!(message = "Sending response");
 
     response
 }
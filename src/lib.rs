@@ -33,7 +33,9 @@
 //! `Mempool`, `Info` and `Snapshot`.
 //!
 //! > Note: Implementations of these traits are expected to be `Send + Sync` and methods take immutable reference of
-//! `self`. So, internal mutability must be handled using thread safe (`Arc`, `Mutex`, etc.) constructs.
+//! > `self`. So, internal mutability must be handled using thread safe (`Arc`, `Mutex`, etc.) constructs. When using
+//! > `sync_api`, consensus connection can also be implemented using `ConsensusMut` trait which takes mutable reference
+//! > of `self`.
 //!
 //! ## Synchronous and asynchronous APIs
 //!
@@ -42,9 +44,77 @@
 //!
 //! ## Async runtimes
 //!
-//! `abci-rs` also supports multiple async runtimes. These different runtimes can be enabled by using cargo features
-//! `use-async-std`, `use-smol` or `use-tokio`. Only one runtime can be enabled at a time. Compilation will fail if more
-//! than one runtime is enabled or none of them are enabled. By default, `use-tokio` feature is enabled.
+//! `abci-rs` also supports multiple async runtimes. Support for these runtimes can be enabled by using cargo features
+//! `use-async-std`, `use-smol` or `use-tokio`. Any number of runtimes can be enabled at the same time. By default,
+//! `use-tokio` feature is enabled.
+//!
+//! Servers use a [`Runtime`](crate::runtime::Runtime) supplied at construction time (e.g., using
+//! [`async_api::Server::with_runtime`](crate::async_api::Server::with_runtime)). Constructors which do not take a
+//! runtime use [`DefaultRuntime`](crate::runtime::DefaultRuntime), i.e., the first enabled runtime in this order:
+//! `tokio`, `async-std`, `smol` and `std`. Applications can also use their own runtime (or executor) by implementing
+//! [`Runtime`](crate::runtime::Runtime) trait.
+//!
+//! Applications which do not want to depend on an async runtime at all can use `use-std` feature instead (with
+//! `default-features = false`). With `use-std`, the server uses `std::net` listeners and streams and spawns an OS
+//! thread for every connection (and for every in-flight `CheckTx` request). This is well suited for small
+//! applications using `sync_api`. As there is no thread pool, the default limit of in-flight `CheckTx` requests is
+//! lower with `StdRuntime` (see `StdRuntime` for its other limitations).
+//!
+//! ## Addresses
+//!
+//! [`Address`](crate::Address) can be parsed from the syntax used by Tendermint for `proxy_app` (e.g.,
+//! `tcp://127.0.0.1:26658`, `tcp://localhost:26658` or `unix:///path/to/app.sock`). Host names are resolved when the
+//! server binds. With `serde` feature enabled, [`Address`](crate::Address) can be used in configuration files as a
+//! string in the same syntax.
+//!
+//! Servers can also run on an already bound `std` `TcpListener` or `UnixListener` (e.g., one inherited across process
+//! generations for zero-downtime restarts). On Unix, listeners passed by systemd with socket activation can be obtained
+//! using [`Address::from_listen_fds`](crate::Address::from_listen_fds).
+//!
+//! On Unix, [`UnixSocketConfig`](crate::UnixSocketConfig) controls the lifecycle of socket files, i.e., removal of
+//! stale sockets left behind by a crashed server, permissions and ownership of the socket file and its removal when the
+//! server stops. On Linux, abstract-namespace sockets (which have no file) can be used with `unix://@name` addresses.
+//!
+//! A server can listen on multiple addresses at once (e.g., a UDS for local Tendermint node and a TCP port for a
+//! debugging client) using [`async_api::Server::run_all`](crate::async_api::Server::run_all) or
+//! [`sync_api::Server::run_all`](crate::sync_api::Server::run_all). Connections on all the addresses share the same
+//! application instances and consensus state.
+//!
+//! `run` binds and serves in a single call. To learn the actual address of a server before serving (e.g., the port
+//! assigned when binding to port `0` in tests), bind it first using `Server::bind` (or `Server::bind_all`) and then
+//! call `serve` (or `spawn`, to serve in background) on returned
+//! [`async_api::BoundServer`](crate::async_api::BoundServer) or [`sync_api::BoundServer`](crate::sync_api::BoundServer).
+//!
+//! ## Metrics
+//!
+//! With `metrics` feature enabled, servers can expose Prometheus metrics of ABCI traffic (request counts, latencies,
+//! exceptions, in-flight `CheckTx` requests, block height and encoded size of requests/responses per connection) over
+//! HTTP using [`async_api::Server::serve_metrics`](crate::async_api::Server::serve_metrics) or
+//! [`sync_api::Server::serve_metrics`](crate::sync_api::Server::serve_metrics).
+//!
+//! ## Access control
+//!
+//! By default, servers accept connections from any peer which can reach the socket. A
+//! [`PeerAllowlist`](crate::PeerAllowlist) passed to `Server::with_peer_allowlist` restricts TCP peers to given IP ranges,
+//! Unix socket peers to given UIDs (using peer credentials) and configures a
+//! [`DuplicateConnectionPolicy`](crate::DuplicateConnectionPolicy) per connection type, i.e., whether a new connection
+//! is allowed, rejected or replaces the established one when a connection of same type (e.g., consensus) is already
+//! established. Rejected peers are logged and their connections are closed.
+//!
+//! ## TLS
+//!
+//! With `tls` feature enabled, TCP connections can be secured using TLS (with `rustls`) by passing a
+//! [`TlsConfig`](crate::TlsConfig) to `Server::with_tls`. A client CA can be configured using
+//! [`TlsConfig::with_client_ca`](crate::TlsConfig::with_client_ca) to require Tendermint node to authenticate itself
+//! with a client certificate (mutual TLS).
+//!
+//! ## Health
+//!
+//! [`Health`](crate::Health) of a server (established connections, consensus state, time of last commit and the last
+//! protocol violation) can be read using `Server::health` or served over HTTP at `GET /health` and `GET /ready` using
+//! [`async_api::Server::serve_health`](crate::async_api::Server::serve_health) or
+//! [`sync_api::Server::serve_health`](crate::sync_api::Server::serve_health). `/ready` responds with `200 OK` once
+//! consensus, mempool and info connections are established, which makes it usable as a readiness probe.
 //!
 //! ## Examples
 //!
@@ -55,17 +125,8 @@
 #[cfg(not(any(feature = "async-api", feature = "sync-api")))]
 compile_error!("Either feature `async-api` or `sync-api` must be enabled for this crate");
 
-#[cfg(not(any(feature = "use-async-std", feature = "use-smol", feature = "use-tokio",)))]
-compile_error!("One runtime should be enabled: `use-async-std`, `use-smol` or `use-tokio`");
-
-#[cfg(all(feature = "use-async-std", feature = "use-smol"))]
-compile_error!("Only one runtime should be enabled: `use-async-std`, `use-smol` or `use-tokio`");
-#[cfg(all(feature = "use-async-std", feature = "use-tokio"))]
-compile_error!("Only one runtime should be enabled: `use-async-std`, `use-smol` or `use-tokio`");
-#[cfg(all(feature = "use-tokio", feature = "use-smol"))]
-compile_error!("Only one runtime should be enabled: `use-async-std`, `use-smol` or `use-tokio`");
-
 mod address;
+mod allowlist;
 cfg_if::cfg_if! {
     if #[cfg(feature = "async-api")] {
         #[cfg_attr(feature = "doc", doc(cfg(feature = "async-api")))]
@@ -75,6 +136,8 @@ cfg_if::cfg_if! {
     }
 }
 mod handler;
+mod metrics;
+pub mod runtime;
 mod state;
 mod stream_split;
 #[cfg(feature = "sync-api")]
@@ -83,11 +146,28 @@ pub mod sync_api;
 mod tasks;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "tls")]
+mod tls;
 pub mod types;
+#[cfg(unix)]
+mod unix_socket;
 mod utils;
+mod watchdog;
 
 #[cfg(feature = "async-api")]
 #[cfg_attr(feature = "doc", doc(cfg(feature = "async-api")))]
 pub use async_trait::async_trait;
 
-pub use self::address::Address;
+pub use self::{
+    address::Address,
+    allowlist::{DuplicateConnectionPolicy, IpRange, PeerAllowlist},
+    utils::{ConnectionType, Health, Method, QueueMetrics, RecheckStats},
+    watchdog::DeadlineAction,
+};
+
+#[cfg(feature = "tls")]
+#[cfg_attr(feature = "doc", doc(cfg(feature = "tls")))]
+pub use self::tls::TlsConfig;
+#[cfg(unix)]
+#[cfg_attr(feature = "doc", doc(cfg(unix)))]
+pub use self::unix_socket::UnixSocketConfig;
@@ -0,0 +1,12 @@
+//! Prometheus metrics of ABCI traffic (enabled using `metrics` feature)
+//!
+//! When `metrics` feature is disabled, all the recording functions are no-ops.
+#[cfg(not(feature = "metrics"))]
+mod noop;
+#[cfg(feature = "metrics")]
+mod prometheus;
+
+#[cfg(not(feature = "metrics"))]
+pub use self::noop::{Metrics, RequestTimer};
+#[cfg(feature = "metrics")]
+pub use self::prometheus::{serve, Metrics, RequestTimer};
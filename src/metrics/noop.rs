@@ -0,0 +1,47 @@
+use tendermint_proto::abci::{request::Value as RequestValue, Response};
+
+use crate::{
+    types::{RequestCheckTx, RequestDeliverTx},
+    utils::ConnectionType,
+};
+
+/// Metrics of ABCI traffic of a server
+#[derive(Default)]
+pub struct Metrics;
+
+impl Metrics {
+    /// Records height of the block being executed
+    #[inline]
+    pub fn set_block_height(&self, _height: i64) {}
+}
+
+/// Records metrics of a single request
+pub struct RequestTimer;
+
+impl RequestTimer {
+    /// Starts timing a request received on given connection
+    #[inline]
+    pub fn start(
+        _metrics: &Metrics,
+        _connection_type: ConnectionType,
+        _request_value: &RequestValue,
+    ) -> Self {
+        Self
+    }
+
+    /// Starts timing a `CheckTx` request which is executed as a part of a batch
+    #[inline]
+    pub fn start_check_tx(_metrics: &Metrics, _check_tx_request: &RequestCheckTx) -> Self {
+        Self
+    }
+
+    /// Starts timing a `DeliverTx` request which is executed as a part of a batch
+    #[inline]
+    pub fn start_deliver_tx(_metrics: &Metrics, _deliver_tx_request: &RequestDeliverTx) -> Self {
+        Self
+    }
+
+    /// Records the response of request
+    #[inline]
+    pub fn finish(self, _response: &Response) {}
+}
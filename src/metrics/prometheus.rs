@@ -0,0 +1,459 @@
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use futures_lite::future::ready;
+use integer_encoding::VarInt;
+use prost::encoding::message;
+use tendermint_proto::abci::{
+    request::Value as RequestValue, response::Value as ResponseValue, Response,
+};
+
+use crate::{
+    runtime::{Listener, Runtime},
+    types::{RequestCheckTx, RequestDeliverTx},
+    utils::{
+        serve_http, ConnectionType, HttpHandler, HttpResponse, InFlightLimiter, Method,
+        QueueMetrics,
+    },
+};
+
+/// Tags of `CheckTx` and `DeliverTx` fields in `Request` message
+const CHECK_TX_TAG: u32 = 8;
+const DELIVER_TX_TAG: u32 = 9;
+
+/// Upper bounds (in seconds) of buckets of request duration histogram
+const DURATION_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Connection types for which metrics are recorded (in the order in which they are rendered)
+const CONNECTION_TYPES: [ConnectionType; 5] = [
+    ConnectionType::Consensus,
+    ConnectionType::Mempool,
+    ConnectionType::Info,
+    ConnectionType::Snapshot,
+    ConnectionType::Unknown,
+];
+
+/// Metrics of ABCI traffic of a server. Counters are atomic, so, recording a request does not take any lock.
+pub struct Metrics {
+    /// Metrics of requests indexed by connection type and method (see [`Metrics::request_index`])
+    requests: Vec<MethodMetrics>,
+    /// Encoded size of requests and responses indexed by connection type
+    bytes: [(AtomicU64, AtomicU64); CONNECTION_TYPES.len()],
+    block_height: AtomicI64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            requests: (0..CONNECTION_TYPES.len() * Method::ALL.len())
+                .map(|_| Default::default())
+                .collect(),
+            bytes: Default::default(),
+            block_height: Default::default(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Records height of the block being executed
+    pub fn set_block_height(&self, height: i64) {
+        self.block_height.store(height, Ordering::Relaxed);
+    }
+
+    /// Renders all the metrics in Prometheus text format
+    pub fn render(&self, check_tx_queue_metrics: QueueMetrics) -> String {
+        let mut output = String::new();
+
+        write_header(
+            &mut output,
+            "abci_requests_total",
+            "counter",
+            "Number of ABCI requests handled",
+        );
+        for (connection, method, metrics) in self.recorded_requests() {
+            let _ = writeln!(
+                output,
+                "abci_requests_total{{connection=\"{}\",method=\"{}\"}} {}",
+                connection,
+                method,
+                metrics.count.load(Ordering::Relaxed)
+            );
+        }
+
+        write_header(
+            &mut output,
+            "abci_request_exceptions_total",
+            "counter",
+            "Number of ABCI requests which resulted in an exception",
+        );
+        for (connection, method, metrics) in self.recorded_requests() {
+            let _ = writeln!(
+                output,
+                "abci_request_exceptions_total{{connection=\"{}\",method=\"{}\"}} {}",
+                connection,
+                method,
+                metrics.exceptions.load(Ordering::Relaxed)
+            );
+        }
+
+        write_header(
+            &mut output,
+            "abci_request_duration_seconds",
+            "histogram",
+            "Time taken to handle ABCI requests",
+        );
+        for (connection, method, metrics) in self.recorded_requests() {
+            metrics.duration.render(
+                &mut output,
+                "abci_request_duration_seconds",
+                &format!("connection=\"{}\",method=\"{}\"", connection, method),
+            );
+        }
+
+        write_header(
+            &mut output,
+            "abci_check_tx_in_flight",
+            "gauge",
+            "Number of CheckTx requests currently in-flight",
+        );
+        let _ = writeln!(
+            output,
+            "abci_check_tx_in_flight {}",
+            check_tx_queue_metrics.in_flight
+        );
+
+        write_header(
+            &mut output,
+            "abci_block_height",
+            "gauge",
+            "Height of the current block",
+        );
+        let _ = writeln!(
+            output,
+            "abci_block_height {}",
+            self.block_height.load(Ordering::Relaxed)
+        );
+
+        write_header(
+            &mut output,
+            "abci_request_bytes_total",
+            "counter",
+            "Encoded size of ABCI requests (including length prefix) in bytes",
+        );
+        for (connection, (request_bytes, _)) in self.recorded_bytes() {
+            let _ = writeln!(
+                output,
+                "abci_request_bytes_total{{connection=\"{}\"}} {}",
+                connection, request_bytes
+            );
+        }
+
+        write_header(
+            &mut output,
+            "abci_response_bytes_total",
+            "counter",
+            "Encoded size of ABCI responses (including length prefix) in bytes",
+        );
+        for (connection, (_, response_bytes)) in self.recorded_bytes() {
+            let _ = writeln!(
+                output,
+                "abci_response_bytes_total{{connection=\"{}\"}} {}",
+                connection, response_bytes
+            );
+        }
+
+        output
+    }
+
+    /// Returns metrics of (connection type, method) pairs which received at least one request
+    fn recorded_requests(
+        &self,
+    ) -> impl Iterator<Item = (&'static str, &'static str, &MethodMetrics)> {
+        CONNECTION_TYPES
+            .iter()
+            .flat_map(|connection_type| {
+                Method::ALL
+                    .iter()
+                    .map(move |method| (*connection_type, *method))
+            })
+            .zip(self.requests.iter())
+            .filter(|(_, metrics)| metrics.count.load(Ordering::Relaxed) > 0)
+            .map(|((connection_type, method), metrics)| {
+                (connection_type.as_str(), method.as_str(), metrics)
+            })
+    }
+
+    /// Returns encoded size of requests and responses on connection types which received at least one request
+    ///
+    /// Sizes are computed from the messages (with their length prefixes) and not counted on sockets, so, they do not
+    /// include transport overhead (e.g., TLS records).
+    fn recorded_bytes(&self) -> impl Iterator<Item = (&'static str, (u64, u64))> + '_ {
+        CONNECTION_TYPES
+            .iter()
+            .zip(self.bytes.iter())
+            .map(|(connection_type, (request_bytes, response_bytes))| {
+                (
+                    connection_type.as_str(),
+                    (
+                        request_bytes.load(Ordering::Relaxed),
+                        response_bytes.load(Ordering::Relaxed),
+                    ),
+                )
+            })
+            .filter(|(_, (request_bytes, _))| *request_bytes > 0)
+    }
+
+    fn request_index(connection_type: ConnectionType, method: Method) -> usize {
+        connection_index(connection_type) * Method::ALL.len() + method.index()
+    }
+}
+
+/// Records metrics of a single request
+pub struct RequestTimer<'a> {
+    metrics: &'a Metrics,
+    connection_type: ConnectionType,
+    method: Method,
+    request_len: usize,
+    start: Instant,
+}
+
+impl<'a> RequestTimer<'a> {
+    /// Starts timing a request received on given connection
+    pub fn start(
+        metrics: &'a Metrics,
+        connection_type: ConnectionType,
+        request_value: &RequestValue,
+    ) -> Self {
+        Self::with_len(
+            metrics,
+            connection_type,
+            Method::from(request_value),
+            request_value.encoded_len(),
+        )
+    }
+
+    /// Starts timing a `CheckTx` request which is executed as a part of a batch
+    pub fn start_check_tx(metrics: &'a Metrics, check_tx_request: &RequestCheckTx) -> Self {
+        Self::with_len(
+            metrics,
+            ConnectionType::Mempool,
+            Method::CheckTx,
+            message::encoded_len(CHECK_TX_TAG, check_tx_request),
+        )
+    }
+
+    /// Starts timing a `DeliverTx` request which is executed as a part of a batch
+    pub fn start_deliver_tx(metrics: &'a Metrics, deliver_tx_request: &RequestDeliverTx) -> Self {
+        Self::with_len(
+            metrics,
+            ConnectionType::Consensus,
+            Method::DeliverTx,
+            message::encoded_len(DELIVER_TX_TAG, deliver_tx_request),
+        )
+    }
+
+    fn with_len(
+        metrics: &'a Metrics,
+        connection_type: ConnectionType,
+        method: Method,
+        request_len: usize,
+    ) -> Self {
+        Self {
+            metrics,
+            connection_type,
+            method,
+            request_len: framed_len(request_len),
+            start: Instant::now(),
+        }
+    }
+
+    /// Records the response of request
+    pub fn finish(self, response: &Response) {
+        let duration = self.start.elapsed().as_secs_f64();
+        let response_len = framed_len(
+            response
+                .value
+                .as_ref()
+                .map(ResponseValue::encoded_len)
+                .unwrap_or_default(),
+        );
+        let exception = matches!(response.value, Some(ResponseValue::Exception(_)));
+
+        let metrics =
+            &self.metrics.requests[Metrics::request_index(self.connection_type, self.method)];
+
+        metrics.count.fetch_add(1, Ordering::Relaxed);
+        metrics.duration.observe(duration);
+
+        if exception {
+            metrics.exceptions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let (request_bytes, response_bytes) =
+            &self.metrics.bytes[connection_index(self.connection_type)];
+
+        request_bytes.fetch_add(self.request_len as u64, Ordering::Relaxed);
+        response_bytes.fetch_add(response_len as u64, Ordering::Relaxed);
+    }
+}
+
+/// Serves metrics over HTTP (at `GET /metrics`) on connections accepted by given listener
+pub async fn serve(
+    runtime: Arc<dyn Runtime>,
+    listener: Box<dyn Listener>,
+    metrics: Arc<Metrics>,
+    check_tx_limiter: Arc<InFlightLimiter>,
+) {
+    let handler: HttpHandler = Arc::new(move |path| {
+        let response = match path {
+            "/metrics" => HttpResponse {
+                status: "200 OK",
+                content_type: "text/plain; version=0.0.4",
+                body: metrics.render(check_tx_limiter.metrics()),
+            },
+            _ => HttpResponse::not_found(),
+        };
+
+        Box::pin(ready(response))
+    });
+
+    serve_http(runtime, listener, handler).await
+}
+
+#[derive(Default)]
+struct MethodMetrics {
+    count: AtomicU64,
+    exceptions: AtomicU64,
+    duration: Histogram,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// Number of observations in each bucket (non-cumulative)
+    buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    /// Sum of observations in nanoseconds
+    sum_nanos: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, value: f64) {
+        if let Some(index) = DURATION_BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.sum_nanos
+            .fetch_add((value * 1e9) as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, output: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+
+        for (bound, count) in DURATION_BUCKETS.iter().zip(self.buckets.iter()) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                output,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, bound, cumulative
+            );
+        }
+
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+
+        let _ = writeln!(
+            output,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, count
+        );
+        let _ = writeln!(output, "{}_sum{{{}}} {}", name, labels, sum);
+        let _ = writeln!(output, "{}_count{{{}}} {}", name, labels, count);
+    }
+}
+
+/// Returns the index of connection type in [`CONNECTION_TYPES`]
+fn connection_index(connection_type: ConnectionType) -> usize {
+    connection_type
+        .index()
+        .unwrap_or(CONNECTION_TYPES.len() - 1)
+}
+
+fn write_header(output: &mut String, name: &str, r#type: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, r#type);
+}
+
+/// Returns the encoded size of a length-prefixed message
+fn framed_len(message_len: usize) -> usize {
+    message_len + (message_len as i64).required_space()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_histogram_render() {
+        let histogram = Histogram::default();
+
+        histogram.observe(0.002);
+        histogram.observe(0.3);
+        histogram.observe(20.0);
+
+        let mut output = String::new();
+        histogram.render(&mut output, "duration", "method=\"check_tx\"");
+
+        assert!(output.contains("duration_bucket{method=\"check_tx\",le=\"0.001\"} 0\n"));
+        assert!(output.contains("duration_bucket{method=\"check_tx\",le=\"0.0025\"} 1\n"));
+        assert!(output.contains("duration_bucket{method=\"check_tx\",le=\"0.5\"} 2\n"));
+        assert!(output.contains("duration_bucket{method=\"check_tx\",le=\"10\"} 2\n"));
+        assert!(output.contains("duration_bucket{method=\"check_tx\",le=\"+Inf\"} 3\n"));
+        assert!(output.contains("duration_count{method=\"check_tx\"} 3\n"));
+    }
+
+    #[test]
+    fn check_metrics_are_per_instance() {
+        let first = Metrics::default();
+        let second = Metrics::default();
+
+        let request_value = RequestValue::Flush(Default::default());
+        let response = Response {
+            value: Some(ResponseValue::Flush(Default::default())),
+        };
+
+        RequestTimer::start(&first, ConnectionType::Mempool, &request_value).finish(&response);
+        RequestTimer::start(&first, ConnectionType::Mempool, &request_value).finish(&response);
+        first.set_block_height(5);
+
+        let output = first.render(InFlightLimiter::new(1).metrics());
+        assert!(output.contains("abci_requests_total{connection=\"mempool\",method=\"flush\"} 2\n"));
+        assert!(output.contains("abci_block_height 5\n"));
+
+        let output = second.render(InFlightLimiter::new(1).metrics());
+        assert!(!output.contains("abci_requests_total{"));
+        assert!(!output.contains("abci_request_bytes_total{"));
+        assert!(output.contains("abci_block_height 0\n"));
+    }
+
+    #[test]
+    fn check_framed_len() {
+        let request_value = RequestValue::Flush(Default::default());
+        let request = tendermint_proto::abci::Request {
+            value: Some(request_value.clone()),
+        };
+
+        let mut buf = bytes::BytesMut::new();
+        crate::types::encode(request, &mut buf).unwrap();
+
+        assert_eq!(buf.len(), framed_len(request_value.encoded_len()));
+    }
+}
@@ -0,0 +1,423 @@
+//! Async runtime abstraction used by ABCI servers
+//!
+//! Servers do not depend on a particular async runtime. Instead, all the runtime specific operations (spawning tasks,
+//! running blocking code and binding listeners) go through [`Runtime`] trait, an implementation of which can be
+//! supplied while constructing a server (e.g., [`async_api::Server::with_runtime`](crate::async_api::Server::with_runtime)).
+//!
+//! `abci-rs` provides implementations of [`Runtime`] for `tokio` ([`TokioRuntime`]), `async-std` (`AsyncStdRuntime`),
+//! `smol` (`SmolRuntime`) and plain `std` threads (`StdRuntime`), each behind its own cargo feature (`use-tokio`,
+//! `use-async-std`, `use-smol` and `use-std`). Any number of these features can be enabled at the same time.
+//! [`DefaultRuntime`] is used by constructors which do not take a runtime and is the first enabled runtime in this
+//! order: `tokio`, `async-std`, `smol` and `std`.
+//!
+//! Mutexes and channels used by servers are runtime agnostic (from `async-lock` and `async-channel` crates) and work
+//! with any runtime.
+#[cfg(feature = "use-async-std")]
+mod async_std;
+#[cfg(any(feature = "use-tokio", test))]
+mod compat;
+#[cfg(feature = "use-smol")]
+mod smol;
+#[cfg(feature = "use-std")]
+mod std;
+#[cfg(feature = "use-tokio")]
+mod tokio;
+
+use ::std::{
+    future::Future,
+    io::{Error, ErrorKind, Result},
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+#[cfg(unix)]
+use ::std::{
+    os::{fd::AsFd, unix::net::UnixListener},
+    path::PathBuf,
+};
+
+use async_channel::{bounded, Receiver, Sender};
+use futures_lite::{
+    io::{AsyncRead, AsyncWrite},
+    Stream,
+};
+#[cfg(test)]
+use mock_io::tokio::MockListener;
+
+#[cfg(feature = "use-async-std")]
+#[cfg_attr(feature = "doc", doc(cfg(feature = "use-async-std")))]
+pub use self::async_std::AsyncStdRuntime;
+#[cfg(any(feature = "use-tokio", test))]
+pub(crate) use self::compat::Compat;
+#[cfg(feature = "use-smol")]
+#[cfg_attr(feature = "doc", doc(cfg(feature = "use-smol")))]
+pub use self::smol::SmolRuntime;
+#[cfg(feature = "use-std")]
+#[cfg_attr(feature = "doc", doc(cfg(feature = "use-std")))]
+pub use self::std::StdRuntime;
+#[cfg(feature = "use-tokio")]
+#[cfg_attr(feature = "doc", doc(cfg(feature = "use-tokio")))]
+pub use self::tokio::TokioRuntime;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "use-tokio")] {
+        /// Runtime used by server constructors which do not take a runtime
+        pub type DefaultRuntime = TokioRuntime;
+    } else if #[cfg(feature = "use-async-std")] {
+        /// Runtime used by server constructors which do not take a runtime
+        pub type DefaultRuntime = AsyncStdRuntime;
+    } else if #[cfg(feature = "use-smol")] {
+        /// Runtime used by server constructors which do not take a runtime
+        pub type DefaultRuntime = SmolRuntime;
+    } else if #[cfg(feature = "use-std")] {
+        /// Runtime used by server constructors which do not take a runtime
+        pub type DefaultRuntime = StdRuntime;
+    }
+}
+
+/// An owned, dynamically typed [`Future`] which can be sent across threads
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Reader half of a connection
+pub type BoxReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Writer half of a connection
+pub type BoxWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Sets the timeout of reads on a connection (see [`Connection::read_timeout_setter`])
+pub type ReadTimeoutSetter = Box<dyn Fn(Option<Duration>) -> Result<()> + Send + Sync>;
+
+/// Operations of an async runtime required by ABCI servers
+pub trait Runtime: Send + Sync + 'static {
+    /// Spawns a future in background
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+
+    /// Runs a blocking function on a thread where blocking is acceptable
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>);
+
+    /// Runs a future to completion on current thread, blocking it until the future completes
+    fn block_on(&self, future: BoxFuture<'_, Result<()>>) -> Result<()>;
+
+    /// Creates a TCP listener bound to given address
+    fn bind_tcp(&self, addr: SocketAddr) -> BoxFuture<'static, Result<Box<dyn Listener>>>;
+
+    /// Creates a UDS listener bound to given path
+    #[cfg(unix)]
+    #[cfg_attr(feature = "doc", doc(cfg(unix)))]
+    fn bind_unix(&self, path: PathBuf) -> BoxFuture<'static, Result<Box<dyn Listener>>>;
+
+    /// Creates a listener from an existing (already bound) `std` TCP listener, e.g., one inherited from parent process
+    ///
+    /// Default implementation returns an error of kind [`Unsupported`](::std::io::ErrorKind::Unsupported).
+    fn wrap_tcp(&self, listener: ::std::net::TcpListener) -> Result<Box<dyn Listener>> {
+        let _ = listener;
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "Runtime does not support pre-bound TCP listeners",
+        ))
+    }
+
+    /// Creates a listener from an existing (already bound) `std` UDS listener, e.g., one inherited from parent process
+    ///
+    /// Default implementation returns an error of kind [`Unsupported`](::std::io::ErrorKind::Unsupported).
+    #[cfg(unix)]
+    #[cfg_attr(feature = "doc", doc(cfg(unix)))]
+    fn wrap_unix(&self, listener: UnixListener) -> Result<Box<dyn Listener>> {
+        let _ = listener;
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "Runtime does not support pre-bound UDS listeners",
+        ))
+    }
+
+    /// Returns a future which completes after given duration
+    ///
+    /// Default implementation sleeps on a new OS thread. Runtimes with their own timers should override it.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let (sender, receiver) = bounded(1);
+
+        ::std::thread::spawn(move || {
+            ::std::thread::sleep(duration);
+            let _ = sender.try_send(());
+        });
+
+        Box::pin(async move {
+            let _ = receiver.recv().await;
+        })
+    }
+
+    /// Returns `true` if every spawned task runs on its own OS thread and blocks it on I/O (default: `false`)
+    ///
+    /// Servers use lower default limits for such runtimes (see
+    /// [`DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX`](crate::async_api::DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX)).
+    fn is_thread_per_task(&self) -> bool {
+        false
+    }
+}
+
+impl<R> Runtime for Arc<R>
+where
+    R: Runtime + ?Sized,
+{
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        (**self).spawn(future)
+    }
+
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+        (**self).spawn_blocking(f)
+    }
+
+    fn block_on(&self, future: BoxFuture<'_, Result<()>>) -> Result<()> {
+        (**self).block_on(future)
+    }
+
+    fn bind_tcp(&self, addr: SocketAddr) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        (**self).bind_tcp(addr)
+    }
+
+    #[cfg(unix)]
+    fn bind_unix(&self, path: PathBuf) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        (**self).bind_unix(path)
+    }
+
+    fn wrap_tcp(&self, listener: ::std::net::TcpListener) -> Result<Box<dyn Listener>> {
+        (**self).wrap_tcp(listener)
+    }
+
+    #[cfg(unix)]
+    fn wrap_unix(&self, listener: UnixListener) -> Result<Box<dyn Listener>> {
+        (**self).wrap_unix(listener)
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        (**self).sleep(duration)
+    }
+
+    fn is_thread_per_task(&self) -> bool {
+        (**self).is_thread_per_task()
+    }
+}
+
+impl dyn Runtime {
+    /// Spawns a future in background and returns a handle to its output
+    pub(crate) fn spawn_with_handle<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (sender, handle) = JoinHandle::pair();
+
+        self.spawn(Box::pin(async move {
+            let _ = sender.send(future.await).await;
+        }));
+
+        handle
+    }
+
+    /// Runs a blocking function on a thread where blocking is acceptable and returns its output
+    pub(crate) async fn unblock<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = bounded(1);
+
+        self.spawn_blocking(Box::new(move || {
+            let _ = sender.try_send(f());
+        }));
+
+        receiver
+            .recv()
+            .await
+            .expect("Failed to execute blocking task")
+    }
+
+    /// Resolves given host name and creates a TCP listener bound to the first resolved address which can be bound.
+    /// Returns the listener along with its address.
+    pub(crate) async fn bind_tcp_host(
+        &self,
+        host: String,
+        port: u16,
+    ) -> Result<(Box<dyn Listener>, SocketAddr)> {
+        let addrs = self
+            .unblock(move || {
+                (host.as_str(), port)
+                    .to_socket_addrs()
+                    .map(Iterator::collect::<Vec<_>>)
+            })
+            .await?;
+
+        let mut last_err = None;
+
+        for addr in addrs {
+            match self.bind_tcp(addr).await {
+                Ok(listener) => return Ok((listener, addr)),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            Error::new(
+                ErrorKind::AddrNotAvailable,
+                "Host name did not resolve to any address",
+            )
+        }))
+    }
+}
+
+/// A listener which accepts incoming connections
+pub trait Listener: Send {
+    /// Accepts a new incoming connection
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>>;
+
+    /// Returns the local address of a TCP listener (e.g., to find the port assigned when binding to port `0`)
+    ///
+    /// Default implementation returns `None`.
+    fn local_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    /// Sets the timeout of reads on connections accepted by this listener after this call (e.g., so that a client of
+    /// a local HTTP endpoint cannot keep its connection open forever)
+    ///
+    /// Only listeners with blocking sockets (see [`Runtime::is_thread_per_task`]) need to implement this, as they
+    /// cannot interrupt a blocked read otherwise. Default implementation does nothing.
+    fn set_read_timeout(&mut self, _timeout: Option<Duration>) {}
+}
+
+/// An incoming connection accepted by a [`Listener`]
+pub struct Connection {
+    /// Reader half of connection
+    pub reader: BoxReader,
+    /// Writer half of connection
+    pub writer: BoxWriter,
+    /// Address of peer (used for logging)
+    pub peer_addr: String,
+    /// IP address of peer (for TCP connections)
+    pub peer_ip: Option<IpAddr>,
+    /// User ID of peer process (for Unix socket connections, if available, see [`peer_uid`])
+    pub peer_uid: Option<u32>,
+    /// Sets the timeout of reads on connection (e.g., to bound the duration of TLS handshake)
+    ///
+    /// Only connections with blocking sockets (see [`Runtime::is_thread_per_task`]) need to provide this, as they
+    /// cannot interrupt a blocked read otherwise.
+    pub read_timeout_setter: Option<ReadTimeoutSetter>,
+}
+
+impl Connection {
+    /// Creates a new instance of [`Connection`]
+    pub fn new<R, W>(reader: R, writer: W, peer_addr: String) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        Self {
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            peer_addr,
+            peer_ip: None,
+            peer_uid: None,
+            read_timeout_setter: None,
+        }
+    }
+}
+
+/// Returns the user ID of peer process of a connected Unix socket
+///
+/// Peer credentials are read using `SO_PEERCRED`, which is only supported on Linux and Android. Returns `None` on other
+/// platforms or if credentials cannot be read.
+#[cfg(unix)]
+#[cfg_attr(feature = "doc", doc(cfg(unix)))]
+pub fn peer_uid<S: AsFd>(stream: &S) -> Option<u32> {
+    cfg_if::cfg_if! {
+        if #[cfg(any(target_os = "linux", target_os = "android"))] {
+            rustix::net::sockopt::get_socket_peercred(stream)
+                .ok()
+                .map(|credentials| credentials.uid.as_raw())
+        } else {
+            let _ = stream;
+            None
+        }
+    }
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.writer).poll_close(cx)
+    }
+}
+
+/// Handle to a future spawned on a [`Runtime`] (e.g., using
+/// [`async_api::BoundServer::spawn`](crate::async_api::BoundServer::spawn)). Resolves to `None` if the future was
+/// dropped (e.g., because it panicked) before completing.
+pub struct JoinHandle<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Returns a handle which resolves to the value sent using returned sender
+    pub(crate) fn pair() -> (Sender<T>, Self) {
+        let (sender, receiver) = bounded(1);
+        (sender, Self { receiver })
+    }
+
+    /// Returns a handle which resolves to given value
+    pub(crate) fn ready(value: T) -> Self {
+        let (sender, handle) = Self::pair();
+        let _ = sender.try_send(value);
+
+        handle
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+impl Listener for MockListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(async move {
+            match MockListener::accept(self).await {
+                Ok(stream) => {
+                    let (reader, writer) = stream.split();
+                    Ok(Connection::new(
+                        Compat::new(reader),
+                        Compat::new(writer),
+                        "test_peer".to_string(),
+                    ))
+                }
+                // All the handles are dropped, i.e., no more connections will be made
+                Err(_) => futures_lite::future::pending().await,
+            }
+        })
+    }
+}
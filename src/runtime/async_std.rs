@@ -0,0 +1,88 @@
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::{io::Result, net::SocketAddr, time::Duration};
+
+#[cfg(unix)]
+use async_std::os::unix::net::UnixListener;
+use async_std::{net::TcpListener, task};
+
+#[cfg(unix)]
+use super::peer_uid;
+use super::{BoxFuture, Connection, Listener, Runtime};
+
+/// [`Runtime`] backed by `async-std`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncStdRuntime;
+
+impl Runtime for AsyncStdRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        task::spawn(future);
+    }
+
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+        task::spawn_blocking(f);
+    }
+
+    fn block_on(&self, future: BoxFuture<'_, Result<()>>) -> Result<()> {
+        task::block_on(future)
+    }
+
+    fn bind_tcp(&self, addr: SocketAddr) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        Box::pin(async move {
+            let listener = TcpListener::bind(addr).await?;
+            Ok(Box::new(listener) as Box<dyn Listener>)
+        })
+    }
+
+    #[cfg(unix)]
+    fn bind_unix(&self, path: PathBuf) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        Box::pin(async move {
+            let listener = UnixListener::bind(path).await?;
+            Ok(Box::new(listener) as Box<dyn Listener>)
+        })
+    }
+
+    fn wrap_tcp(&self, listener: std::net::TcpListener) -> Result<Box<dyn Listener>> {
+        listener.set_nonblocking(true)?;
+        Ok(Box::new(TcpListener::from(listener)))
+    }
+
+    #[cfg(unix)]
+    fn wrap_unix(&self, listener: std::os::unix::net::UnixListener) -> Result<Box<dyn Listener>> {
+        listener.set_nonblocking(true)?;
+        Ok(Box::new(UnixListener::from(listener)))
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(task::sleep(duration))
+    }
+}
+
+impl Listener for TcpListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(async move {
+            let (stream, peer_addr) = TcpListener::accept(self).await?;
+            Ok(Connection {
+                peer_ip: Some(peer_addr.ip()),
+                ..Connection::new(stream.clone(), stream, peer_addr.to_string())
+            })
+        })
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        TcpListener::local_addr(self).ok()
+    }
+}
+
+#[cfg(unix)]
+impl Listener for UnixListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(async move {
+            let (stream, peer_addr) = UnixListener::accept(self).await?;
+            Ok(Connection {
+                peer_uid: peer_uid(&stream),
+                ..Connection::new(stream.clone(), stream, format!("{:?}", peer_addr))
+            })
+        })
+    }
+}
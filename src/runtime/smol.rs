@@ -0,0 +1,88 @@
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::{io::Result, net::SocketAddr, time::Duration};
+
+#[cfg(unix)]
+use smol::net::unix::UnixListener;
+use smol::net::TcpListener;
+
+#[cfg(unix)]
+use super::peer_uid;
+use super::{BoxFuture, Connection, Listener, Runtime};
+
+/// [`Runtime`] backed by `smol`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SmolRuntime;
+
+impl Runtime for SmolRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        smol::spawn(future).detach();
+    }
+
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+        smol::unblock(f).detach();
+    }
+
+    fn block_on(&self, future: BoxFuture<'_, Result<()>>) -> Result<()> {
+        smol::block_on(future)
+    }
+
+    fn bind_tcp(&self, addr: SocketAddr) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        Box::pin(async move {
+            let listener = TcpListener::bind(addr).await?;
+            Ok(Box::new(listener) as Box<dyn Listener>)
+        })
+    }
+
+    #[cfg(unix)]
+    fn bind_unix(&self, path: PathBuf) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        Box::pin(async move {
+            let listener = UnixListener::bind(path)?;
+            Ok(Box::new(listener) as Box<dyn Listener>)
+        })
+    }
+
+    fn wrap_tcp(&self, listener: std::net::TcpListener) -> Result<Box<dyn Listener>> {
+        Ok(Box::new(TcpListener::try_from(listener)?))
+    }
+
+    #[cfg(unix)]
+    fn wrap_unix(&self, listener: std::os::unix::net::UnixListener) -> Result<Box<dyn Listener>> {
+        Ok(Box::new(UnixListener::try_from(listener)?))
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            smol::Timer::after(duration).await;
+        })
+    }
+}
+
+impl Listener for TcpListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(async move {
+            let (stream, peer_addr) = TcpListener::accept(self).await?;
+            Ok(Connection {
+                peer_ip: Some(peer_addr.ip()),
+                ..Connection::new(stream.clone(), stream, peer_addr.to_string())
+            })
+        })
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        TcpListener::local_addr(self).ok()
+    }
+}
+
+#[cfg(unix)]
+impl Listener for UnixListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(async move {
+            let (stream, peer_addr) = UnixListener::accept(self).await?;
+            Ok(Connection {
+                peer_uid: peer_uid(&stream),
+                ..Connection::new(stream.clone(), stream, format!("{:?}", peer_addr))
+            })
+        })
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Result, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    pin::Pin,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, OnceLock,
+    },
+    task::{Context, Poll},
+    thread::Builder,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+};
+
+use async_channel::{bounded, Receiver, Sender};
+use futures_lite::{
+    future::{block_on, ready},
+    io::{AsyncRead, AsyncWrite},
+};
+
+#[cfg(unix)]
+use super::peer_uid;
+use super::{BoxFuture, Connection, Listener, Runtime};
+
+/// [`Runtime`] which does not depend on an async runtime. It uses `std::net` listeners and streams and spawns an OS
+/// thread for every task (i.e., for every connection and for every in-flight `CheckTx` request).
+///
+/// # Limitations
+///
+/// - Sockets are in blocking mode. Accepting a connection and reading from (or writing to) a stream block the thread
+///   of current task until the operation completes, even when called inside a `select`-like combinator. A blocked
+///   read is only interrupted by shutting down the socket.
+/// - There is no thread pool. Every spawned task starts a new OS thread, so the number of threads grows with the
+///   number of connections and in-flight requests. Servers therefore use a lower default limit of in-flight `CheckTx`
+///   requests (see
+///   [`DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX`](crate::async_api::DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX)).
+/// - Blocking functions passed to [`Runtime::spawn_blocking`] are executed inline, on the thread of current task.
+///
+/// Sleeps (e.g., for deadlines of ABCI methods) do not start new threads. All of them are served by a single timer
+/// thread which is started on first use.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdRuntime;
+
+impl Runtime for StdRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        std::thread::spawn(move || block_on(future));
+    }
+
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+        // Every task already runs on its own thread
+        f()
+    }
+
+    fn is_thread_per_task(&self) -> bool {
+        true
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let receiver = Timer::get().schedule(Instant::now() + duration);
+
+        Box::pin(async move {
+            let _ = receiver.recv().await;
+        })
+    }
+
+    fn block_on(&self, future: BoxFuture<'_, Result<()>>) -> Result<()> {
+        block_on(future)
+    }
+
+    fn bind_tcp(&self, addr: SocketAddr) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        Box::pin(ready(TcpListener::bind(addr).map(|listener| {
+            Box::new(BlockingTcpListener::new(listener)) as Box<dyn Listener>
+        })))
+    }
+
+    #[cfg(unix)]
+    fn bind_unix(&self, path: PathBuf) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        Box::pin(ready(
+            UnixListener::bind(path).map(|listener| Box::new(listener) as Box<dyn Listener>),
+        ))
+    }
+
+    fn wrap_tcp(&self, listener: TcpListener) -> Result<Box<dyn Listener>> {
+        // Inherited listeners may be in non-blocking mode
+        listener.set_nonblocking(false)?;
+        Ok(Box::new(BlockingTcpListener::new(listener)))
+    }
+
+    #[cfg(unix)]
+    fn wrap_unix(&self, listener: UnixListener) -> Result<Box<dyn Listener>> {
+        listener.set_nonblocking(false)?;
+        Ok(Box::new(listener))
+    }
+}
+
+/// Timer thread shared by all the sleeps of [`StdRuntime`]
+struct Timer {
+    sender: mpsc::Sender<(Instant, Sender<()>)>,
+}
+
+impl Timer {
+    /// Returns the timer, starting its thread if it is not running yet
+    fn get() -> &'static Self {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+
+        TIMER.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel();
+
+            Builder::new()
+                .name("abci-timer".to_string())
+                .spawn(move || run_timer(receiver))
+                .expect("Failed to spawn timer thread");
+
+            Self { sender }
+        })
+    }
+
+    /// Returns a receiver which receives a message (or is closed) once `deadline` is reached
+    fn schedule(&self, deadline: Instant) -> Receiver<()> {
+        let (sender, receiver) = bounded(1);
+
+        // Dropping the sender closes the channel, so a stopped timer thread wakes up the sleep instead of blocking it
+        let _ = self.sender.send((deadline, sender));
+
+        receiver
+    }
+}
+
+/// Wakes up sleeps when their deadlines are reached. Sleeps are ordered by deadline (and then, by the order in which
+/// they were scheduled).
+fn run_timer(receiver: mpsc::Receiver<(Instant, Sender<()>)>) {
+    let mut sleeps: BTreeMap<(Instant, u64), Sender<()>> = BTreeMap::new();
+    let mut next_id = 0;
+
+    loop {
+        let now = Instant::now();
+
+        while let Some(entry) = sleeps.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+
+            // Sleep may have been dropped already
+            let _ = entry.remove().try_send(());
+        }
+
+        let scheduled = match sleeps.keys().next() {
+            Some((deadline, _)) => receiver.recv_timeout(deadline.saturating_duration_since(now)),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match scheduled {
+            Ok((deadline, sender)) => {
+                sleeps.insert((deadline, next_id), sender);
+                next_id += 1;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+/// TCP listener which applies its read timeout (if any) to accepted streams
+struct BlockingTcpListener {
+    listener: TcpListener,
+    read_timeout: Option<Duration>,
+}
+
+impl BlockingTcpListener {
+    fn new(listener: TcpListener) -> Self {
+        Self {
+            listener,
+            read_timeout: None,
+        }
+    }
+
+    fn accept_stream(&self) -> Result<Connection> {
+        let (stream, peer_addr) = self.listener.accept()?;
+        stream.set_read_timeout(self.read_timeout)?;
+
+        let (reader, writer) = BlockingStream::<TcpStream>::split(stream);
+        let stream = reader.stream.clone();
+
+        Ok(Connection {
+            peer_ip: Some(peer_addr.ip()),
+            read_timeout_setter: Some(Box::new(move |timeout| stream.set_read_timeout(timeout))),
+            ..Connection::new(reader, writer, peer_addr.to_string())
+        })
+    }
+}
+
+impl Listener for BlockingTcpListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(ready(self.accept_stream()))
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+}
+
+#[cfg(unix)]
+impl Listener for UnixListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(ready(UnixListener::accept(self).map(
+            |(stream, peer_addr)| {
+                let peer_uid = peer_uid(&stream);
+                let (reader, writer) = BlockingStream::<UnixStream>::split(stream);
+
+                Connection {
+                    peer_uid,
+                    ..Connection::new(reader, writer, format!("{:?}", peer_addr))
+                }
+            },
+        )))
+    }
+}
+
+/// Wraps a blocking `std` stream so that it can be used by async tasks.
+///
+/// Reads and writes block the current thread. This is fine because every task runs on its own thread with
+/// [`StdRuntime`].
+struct BlockingStream<S> {
+    stream: Arc<S>,
+}
+
+impl<S> BlockingStream<S> {
+    /// Returns reader and writer halves of the stream
+    fn split(stream: S) -> (Self, Self) {
+        let stream = Arc::new(stream);
+
+        (
+            Self {
+                stream: stream.clone(),
+            },
+            Self { stream },
+        )
+    }
+}
+
+impl<S> AsyncRead for BlockingStream<S>
+where
+    for<'a> &'a S: Read,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        Poll::Ready((&*self.stream).read(buf))
+    }
+}
+
+impl<S> AsyncWrite for BlockingStream<S>
+where
+    for<'a> &'a S: Write,
+{
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        Poll::Ready((&*self.stream).write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready((&*self.stream).flush())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_sleep() {
+        let runtime = StdRuntime;
+        let start = Instant::now();
+
+        // A later sleep with an earlier deadline does not wait for the earlier one
+        let long = runtime.sleep(Duration::from_millis(200));
+        let short = runtime.sleep(Duration::from_millis(50));
+
+        block_on(short);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        block_on(long);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // Dropped sleeps do not affect the others
+        drop(runtime.sleep(Duration::from_millis(10)));
+        block_on(runtime.sleep(Duration::from_millis(20)));
+    }
+}
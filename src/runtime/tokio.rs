@@ -0,0 +1,173 @@
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::{io::Result, net::SocketAddr, sync::Arc, time::Duration};
+
+use futures_lite::future::ready;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::{
+    net::TcpListener,
+    runtime::{Handle, Runtime as Tokio},
+};
+
+#[cfg(unix)]
+use super::peer_uid;
+use super::{BoxFuture, Compat, Connection, Listener, Runtime};
+
+/// [`Runtime`] backed by `tokio`
+#[derive(Debug, Clone)]
+pub struct TokioRuntime {
+    handle: Handle,
+    /// Runtime owned by this instance (if any)
+    runtime: Option<Arc<Tokio>>,
+}
+
+impl TokioRuntime {
+    /// Creates a new instance of [`TokioRuntime`] which owns a new multi-threaded `tokio` runtime
+    pub fn new() -> Result<Self> {
+        let runtime = Tokio::new()?;
+
+        Ok(Self {
+            handle: runtime.handle().clone(),
+            runtime: Some(Arc::new(runtime)),
+        })
+    }
+
+    /// Creates a new instance of [`TokioRuntime`] which uses an existing `tokio` runtime
+    pub fn from_handle(handle: Handle) -> Self {
+        Self {
+            handle,
+            runtime: None,
+        }
+    }
+}
+
+impl Default for TokioRuntime {
+    /// Uses current `tokio` runtime when called from within a runtime and creates a new one otherwise
+    fn default() -> Self {
+        match Handle::try_current() {
+            Ok(handle) => Self::from_handle(handle),
+            Err(_) => Self::new().expect("Unable to create tokio runtime"),
+        }
+    }
+}
+
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        self.handle.spawn(future);
+    }
+
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+        self.handle.spawn_blocking(f);
+    }
+
+    fn block_on(&self, future: BoxFuture<'_, Result<()>>) -> Result<()> {
+        match self.runtime {
+            Some(ref runtime) => runtime.block_on(future),
+            None => self.handle.block_on(future),
+        }
+    }
+
+    fn bind_tcp(&self, addr: SocketAddr) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        let listener =
+            std::net::TcpListener::bind(addr).and_then(|listener| self.wrap_tcp(listener));
+
+        Box::pin(ready(listener))
+    }
+
+    #[cfg(unix)]
+    fn bind_unix(&self, path: PathBuf) -> BoxFuture<'static, Result<Box<dyn Listener>>> {
+        let _guard = self.handle.enter();
+        let listener = UnixListener::bind(path);
+
+        Box::pin(ready(
+            listener.map(|listener| Box::new(listener) as Box<dyn Listener>),
+        ))
+    }
+
+    fn wrap_tcp(&self, listener: std::net::TcpListener) -> Result<Box<dyn Listener>> {
+        listener.set_nonblocking(true)?;
+
+        let _guard = self.handle.enter();
+        let listener = TcpListener::from_std(listener)?;
+
+        Ok(Box::new(listener))
+    }
+
+    #[cfg(unix)]
+    fn wrap_unix(&self, listener: std::os::unix::net::UnixListener) -> Result<Box<dyn Listener>> {
+        listener.set_nonblocking(true)?;
+
+        let _guard = self.handle.enter();
+        let listener = UnixListener::from_std(listener)?;
+
+        Ok(Box::new(listener))
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let _guard = self.handle.enter();
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+impl Listener for TcpListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(async move {
+            let (stream, peer_addr) = TcpListener::accept(self).await?;
+            let (reader, writer) = stream.into_split();
+
+            Ok(Connection {
+                peer_ip: Some(peer_addr.ip()),
+                ..Connection::new(
+                    Compat::new(reader),
+                    Compat::new(writer),
+                    peer_addr.to_string(),
+                )
+            })
+        })
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        TcpListener::local_addr(self).ok()
+    }
+}
+
+#[cfg(unix)]
+impl Listener for UnixListener {
+    fn accept(&mut self) -> BoxFuture<'_, Result<Connection>> {
+        Box::pin(async move {
+            let (stream, peer_addr) = UnixListener::accept(self).await?;
+            let peer_uid = peer_uid(&stream);
+            let (reader, writer) = stream.into_split();
+
+            Ok(Connection {
+                peer_uid,
+                ..Connection::new(
+                    Compat::new(reader),
+                    Compat::new(writer),
+                    format!("{:?}", peer_addr),
+                )
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn check_spawn_with_handle_and_unblock() {
+        let runtime: Arc<dyn Runtime> = Arc::new(TokioRuntime::default());
+
+        let handles: Vec<_> = (0..10u64)
+            .map(|i| runtime.spawn_with_handle(async move { i * 2 }))
+            .collect();
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(Some(i as u64 * 2), handle.await);
+        }
+
+        assert_eq!(42, runtime.unblock(|| 42).await);
+    }
+}
@@ -1,8 +1,21 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
 use crate::types::*;
 
 #[derive(Debug, Default)]
 pub struct ConsensusStateValidator {
     state: ConsensusState,
+    /// Number of `DeliverTx` requests received for current block
+    delivered_txs: u64,
+    /// Time of last successful commit
+    last_commit: Option<SystemTime>,
+    /// Last protocol violation (i.e., an out of order request or an invalid response) seen by validator
+    protocol_violation: Option<String>,
+    /// Copy of the state of validator which is updated whenever validator records a request or a response
+    snapshot: Arc<StateSnapshotCell>,
 }
 
 impl ConsensusStateValidator {
@@ -18,10 +31,85 @@ impl ConsensusStateValidator {
                     app_hash: info_response.last_block_app_hash.clone(),
                 };
             }
+
+            self.publish();
+        }
+    }
+
+    /// Returns a handle to the snapshot of state of validator which can be read without locking the validator
+    pub fn snapshot(&self) -> Arc<StateSnapshotCell> {
+        self.snapshot.clone()
+    }
+
+    /// Returns the height of block being executed
+    pub fn block_height(&self) -> Option<i64> {
+        match self.state {
+            ConsensusState::ExecutingBlock { block_height, .. } => Some(block_height),
+            _ => None,
         }
     }
 
     pub fn on_init_chain_request(&mut self) -> Result<(), String> {
+        let result = self.validate_init_chain_request();
+        self.record(result)
+    }
+
+    pub fn on_begin_block_request(
+        &mut self,
+        begin_block_request: &RequestBeginBlock,
+    ) -> Result<(), String> {
+        let result = self.validate_begin_block_request(begin_block_request);
+        self.record(result)
+    }
+
+    /// Validates a `DeliverTx` request and returns the index of transaction in current block
+    pub fn on_deliver_tx_request(&mut self) -> Result<u64, String> {
+        let result = self.validate_deliver_tx_request();
+        self.record(result)
+    }
+
+    pub fn on_end_block_request(
+        &mut self,
+        end_block_request: &RequestEndBlock,
+    ) -> Result<(), String> {
+        let result = self.validate_end_block_request(end_block_request);
+        self.record(result)
+    }
+
+    pub fn on_commit_request(&mut self) -> Result<(), String> {
+        let result = self.validate_commit_request();
+        self.record(result)
+    }
+
+    pub fn on_commit_response(&mut self, commit_response: &ResponseCommit) -> Result<(), String> {
+        let result = self.validate_commit_response(commit_response);
+
+        if result.is_ok() {
+            self.last_commit = Some(SystemTime::now());
+        }
+
+        self.record(result)
+    }
+
+    fn record<T>(&mut self, result: Result<T, String>) -> Result<T, String> {
+        if let Err(ref error) = result {
+            self.protocol_violation = Some(error.clone());
+        }
+
+        self.publish();
+
+        result
+    }
+
+    fn publish(&self) {
+        self.snapshot.set(StateSnapshot {
+            state: self.state.clone(),
+            last_commit: self.last_commit,
+            protocol_violation: self.protocol_violation.clone(),
+        });
+    }
+
+    fn validate_init_chain_request(&mut self) -> Result<(), String> {
         if self.state != ConsensusState::NotInitialized {
             return Err("Received `InitChain` call when chain is already initialized".to_string());
         }
@@ -30,7 +118,7 @@ impl ConsensusStateValidator {
         Ok(())
     }
 
-    pub fn on_begin_block_request(
+    fn validate_begin_block_request(
         &mut self,
         begin_block_request: &RequestBeginBlock,
     ) -> Result<(), String> {
@@ -85,16 +173,24 @@ impl ConsensusStateValidator {
         };
 
         self.state = new_state;
+        self.delivered_txs = 0;
 
         Ok(())
     }
 
-    pub fn on_deliver_tx_request(&mut self) -> Result<(), String> {
+    fn validate_deliver_tx_request(&mut self) -> Result<u64, String> {
         match self.state {
             ConsensusState::ExecutingBlock {
                 ref mut execution_state,
                 ..
-            } => execution_state.validate(BlockExecutionState::DeliverTx),
+            } => {
+                execution_state.validate(BlockExecutionState::DeliverTx)?;
+
+                let tx_index = self.delivered_txs;
+                self.delivered_txs += 1;
+
+                Ok(tx_index)
+            }
             _ => Err(format!(
                 "`DeliverTx` cannot be called after {:?}",
                 self.state
@@ -102,7 +198,7 @@ impl ConsensusStateValidator {
         }
     }
 
-    pub fn on_end_block_request(
+    fn validate_end_block_request(
         &mut self,
         end_block_request: &RequestEndBlock,
     ) -> Result<(), String> {
@@ -130,7 +226,7 @@ impl ConsensusStateValidator {
     }
 
     #[inline]
-    pub fn on_commit_request(&mut self) -> Result<(), String> {
+    fn validate_commit_request(&mut self) -> Result<(), String> {
         match self.state {
             ConsensusState::ExecutingBlock {
                 ref mut execution_state,
@@ -140,7 +236,7 @@ impl ConsensusStateValidator {
         }
     }
 
-    pub fn on_commit_response(&mut self, commit_response: &ResponseCommit) -> Result<(), String> {
+    fn validate_commit_response(&mut self, commit_response: &ResponseCommit) -> Result<(), String> {
         let new_state = match self.state {
             ConsensusState::ExecutingBlock {
                 execution_state: BlockExecutionState::Commit,
@@ -158,8 +254,38 @@ impl ConsensusStateValidator {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// State of [`ConsensusStateValidator`] at a point in time
+#[derive(Debug, Clone, Default)]
+pub struct StateSnapshot {
+    pub state: ConsensusState,
+    /// Time of last successful commit
+    pub last_commit: Option<SystemTime>,
+    /// Last protocol violation seen by validator
+    pub protocol_violation: Option<String>,
+}
+
+/// Holds the latest [`StateSnapshot`] of a validator. Unlike the validator itself (which stays locked while the
+/// application executes a consensus request), the snapshot is only locked while it is copied, so, it can be read at
+/// any time (e.g., by health checks or when a request exceeds its deadline).
+#[derive(Debug, Default)]
+pub struct StateSnapshotCell {
+    inner: Mutex<StateSnapshot>,
+}
+
+impl StateSnapshotCell {
+    /// Returns the latest snapshot
+    pub fn get(&self) -> StateSnapshot {
+        self.inner.lock().expect("Lock poisoned").clone()
+    }
+
+    fn set(&self, snapshot: StateSnapshot) {
+        *self.inner.lock().expect("Lock poisoned") = snapshot;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum ConsensusState {
+    #[default]
     NoInfo,
     NotInitialized,
     InitChain,
@@ -173,9 +299,25 @@ pub enum ConsensusState {
     },
 }
 
-impl Default for ConsensusState {
-    fn default() -> Self {
-        Self::NoInfo
+impl ConsensusState {
+    /// Returns the name of state (used in health reports)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NoInfo => "no_info",
+            Self::NotInitialized => "not_initialized",
+            Self::InitChain => "init_chain",
+            Self::WaitingForBlock { .. } => "waiting_for_block",
+            Self::ExecutingBlock { .. } => "executing_block",
+        }
+    }
+
+    /// Returns the height of block which is being executed or expected next
+    pub fn block_height(&self) -> Option<i64> {
+        match self {
+            Self::WaitingForBlock { block_height, .. }
+            | Self::ExecutingBlock { block_height, .. } => Some(*block_height),
+            _ => None,
+        }
     }
 }
 
@@ -206,3 +348,25 @@ impl BlockExecutionState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_state_snapshot() {
+        let mut validator = ConsensusStateValidator::default();
+        let snapshot = validator.snapshot();
+
+        validator.on_info_response(&Default::default());
+        assert_eq!(ConsensusState::NotInitialized, snapshot.get().state);
+
+        validator.on_init_chain_request().unwrap();
+        assert!(validator.on_commit_request().is_err());
+
+        let state_snapshot = snapshot.get();
+        assert_eq!(ConsensusState::InitChain, state_snapshot.state);
+        assert!(state_snapshot.last_commit.is_none());
+        assert!(state_snapshot.protocol_violation.is_some());
+    }
+}
@@ -1,32 +1,20 @@
-#[cfg(all(unix, feature = "use-async-std"))]
-use async_std::os::unix::net::UnixStream;
-#[cfg(feature = "use-async-std")]
-use async_std::{
-    io::{Read, Write},
-    net::TcpStream,
+#[cfg(feature = "tls")]
+use std::{
+    io::{self, Read as _, Write as _},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
 };
+
+use futures_lite::io::{AsyncRead as Read, AsyncWrite as Write};
+#[cfg(feature = "tls")]
+use futures_rustls::{rustls::ServerConnection, server::TlsStream};
 #[cfg(test)]
 use mock_io::tokio::{MockStream, ReadHalf as MockReadHalf, WriteHalf as MockWriteHalf};
-#[cfg(all(unix, feature = "use-smol"))]
-use smol::net::unix::UnixStream;
-#[cfg(feature = "use-smol")]
-use smol::{
-    io::{AsyncRead as Read, AsyncWrite as Write},
-    net::TcpStream,
-};
-#[cfg(all(unix, feature = "use-tokio"))]
-use tokio::net::{
-    unix::{OwnedReadHalf as UnixReadHalf, OwnedWriteHalf as UnixWriteHalf},
-    UnixStream,
-};
-#[cfg(feature = "use-tokio")]
-use tokio::{
-    io::{AsyncRead as Read, AsyncWrite as Write},
-    net::{
-        tcp::{OwnedReadHalf, OwnedWriteHalf},
-        TcpStream,
-    },
-};
+
+#[cfg(test)]
+use crate::runtime::Compat;
+use crate::runtime::{BoxReader, BoxWriter, Connection};
 
 pub trait StreamSplit {
     type Reader: Read + Send + Unpin + 'static;
@@ -36,52 +24,185 @@ pub trait StreamSplit {
     fn split_stream(self) -> (Self::Reader, Self::Writer);
 }
 
-#[cfg(any(feature = "use-async-std", feature = "use-smol"))]
-impl StreamSplit for TcpStream {
-    type Reader = Self;
-    type Writer = Self;
+impl StreamSplit for Connection {
+    type Reader = BoxReader;
+    type Writer = BoxWriter;
 
     fn split_stream(self) -> (Self::Reader, Self::Writer) {
-        (self.clone(), self)
+        (self.reader, self.writer)
     }
 }
 
-#[cfg(feature = "use-tokio")]
-impl StreamSplit for TcpStream {
-    type Reader = OwnedReadHalf;
-    type Writer = OwnedWriteHalf;
+#[cfg(feature = "tls")]
+impl StreamSplit for TlsStream<Connection> {
+    type Reader = TlsReader;
+    type Writer = TlsWriter;
 
     fn split_stream(self) -> (Self::Reader, Self::Writer) {
-        self.into_split()
+        let (connection, session) = self.into_inner();
+        let session = Arc::new(Mutex::new(session));
+
+        (
+            TlsReader {
+                reader: connection.reader,
+                session: session.clone(),
+                read_buf: [0; TLS_READ_BUFFER_SIZE],
+            },
+            TlsWriter {
+                writer: connection.writer,
+                session,
+                pending: Vec::new(),
+                closing: false,
+            },
+        )
     }
 }
 
-#[cfg(all(unix, any(feature = "use-async-std", feature = "use-smol")))]
-impl StreamSplit for UnixStream {
-    type Reader = Self;
-    type Writer = Self;
+#[cfg(feature = "tls")]
+const TLS_READ_BUFFER_SIZE: usize = 4096;
 
-    fn split_stream(self) -> (Self::Reader, Self::Writer) {
-        (self.clone(), self)
+/// Reader half of a TLS stream
+///
+/// Both halves share the TLS session, but, unlike `futures_lite::io::split`, its lock is never held while reading from
+/// (or writing to) the underlying stream. So, a pending read (which blocks the thread on `StdRuntime`) does not stop
+/// the writer half from writing responses.
+#[cfg(feature = "tls")]
+pub struct TlsReader {
+    reader: BoxReader,
+    session: Arc<Mutex<ServerConnection>>,
+    read_buf: [u8; TLS_READ_BUFFER_SIZE],
+}
+
+#[cfg(feature = "tls")]
+impl Read for TlsReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        loop {
+            match lock(&this.session).reader().read(buf) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                result => return Poll::Ready(result),
+            }
+
+            let len = ready!(Pin::new(&mut this.reader).poll_read(cx, &mut this.read_buf))?;
+
+            let mut session = lock(&this.session);
+            let mut received = &this.read_buf[..len];
+
+            // Reading an empty slice records EOF of the underlying stream in session
+            loop {
+                session.read_tls(&mut received)?;
+                session
+                    .process_new_packets()
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+                if received.is_empty() {
+                    break;
+                }
+            }
+        }
     }
 }
 
-#[cfg(all(unix, feature = "use-tokio"))]
-impl StreamSplit for UnixStream {
-    type Reader = UnixReadHalf;
-    type Writer = UnixWriteHalf;
+/// Writer half of a TLS stream (see [`TlsReader`])
+///
+/// TLS records are written to the underlying stream after releasing the lock of session. Records which are not yet
+/// written are kept in a buffer and written before any new data.
+#[cfg(feature = "tls")]
+pub struct TlsWriter {
+    writer: BoxWriter,
+    session: Arc<Mutex<ServerConnection>>,
+    pending: Vec<u8>,
+    /// Whether `close_notify` alert is already queued
+    closing: bool,
+}
+
+#[cfg(feature = "tls")]
+impl TlsWriter {
+    /// Moves TLS records of session (e.g., encrypted data or alerts) to the buffer of pending records
+    fn take_records(&mut self) -> io::Result<()> {
+        let mut session = lock(&self.session);
 
-    fn split_stream(self) -> (Self::Reader, Self::Writer) {
-        self.into_split()
+        while session.wants_write() {
+            session.write_tls(&mut self.pending)?;
+        }
+
+        Ok(())
     }
+
+    /// Writes all the pending records to the underlying stream
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let len = ready!(Pin::new(&mut self.writer).poll_write(cx, &self.pending))?;
+
+            if len == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+
+            self.pending.drain(..len);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Write for TlsWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        ready!(this.poll_write_pending(cx))?;
+
+        let len = lock(&this.session).writer().write(buf)?;
+        this.take_records()?;
+
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        this.take_records()?;
+        ready!(this.poll_write_pending(cx))?;
+
+        Pin::new(&mut this.writer).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if !this.closing {
+            lock(&this.session).send_close_notify();
+            this.closing = true;
+        }
+
+        this.take_records()?;
+        ready!(this.poll_write_pending(cx))?;
+
+        Pin::new(&mut this.writer).poll_close(cx)
+    }
+}
+
+#[cfg(feature = "tls")]
+fn lock(session: &Mutex<ServerConnection>) -> std::sync::MutexGuard<'_, ServerConnection> {
+    session.lock().expect("Lock poisoned")
 }
 
 #[cfg(test)]
 impl StreamSplit for MockStream {
-    type Reader = MockReadHalf;
-    type Writer = MockWriteHalf;
+    type Reader = Compat<MockReadHalf>;
+    type Writer = Compat<MockWriteHalf>;
 
     fn split_stream(self) -> (Self::Reader, Self::Writer) {
-        self.split()
+        let (reader, writer) = self.split();
+        (Compat::new(reader), Compat::new(writer))
     }
 }
@@ -1,9 +1,11 @@
 //! Synchronous ABCI server API implementation
 mod application;
 mod async_impls;
+mod executor;
 mod server;
 
 pub use self::{
-    application::{Consensus, Info, Mempool, Snapshot},
-    server::Server,
+    application::{Consensus, ConsensusMut, Info, Mempool, Snapshot},
+    executor::ExecutionMode,
+    server::{BoundServer, Server},
 };
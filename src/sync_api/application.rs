@@ -77,6 +77,15 @@ pub trait Consensus {
     /// Execute the transaction in full. The workhorse of the application.
     fn deliver_tx(&self, deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx;
 
+    /// Returns the set of keys read and written by a transaction, or `None` if it is not known.
+    ///
+    /// Only used when parallel execution of transactions is enabled on server (see
+    /// [`Server::with_parallel_deliver_tx`](super::Server::with_parallel_deliver_tx)). See
+    /// [`async_api::Consensus::deliver_tx_access`](crate::async_api::Consensus::deliver_tx_access) for more details.
+    fn deliver_tx_access(&self, _deliver_tx_request: &RequestDeliverTx) -> Option<TxAccess> {
+        None
+    }
+
     /// Signals the end of a block. Called after all transactions, prior to each [`commit`](self::Consensus::commit).
     fn end_block(&self, end_block_request: RequestEndBlock) -> ResponseEndBlock;
 
@@ -112,6 +121,100 @@ pub trait Consensus {
     }
 }
 
+/// Trait for managing consensus of blockchain which takes mutable reference of `self`.
+///
+/// # Details
+///
+/// This is a variant of [`Consensus`] for applications which own their `consensus_state`. Because calls on consensus
+/// connection are strictly sequential, the server takes ownership of the implementation and calls its methods with an
+/// exclusive reference, so the `consensus_state` does not need to be wrapped in a `Mutex`. Unlike [`Consensus`],
+/// implementations of this trait only need to be `Send`.
+///
+/// When used with [`ExecutionMode::DedicatedThread`](super::ExecutionMode::DedicatedThread), the implementation is
+/// owned by the dedicated thread of consensus connection and no locking is involved at all. Otherwise, calls are
+/// serialized using a `Mutex`.
+///
+/// Every implementation of [`Consensus`] (which is `Send + Sync`) is also an implementation of [`ConsensusMut`]. So,
+/// [`Server`](super::Server) accepts implementations of both the traits. Implementations of [`Consensus`] are still
+/// called through a shared reference, without any locking, in both the execution modes.
+///
+/// [`ConsensusMut`]: self::ConsensusMut
+pub trait ConsensusMut {
+    /// Echo a string to test abci client/server implementation.
+    fn echo(&mut self, echo_request: RequestEcho) -> ResponseEcho {
+        ResponseEcho {
+            message: echo_request.message,
+        }
+    }
+
+    /// Called once upon genesis. Usually used to establish initial (genesis) state.
+    fn init_chain(&mut self, init_chain_request: RequestInitChain) -> ResponseInitChain;
+
+    /// Signals the beginning of a new block. Called prior to any [`deliver_tx`](self::ConsensusMut::deliver_tx)s.
+    fn begin_block(&mut self, begin_block_request: RequestBeginBlock) -> ResponseBeginBlock;
+
+    /// Execute the transaction in full. The workhorse of the application.
+    fn deliver_tx(&mut self, deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx;
+
+    /// Signals the end of a block. Called after all transactions, prior to each
+    /// [`commit`](self::ConsensusMut::commit).
+    fn end_block(&mut self, end_block_request: RequestEndBlock) -> ResponseEndBlock;
+
+    /// Persist the application state. See [`Consensus::commit`](self::Consensus::commit) for more details.
+    fn commit(&mut self, commit_request: RequestCommit) -> ResponseCommit;
+
+    /// Signals that messages queued on the client should be flushed to the server.
+    fn flush(&mut self, _flush_request: RequestFlush) -> ResponseFlush {
+        Default::default()
+    }
+
+    /// Returns `self` as a [`Consensus`] implementation (which does not need exclusive access), if it is one
+    #[doc(hidden)]
+    fn into_consensus(self) -> Result<Box<dyn Consensus + Send + Sync>, Self>
+    where
+        Self: Sized,
+    {
+        Err(self)
+    }
+}
+
+impl<C> ConsensusMut for C
+where
+    C: Consensus + Send + Sync + 'static,
+{
+    fn echo(&mut self, echo_request: RequestEcho) -> ResponseEcho {
+        Consensus::echo(self, echo_request)
+    }
+
+    fn init_chain(&mut self, init_chain_request: RequestInitChain) -> ResponseInitChain {
+        Consensus::init_chain(self, init_chain_request)
+    }
+
+    fn begin_block(&mut self, begin_block_request: RequestBeginBlock) -> ResponseBeginBlock {
+        Consensus::begin_block(self, begin_block_request)
+    }
+
+    fn deliver_tx(&mut self, deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx {
+        Consensus::deliver_tx(self, deliver_tx_request)
+    }
+
+    fn end_block(&mut self, end_block_request: RequestEndBlock) -> ResponseEndBlock {
+        Consensus::end_block(self, end_block_request)
+    }
+
+    fn commit(&mut self, commit_request: RequestCommit) -> ResponseCommit {
+        Consensus::commit(self, commit_request)
+    }
+
+    fn flush(&mut self, flush_request: RequestFlush) -> ResponseFlush {
+        Consensus::flush(self, flush_request)
+    }
+
+    fn into_consensus(self) -> Result<Box<dyn Consensus + Send + Sync>, Self> {
+        Ok(Box::new(self))
+    }
+}
+
 /// Trait for managing tendermint's mempool.
 ///
 /// # Details
@@ -152,6 +255,53 @@ pub trait Mempool {
     /// Technically optional - not involved in processing blocks.
     fn check_tx(&self, check_tx_request: RequestCheckTx) -> ResponseCheckTx;
 
+    /// Checks a batch of transactions at once (e.g., to verify their signatures in a single batch). Responses should
+    /// be returned in the same order as requests.
+    ///
+    /// This is only called when batching is enabled on server (see
+    /// [`Server::with_check_tx_batch_limit`](super::Server::with_check_tx_batch_limit)). Default implementation calls
+    /// [`check_tx`](self::Mempool::check_tx) for each request.
+    fn check_tx_batch(&self, check_tx_requests: Vec<RequestCheckTx>) -> Vec<ResponseCheckTx> {
+        check_tx_requests
+            .into_iter()
+            .map(|check_tx_request| self.check_tx(check_tx_request))
+            .collect()
+    }
+
+    /// Returns a fingerprint of the state relevant for validating a transaction (e.g., nonce and balance of its sender),
+    /// or `None` if the response of transaction should not be cached.
+    ///
+    /// This is only called when recheck cache is enabled on server (see
+    /// [`Server::with_recheck_cache`](super::Server::with_recheck_cache)). When a transaction is rechecked and its
+    /// fingerprint is the same as the one returned when it was last checked, server responds with the cached response
+    /// without calling [`check_tx`](self::Mempool::check_tx).
+    ///
+    /// Because [`check_tx`](self::Mempool::check_tx) is skipped on a cache hit, any changes it makes to
+    /// `mempool_state` (e.g., incrementing nonce of sender) are lost unless the fingerprint covers all the state which
+    /// `check_tx` mutates (so that a changed state always causes a cache miss), or those changes are replayed in
+    /// [`on_cached_recheck`](self::Mempool::on_cached_recheck).
+    fn recheck_fingerprint(&self, _check_tx_request: &RequestCheckTx) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Called when a rechecked transaction is answered from recheck cache instead of calling
+    /// [`check_tx`](self::Mempool::check_tx) (see [`recheck_fingerprint`](self::Mempool::recheck_fingerprint)). Can be
+    /// used to replay the changes `check_tx` makes to `mempool_state` for a transaction with given cached response.
+    fn on_cached_recheck(
+        &self,
+        _check_tx_request: &RequestCheckTx,
+        _check_tx_response: &ResponseCheckTx,
+    ) {
+    }
+
+    /// Called after each successful [`commit`](self::Consensus::commit). Can be used to reset `mempool_state` to the
+    /// **latest committed state**.
+    ///
+    /// When commit barrier is enabled on server (see
+    /// [`Server::with_commit_barrier`](super::Server::with_commit_barrier)), execution of `CheckTx` requests is paused
+    /// until this hook completes, so the reset is atomic with the commit.
+    fn on_commit(&self, _commit_response: &ResponseCommit) {}
+
     /// Signals that messages queued on the client should be flushed to the server.
     fn flush(&self, _flush_request: RequestFlush) -> ResponseFlush {
         Default::default()
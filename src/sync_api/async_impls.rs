@@ -1,90 +1,112 @@
-use std::sync::Arc;
-
-#[cfg(feature = "use-async-std")]
-use async_std::task::spawn_blocking;
 use async_trait::async_trait;
-#[cfg(feature = "use-smol")]
-use smol::unblock as spawn_blocking;
-#[cfg(feature = "use-tokio")]
-use tokio::task::spawn_blocking;
 
 use crate::{
     async_api::{
         Consensus as AsyncConsensus, Info as AsyncInfo, Mempool as AsyncMempool,
         Snapshot as AsyncSnapshot,
     },
-    sync_api::{Consensus, Info, Mempool, Snapshot},
+    sync_api::{
+        executor::{ExclusiveExecutor, ExecutionMode, Executor, Unblocker},
+        Consensus, ConsensusMut, Info, Mempool, Snapshot,
+    },
     types::*,
 };
 
-macro_rules! spawn_blocking {
-    ($expr: expr) => {{
-        cfg_if::cfg_if! {
-            if #[cfg(any(feature = "use-async-std", feature = "use-smol"))] {
-                spawn_blocking($expr).await
-            } else if #[cfg(feature = "use-tokio")] {
-                spawn_blocking($expr).await.expect("Failed to execute blocking task")
-            }
-        }
-    }};
+pub struct AsyncConsensusImpl<C>
+where
+    C: ConsensusMut + Send + 'static,
+{
+    inner: ConsensusExecutor<C>,
 }
 
-pub struct AsyncConsensusImpl<C>
+/// Executes calls to consensus connection. Implementations of [`Consensus`] are called through a shared reference
+/// (without locking) and other implementations of [`ConsensusMut`] through an exclusive reference.
+enum ConsensusExecutor<C>
 where
-    C: Consensus + Send + Sync + 'static,
+    C: Send + 'static,
 {
-    inner: Arc<C>,
+    Shared(Executor<Box<dyn Consensus + Send + Sync>>),
+    Exclusive(ExclusiveExecutor<C>),
 }
 
 impl<C> AsyncConsensusImpl<C>
 where
-    C: Consensus + Send + Sync,
+    C: ConsensusMut + Send,
 {
-    pub fn new(inner: C) -> Self {
-        Self {
-            inner: Arc::new(inner),
-        }
+    pub fn new(inner: C, execution_mode: ExecutionMode, unblocker: Unblocker) -> Self {
+        let inner = match inner.into_consensus() {
+            Ok(inner) => ConsensusExecutor::Shared(Executor::new(
+                inner,
+                execution_mode,
+                "consensus",
+                unblocker,
+            )),
+            Err(inner) => ConsensusExecutor::Exclusive(ExclusiveExecutor::new(
+                inner,
+                execution_mode,
+                "consensus",
+                unblocker,
+            )),
+        };
+
+        Self { inner }
     }
 }
 
+macro_rules! execute_consensus {
+    ($self: ident, $method: ident, $request: ident) => {
+        match $self.inner {
+            ConsensusExecutor::Shared(ref inner) => {
+                inner.execute(move |inner| inner.$method($request)).await
+            }
+            ConsensusExecutor::Exclusive(ref inner) => {
+                inner.execute(move |inner| inner.$method($request)).await
+            }
+        }
+    };
+}
+
 #[async_trait]
 impl<C> AsyncConsensus for AsyncConsensusImpl<C>
 where
-    C: Consensus + Send + Sync + 'static,
+    C: ConsensusMut + Send + 'static,
 {
     async fn echo(&self, echo_request: RequestEcho) -> ResponseEcho {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.echo(echo_request))
+        execute_consensus!(self, echo, echo_request)
     }
 
     async fn init_chain(&self, init_chain_request: RequestInitChain) -> ResponseInitChain {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.init_chain(init_chain_request))
+        execute_consensus!(self, init_chain, init_chain_request)
     }
 
     async fn begin_block(&self, begin_block_request: RequestBeginBlock) -> ResponseBeginBlock {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.begin_block(begin_block_request))
+        execute_consensus!(self, begin_block, begin_block_request)
     }
 
     async fn deliver_tx(&self, deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.deliver_tx(deliver_tx_request))
+        execute_consensus!(self, deliver_tx, deliver_tx_request)
+    }
+
+    fn deliver_tx_access(&self, deliver_tx_request: &RequestDeliverTx) -> Option<TxAccess> {
+        // Transactions can only be executed in parallel when `Consensus` is shared by threads of the thread pool
+        match self.inner {
+            ConsensusExecutor::Shared(Executor::ThreadPool(ref inner, _)) => {
+                inner.deliver_tx_access(deliver_tx_request)
+            }
+            _ => None,
+        }
     }
 
     async fn end_block(&self, end_block_request: RequestEndBlock) -> ResponseEndBlock {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.end_block(end_block_request))
+        execute_consensus!(self, end_block, end_block_request)
     }
 
     async fn commit(&self, commit_request: RequestCommit) -> ResponseCommit {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.commit(commit_request))
+        execute_consensus!(self, commit, commit_request)
     }
 
     async fn flush(&self, flush_request: RequestFlush) -> ResponseFlush {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.flush(flush_request))
+        execute_consensus!(self, flush, flush_request)
     }
 }
 
@@ -92,16 +114,16 @@ pub struct AsyncInfoImpl<I>
 where
     I: Info + Send + Sync + 'static,
 {
-    inner: Arc<I>,
+    inner: Executor<I>,
 }
 
 impl<I> AsyncInfoImpl<I>
 where
     I: Info + Send + Sync,
 {
-    pub fn new(inner: I) -> Self {
+    pub fn new(inner: I, execution_mode: ExecutionMode, unblocker: Unblocker) -> Self {
         Self {
-            inner: Arc::new(inner),
+            inner: Executor::new(inner, execution_mode, "info", unblocker),
         }
     }
 }
@@ -112,28 +134,33 @@ where
     I: Info + Send + Sync + 'static,
 {
     async fn echo(&self, echo_request: RequestEcho) -> ResponseEcho {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.echo(echo_request))
+        self.inner
+            .execute(move |inner| inner.echo(echo_request))
+            .await
     }
 
     async fn info(&self, info_request: RequestInfo) -> ResponseInfo {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.info(info_request))
+        self.inner
+            .execute(move |inner| inner.info(info_request))
+            .await
     }
 
     async fn set_option(&self, set_option_request: RequestSetOption) -> ResponseSetOption {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.set_option(set_option_request))
+        self.inner
+            .execute(move |inner| inner.set_option(set_option_request))
+            .await
     }
 
     async fn query(&self, query_request: RequestQuery) -> ResponseQuery {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.query(query_request))
+        self.inner
+            .execute(move |inner| inner.query(query_request))
+            .await
     }
 
     async fn flush(&self, flush_request: RequestFlush) -> ResponseFlush {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.flush(flush_request))
+        self.inner
+            .execute(move |inner| inner.flush(flush_request))
+            .await
     }
 }
 
@@ -141,16 +168,16 @@ pub struct AsyncMempoolImpl<M>
 where
     M: Mempool + Send + Sync + 'static,
 {
-    inner: Arc<M>,
+    inner: Executor<M>,
 }
 
 impl<M> AsyncMempoolImpl<M>
 where
     M: Mempool + Send + Sync,
 {
-    pub fn new(inner: M) -> Self {
+    pub fn new(inner: M, execution_mode: ExecutionMode, unblocker: Unblocker) -> Self {
         Self {
-            inner: Arc::new(inner),
+            inner: Executor::new(inner, execution_mode, "mempool", unblocker),
         }
     }
 }
@@ -161,18 +188,56 @@ where
     M: Mempool + Send + Sync + 'static,
 {
     async fn echo(&self, echo_request: RequestEcho) -> ResponseEcho {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.echo(echo_request))
+        self.inner
+            .execute(move |inner| inner.echo(echo_request))
+            .await
     }
 
     async fn check_tx(&self, check_tx_request: RequestCheckTx) -> ResponseCheckTx {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.check_tx(check_tx_request))
+        self.inner
+            .execute(move |inner| inner.check_tx(check_tx_request))
+            .await
+    }
+
+    async fn check_tx_batch(&self, check_tx_requests: Vec<RequestCheckTx>) -> Vec<ResponseCheckTx> {
+        self.inner
+            .execute(move |inner| inner.check_tx_batch(check_tx_requests))
+            .await
+    }
+
+    async fn recheck_fingerprint(&self, check_tx_request: &RequestCheckTx) -> Option<Vec<u8>> {
+        let check_tx_request = check_tx_request.clone();
+
+        self.inner
+            .execute(move |inner| inner.recheck_fingerprint(&check_tx_request))
+            .await
+    }
+
+    async fn on_cached_recheck(
+        &self,
+        check_tx_request: &RequestCheckTx,
+        check_tx_response: &ResponseCheckTx,
+    ) {
+        let check_tx_request = check_tx_request.clone();
+        let check_tx_response = check_tx_response.clone();
+
+        self.inner
+            .execute(move |inner| inner.on_cached_recheck(&check_tx_request, &check_tx_response))
+            .await
+    }
+
+    async fn on_commit(&self, commit_response: &ResponseCommit) {
+        let commit_response = commit_response.clone();
+
+        self.inner
+            .execute(move |inner| inner.on_commit(&commit_response))
+            .await
     }
 
     async fn flush(&self, flush_request: RequestFlush) -> ResponseFlush {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.flush(flush_request))
+        self.inner
+            .execute(move |inner| inner.flush(flush_request))
+            .await
     }
 }
 
@@ -180,16 +245,16 @@ pub struct AsyncSnapshotImpl<S>
 where
     S: Snapshot + Send + Sync + 'static,
 {
-    inner: Arc<S>,
+    inner: Executor<S>,
 }
 
 impl<S> AsyncSnapshotImpl<S>
 where
     S: Snapshot + Send + Sync,
 {
-    pub fn new(inner: S) -> Self {
+    pub fn new(inner: S, execution_mode: ExecutionMode, unblocker: Unblocker) -> Self {
         Self {
-            inner: Arc::new(inner),
+            inner: Executor::new(inner, execution_mode, "snapshot", unblocker),
         }
     }
 }
@@ -200,44 +265,107 @@ where
     S: Snapshot + Send + Sync + 'static,
 {
     async fn echo(&self, echo_request: RequestEcho) -> ResponseEcho {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.echo(echo_request))
+        self.inner
+            .execute(move |inner| inner.echo(echo_request))
+            .await
     }
 
     async fn list_snapshots(
         &self,
         list_snapshots_request: RequestListSnapshots,
     ) -> ResponseListSnapshots {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.list_snapshots(list_snapshots_request))
+        self.inner
+            .execute(move |inner| inner.list_snapshots(list_snapshots_request))
+            .await
     }
 
     async fn offer_snapshot(
         &self,
         offer_snapshot_request: RequestOfferSnapshot,
     ) -> ResponseOfferSnapshot {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.offer_snapshot(offer_snapshot_request))
+        self.inner
+            .execute(move |inner| inner.offer_snapshot(offer_snapshot_request))
+            .await
     }
 
     async fn load_snapshot_chunk(
         &self,
         load_snapshot_chunk_request: RequestLoadSnapshotChunk,
     ) -> ResponseLoadSnapshotChunk {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.load_snapshot_chunk(load_snapshot_chunk_request))
+        self.inner
+            .execute(move |inner| inner.load_snapshot_chunk(load_snapshot_chunk_request))
+            .await
     }
 
     async fn apply_snapshot_chunk(
         &self,
         apply_snapshot_chunk_request: RequestApplySnapshotChunk,
     ) -> ResponseApplySnapshotChunk {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.apply_snapshot_chunk(apply_snapshot_chunk_request))
+        self.inner
+            .execute(move |inner| inner.apply_snapshot_chunk(apply_snapshot_chunk_request))
+            .await
     }
 
     async fn flush(&self, flush_request: RequestFlush) -> ResponseFlush {
-        let inner = self.inner.clone();
-        spawn_blocking!(move || inner.flush(flush_request))
+        self.inner
+            .execute(move |inner| inner.flush(flush_request))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::runtime::DefaultRuntime;
+
+    struct Transfers;
+
+    impl Consensus for Transfers {
+        fn init_chain(&self, _init_chain_request: RequestInitChain) -> ResponseInitChain {
+            Default::default()
+        }
+
+        fn begin_block(&self, _begin_block_request: RequestBeginBlock) -> ResponseBeginBlock {
+            Default::default()
+        }
+
+        fn deliver_tx(&self, _deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx {
+            Default::default()
+        }
+
+        fn deliver_tx_access(&self, _deliver_tx_request: &RequestDeliverTx) -> Option<TxAccess> {
+            Some(TxAccess::new().write("balance"))
+        }
+
+        fn end_block(&self, _end_block_request: RequestEndBlock) -> ResponseEndBlock {
+            Default::default()
+        }
+
+        fn commit(&self, _commit_request: RequestCommit) -> ResponseCommit {
+            Default::default()
+        }
+    }
+
+    #[test]
+    fn check_deliver_tx_access() {
+        let unblocker = Unblocker::new(Arc::new(DefaultRuntime::default()));
+        let request = RequestDeliverTx::default();
+
+        let consensus =
+            AsyncConsensusImpl::new(Transfers, ExecutionMode::ThreadPool, unblocker.clone());
+        assert_eq!(
+            Some(TxAccess::new().write("balance")),
+            AsyncConsensus::deliver_tx_access(&consensus, &request)
+        );
+
+        // Calls on dedicated thread are sequential anyway
+        let consensus =
+            AsyncConsensusImpl::new(Transfers, ExecutionMode::DedicatedThread, unblocker);
+        assert_eq!(
+            None,
+            AsyncConsensus::deliver_tx_access(&consensus, &request)
+        );
     }
 }
@@ -0,0 +1,250 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, Builder},
+};
+
+use async_channel::bounded;
+use tracing::{debug, error};
+
+use crate::runtime::Runtime;
+
+/// Execution mode of synchronous ABCI applications
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Every call to the application is executed on the blocking thread pool of async runtime (default). With
+    /// `StdRuntime`, calls are executed directly on the thread of connection (or on their own OS thread once a deadline
+    /// is set, see [`Server::with_soft_deadline`](super::Server::with_soft_deadline)).
+    #[default]
+    ThreadPool,
+    /// Calls to each application trait (`Consensus`, `Mempool`, `Info` and `Snapshot`) are executed on their own
+    /// dedicated OS thread, fed by a channel.
+    ///
+    /// This avoids a thread pool hop for every request and reduces latency of connections which process requests
+    /// sequentially, e.g., `DeliverTx` requests on consensus connection. Note that this also serializes the calls to
+    /// `Mempool::check_tx` which are otherwise executed concurrently.
+    DedicatedThread,
+}
+
+/// Executes calls to a synchronous application based on [`ExecutionMode`]
+pub enum Executor<T>
+where
+    T: Send + Sync + 'static,
+{
+    ThreadPool(Arc<T>, Unblocker),
+    DedicatedThread(DedicatedThread<T>),
+}
+
+impl<T> Executor<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn new(inner: T, execution_mode: ExecutionMode, name: &str, unblocker: Unblocker) -> Self {
+        match execution_mode {
+            ExecutionMode::ThreadPool => Self::ThreadPool(Arc::new(inner), unblocker),
+            ExecutionMode::DedicatedThread => {
+                Self::DedicatedThread(DedicatedThread::new(inner, name))
+            }
+        }
+    }
+
+    pub async fn execute<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        match self {
+            Self::ThreadPool(inner, unblocker) => {
+                let inner = inner.clone();
+                unblocker.unblock(move || f(&inner)).await
+            }
+            Self::DedicatedThread(thread) => thread.execute(move |inner| f(inner)).await,
+        }
+    }
+}
+
+/// Executes calls to a synchronous application, which requires exclusive access, based on [`ExecutionMode`]
+pub enum ExclusiveExecutor<T>
+where
+    T: Send + 'static,
+{
+    ThreadPool(Arc<Mutex<T>>, Unblocker),
+    DedicatedThread(DedicatedThread<T>),
+}
+
+impl<T> ExclusiveExecutor<T>
+where
+    T: Send + 'static,
+{
+    pub fn new(inner: T, execution_mode: ExecutionMode, name: &str, unblocker: Unblocker) -> Self {
+        match execution_mode {
+            ExecutionMode::ThreadPool => Self::ThreadPool(Arc::new(Mutex::new(inner)), unblocker),
+            ExecutionMode::DedicatedThread => {
+                Self::DedicatedThread(DedicatedThread::new(inner, name))
+            }
+        }
+    }
+
+    pub async fn execute<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        match self {
+            Self::ThreadPool(inner, unblocker) => {
+                let inner = inner.clone();
+                unblocker
+                    .unblock(move || f(&mut inner.lock().expect("Lock poisoned")))
+                    .await
+            }
+            Self::DedicatedThread(thread) => thread.execute(f).await,
+        }
+    }
+}
+
+/// Runs blocking calls of [`Executor`]s in [`ExecutionMode::ThreadPool`]
+///
+/// On runtimes which spawn an OS thread for every task, blocking calls are executed inline and complete within the
+/// first poll of their future, so, the deadlines of ABCI methods cannot be watched. Once
+/// [`spawn_threads`](Self::spawn_threads) is called, every call on such a runtime is executed on its own OS thread.
+#[derive(Clone)]
+pub struct Unblocker {
+    runtime: Arc<dyn Runtime>,
+    spawn_threads: Arc<AtomicBool>,
+}
+
+impl Unblocker {
+    pub fn new(runtime: Arc<dyn Runtime>) -> Self {
+        Self {
+            runtime,
+            spawn_threads: Default::default(),
+        }
+    }
+
+    /// Executes every later call on its own OS thread if the runtime spawns an OS thread for every task
+    pub fn spawn_threads(&self) {
+        if self.runtime.is_thread_per_task() {
+            self.spawn_threads.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub async fn unblock<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if !self.spawn_threads.load(Ordering::Relaxed) {
+            return self.runtime.unblock(f).await;
+        }
+
+        let (sender, receiver) = bounded(1);
+
+        thread::spawn(move || {
+            let _ = sender.try_send(f());
+        });
+
+        receiver
+            .recv()
+            .await
+            .expect("Failed to execute blocking task")
+    }
+}
+
+type Job<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+/// A dedicated OS thread which owns `T` and executes jobs sent to it, in order
+pub struct DedicatedThread<T> {
+    job_sender: Sender<Job<T>>,
+}
+
+impl<T> DedicatedThread<T>
+where
+    T: Send + 'static,
+{
+    pub fn new(mut inner: T, name: &str) -> Self {
+        let (job_sender, job_receiver) = channel::<Job<T>>();
+
+        Builder::new()
+            .name(format!("abci-{}", name))
+            .spawn(move || {
+                while let Ok(job) = job_receiver.recv() {
+                    // A panicking job must not stop the thread, otherwise every later call would fail as well. The
+                    // caller of panicked job observes the panic when result channel is dropped.
+                    if catch_unwind(AssertUnwindSafe(|| job(&mut inner))).is_err() {
+                        error!(message = "Job panicked on dedicated thread");
+                    }
+                }
+
+                debug!(message = "Stopping dedicated thread");
+            })
+            .expect("Failed to spawn dedicated thread");
+
+        Self { job_sender }
+    }
+
+    pub async fn execute<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_sender, result_receiver) = bounded(1);
+
+        self.send(Box::new(move |inner| {
+            let _ = result_sender.try_send(f(inner));
+        }));
+
+        result_receiver
+            .recv()
+            .await
+            .expect("Dedicated thread panicked or stopped before completing the job")
+    }
+
+    fn send(&self, job: Job<T>) {
+        self.job_sender.send(job).expect("Dedicated thread stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::current;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn check_dedicated_thread_execution() {
+        let thread = DedicatedThread::new(Vec::new(), "test");
+
+        for i in 0..10 {
+            thread
+                .execute(move |inner: &mut Vec<u64>| inner.push(i))
+                .await;
+        }
+
+        let (values, thread_name) = thread
+            .execute(|inner| (inner.clone(), current().name().map(ToOwned::to_owned)))
+            .await;
+
+        assert_eq!((0..10).collect::<Vec<_>>(), values);
+        assert_eq!(Some("abci-test".to_string()), thread_name);
+    }
+
+    #[tokio::test]
+    async fn check_dedicated_thread_survives_panic() {
+        let thread = Arc::new(DedicatedThread::new(0u64, "test"));
+
+        let panicking = thread.clone();
+        let result =
+            tokio::spawn(
+                async move { panicking.execute(|_: &mut u64| panic!("Job failed")).await },
+            )
+            .await;
+        assert!(result.unwrap_err().is_panic());
+
+        thread.execute(|inner| *inner += 1).await;
+        assert_eq!(1, thread.execute(|inner| *inner).await);
+    }
+}
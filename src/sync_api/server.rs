@@ -1,18 +1,31 @@
-use std::io::Result;
+use std::{io::Result, net::SocketAddr, sync::Arc, thread, time::Duration};
 
+#[cfg(any(
+    feature = "use-async-std",
+    feature = "use-smol",
+    feature = "use-std",
+    feature = "use-tokio"
+))]
+use crate::runtime::DefaultRuntime;
+#[cfg(feature = "tls")]
+use crate::TlsConfig;
+#[cfg(unix)]
+use crate::UnixSocketConfig;
 use crate::{
-    async_api::Server as AsyncServer,
+    async_api::{BoundServer as AsyncBoundServer, Server as AsyncServer},
+    runtime::Runtime,
     sync_api::{
         async_impls::{AsyncConsensusImpl, AsyncInfoImpl, AsyncMempoolImpl, AsyncSnapshotImpl},
-        Consensus, Info, Mempool, Snapshot,
+        executor::Unblocker,
+        ConsensusMut, ExecutionMode, Info, Mempool, Snapshot,
     },
-    Address,
+    Address, DeadlineAction, Health, Method, PeerAllowlist, QueueMetrics, RecheckStats,
 };
 
 /// ABCI Server
 pub struct Server<C, M, I, S>
 where
-    C: Consensus + Send + Sync + 'static,
+    C: ConsensusMut + Send + 'static,
     M: Mempool + Send + Sync + 'static,
     I: Info + Send + Sync + 'static,
     S: Snapshot + Send + Sync + 'static,
@@ -23,43 +36,393 @@ where
         AsyncInfoImpl<I>,
         AsyncSnapshotImpl<S>,
     >,
+    runtime: Arc<dyn Runtime>,
+    unblocker: Unblocker,
 }
 
 impl<C, M, I, S> Server<C, M, I, S>
 where
-    C: Consensus + Send + Sync + 'static,
+    C: ConsensusMut + Send + 'static,
     M: Mempool + Send + Sync + 'static,
     I: Info + Send + Sync + 'static,
     S: Snapshot + Send + Sync + 'static,
 {
-    /// Creates a new instance of [`Server`](self::Server)
+    /// Creates a new instance of [`Server`](self::Server) which uses [`DefaultRuntime`](crate::runtime::DefaultRuntime)
+    ///
+    /// `consensus` can either be an implementation of [`Consensus`](super::Consensus) or
+    /// [`ConsensusMut`](super::ConsensusMut).
+    #[cfg(any(
+        feature = "use-async-std",
+        feature = "use-smol",
+        feature = "use-std",
+        feature = "use-tokio"
+    ))]
     pub fn new(consensus: C, mempool: M, info: I, snapshot: S) -> Self {
+        Self::with_execution_mode(consensus, mempool, info, snapshot, Default::default())
+    }
+
+    /// Creates a new instance of [`Server`](self::Server) which uses [`DefaultRuntime`](crate::runtime::DefaultRuntime)
+    /// and executes calls to application using given [`ExecutionMode`](super::ExecutionMode)
+    #[cfg(any(
+        feature = "use-async-std",
+        feature = "use-smol",
+        feature = "use-std",
+        feature = "use-tokio"
+    ))]
+    pub fn with_execution_mode(
+        consensus: C,
+        mempool: M,
+        info: I,
+        snapshot: S,
+        execution_mode: ExecutionMode,
+    ) -> Self {
+        Self::with_runtime(
+            consensus,
+            mempool,
+            info,
+            snapshot,
+            execution_mode,
+            DefaultRuntime::default(),
+        )
+    }
+
+    /// Creates a new instance of [`Server`](self::Server) which uses given [`Runtime`](crate::runtime::Runtime) and
+    /// executes calls to application using given [`ExecutionMode`](super::ExecutionMode)
+    pub fn with_runtime<R>(
+        consensus: C,
+        mempool: M,
+        info: I,
+        snapshot: S,
+        execution_mode: ExecutionMode,
+        runtime: R,
+    ) -> Self
+    where
+        R: Runtime,
+    {
+        let runtime: Arc<dyn Runtime> = Arc::new(runtime);
+        let unblocker = Unblocker::new(runtime.clone());
+
         Self {
-            async_server: AsyncServer::new(
-                AsyncConsensusImpl::new(consensus),
-                AsyncMempoolImpl::new(mempool),
-                AsyncInfoImpl::new(info),
-                AsyncSnapshotImpl::new(snapshot),
+            async_server: AsyncServer::with_runtime(
+                AsyncConsensusImpl::new(consensus, execution_mode, unblocker.clone()),
+                AsyncMempoolImpl::new(mempool, execution_mode, unblocker.clone()),
+                AsyncInfoImpl::new(info, execution_mode, unblocker.clone()),
+                AsyncSnapshotImpl::new(snapshot, execution_mode, unblocker.clone()),
+                runtime.clone(),
             ),
+            runtime,
+            unblocker,
         }
     }
 
+    /// Enables parallel execution of non-conflicting transactions in a block (default: `false`, see
+    /// [`async_api::Server::with_parallel_deliver_tx`](crate::async_api::Server::with_parallel_deliver_tx))
+    ///
+    /// Transactions are grouped using [`Consensus::deliver_tx_access`](super::Consensus::deliver_tx_access) and
+    /// non-conflicting transactions are executed in parallel on the thread pool. This only has an effect when
+    /// `consensus` is an implementation of [`Consensus`](super::Consensus) and
+    /// [`ExecutionMode::ThreadPool`](super::ExecutionMode::ThreadPool) is used. Implementations of
+    /// [`ConsensusMut`](super::ConsensusMut) (which need exclusive access) and
+    /// [`ExecutionMode::DedicatedThread`](super::ExecutionMode::DedicatedThread) execute transactions sequentially.
+    pub fn with_parallel_deliver_tx(mut self, enabled: bool) -> Self {
+        self.async_server = self.async_server.with_parallel_deliver_tx(enabled);
+        self
+    }
+
+    /// Enables coordination of `CheckTx` execution on mempool connection with `Commit` on consensus connection (default:
+    /// `false`)
+    ///
+    /// When enabled:
+    ///
+    /// - `Commit` waits for running `CheckTx` requests to complete and no new `CheckTx` request is executed until the
+    ///   commit and [`Mempool::on_commit`](super::Mempool::on_commit) hook complete. So, the application can swap its
+    ///   `mempool_state` with the **latest committed state** atomically.
+    /// - After a commit, new `CheckTx` requests are executed only after all the rechecks received before them complete.
+    pub fn with_commit_barrier(mut self, enabled: bool) -> Self {
+        self.async_server = self.async_server.with_commit_barrier(enabled);
+        self
+    }
+
+    /// Sets a soft deadline for given ABCI method (e.g., [`Method::Commit`](crate::Method::Commit))
+    ///
+    /// When a request takes longer than its soft deadline, a warning is logged with the details of request (connection
+    /// type, method, block height, transaction hash, etc.) and the current consensus state. Request execution is not
+    /// affected.
+    ///
+    /// On runtimes which spawn an OS thread for every task (e.g., `StdRuntime`), calls to the application are executed
+    /// inline on the thread of connection, which leaves no chance to watch a deadline. So, once a deadline is set,
+    /// every call in [`ExecutionMode::ThreadPool`](super::ExecutionMode::ThreadPool) is executed on its own OS thread.
+    pub fn with_soft_deadline(mut self, method: Method, deadline: Duration) -> Self {
+        self.unblocker.spawn_threads();
+        self.async_server = self.async_server.with_soft_deadline(method, deadline);
+        self
+    }
+
+    /// Sets a hard deadline for given ABCI method (e.g., [`Method::Commit`](crate::Method::Commit)) and the action
+    /// to take when a request exceeds it (see [`DeadlineAction`](crate::DeadlineAction))
+    ///
+    /// With [`DeadlineAction::Exception`](crate::DeadlineAction::Exception), the server stops waiting for the method
+    /// and responds with an exception. The method itself keeps running on its blocking thread and its result is
+    /// discarded. See [`with_soft_deadline`](Self::with_soft_deadline) for deadlines on runtimes which spawn an OS
+    /// thread for every task.
+    pub fn with_hard_deadline(
+        mut self,
+        method: Method,
+        deadline: Duration,
+        action: DeadlineAction,
+    ) -> Self {
+        self.unblocker.spawn_threads();
+        self.async_server = self
+            .async_server
+            .with_hard_deadline(method, deadline, action);
+        self
+    }
+
+    /// Enables TLS on TCP connections with given configuration (see
+    /// [`async_api::Server::with_tls`](crate::async_api::Server::with_tls))
+    #[cfg(feature = "tls")]
+    #[cfg_attr(feature = "doc", doc(cfg(feature = "tls")))]
+    pub fn with_tls(mut self, config: TlsConfig) -> Self {
+        self.async_server = self.async_server.with_tls(config);
+        self
+    }
+
+    /// Restricts the peers which can connect to server (see
+    /// [`async_api::Server::with_peer_allowlist`](crate::async_api::Server::with_peer_allowlist))
+    pub fn with_peer_allowlist(mut self, allowlist: PeerAllowlist) -> Self {
+        self.async_server = self.async_server.with_peer_allowlist(allowlist);
+        self
+    }
+
+    /// Configures the lifecycle of socket files created for [`Address::Uds`](crate::Address::Uds) (see
+    /// [`async_api::Server::with_unix_socket_config`](crate::async_api::Server::with_unix_socket_config))
+    #[cfg(unix)]
+    #[cfg_attr(feature = "doc", doc(cfg(unix)))]
+    pub fn with_unix_socket_config(mut self, config: UnixSocketConfig) -> Self {
+        self.async_server = self.async_server.with_unix_socket_config(config);
+        self
+    }
+
+    /// Sets the maximum number of `CheckTx` requests which can be in-flight at once (default:
+    /// [`DEFAULT_MAX_IN_FLIGHT_CHECK_TX`](crate::async_api::DEFAULT_MAX_IN_FLIGHT_CHECK_TX), or
+    /// [`DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX`](crate::async_api::DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX)
+    /// for runtimes which spawn an OS thread for every task, e.g., `StdRuntime`)
+    ///
+    /// `CheckTx` requests on mempool connection are executed concurrently and their responses are written in the
+    /// order in which requests were received. Once the limit is reached, server stops reading new requests from the
+    /// mempool connection until responses of earlier requests are written (backpressure).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_max_in_flight_check_tx(mut self, limit: usize) -> Self {
+        self.async_server = self.async_server.with_max_in_flight_check_tx(limit);
+        self
+    }
+
+    /// Returns the current state of in-flight `CheckTx` requests
+    pub fn check_tx_queue_metrics(&self) -> QueueMetrics {
+        self.async_server.check_tx_queue_metrics()
+    }
+
+    /// Sets the maximum number of `CheckTx` requests which are passed to
+    /// [`Mempool::check_tx_batch`](super::Mempool::check_tx_batch) at once (default: `1`, i.e., batching is disabled)
+    ///
+    /// When batching is enabled, all the `CheckTx` requests which are already buffered by mempool connection (up to
+    /// `limit`) are executed as a single batch. Server never waits for more requests to fill a batch and still writes
+    /// one response per request, in the order in which requests were received.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_check_tx_batch_limit(mut self, limit: usize) -> Self {
+        self.async_server = self.async_server.with_check_tx_batch_limit(limit);
+        self
+    }
+
+    /// Enables caching of `CheckTx` responses with given capacity (number of transactions) so that rechecked
+    /// transactions whose state fingerprint (see
+    /// [`Mempool::recheck_fingerprint`](super::Mempool::recheck_fingerprint)) is unchanged are not validated again
+    ///
+    /// Cached responses of transactions which are not rechecked after a commit (i.e., which are not in mempool anymore)
+    /// are removed from cache.
+    pub fn with_recheck_cache(mut self, capacity: usize) -> Self {
+        self.async_server = self.async_server.with_recheck_cache(capacity);
+        self
+    }
+
+    /// Returns statistics of recheck rounds on mempool connection
+    pub fn recheck_stats(&self) -> RecheckStats {
+        self.async_server.recheck_stats()
+    }
+
+    /// Sets the maximum number of `Query` requests which can be in-flight at once (default:
+    /// [`DEFAULT_MAX_IN_FLIGHT_QUERY`](crate::async_api::DEFAULT_MAX_IN_FLIGHT_QUERY))
+    ///
+    /// `Query` requests on info connection are executed concurrently while all the other requests (`Info`,
+    /// `SetOption`, etc.) are executed in order. Responses are written in the order in which requests were received.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_max_in_flight_query(mut self, limit: usize) -> Self {
+        self.async_server = self.async_server.with_max_in_flight_query(limit);
+        self
+    }
+
+    /// Returns the current state of in-flight `Query` requests
+    pub fn query_queue_metrics(&self) -> QueueMetrics {
+        self.async_server.query_queue_metrics()
+    }
+
+    /// Sets the maximum number of `LoadSnapshotChunk` requests which can be in-flight at once, i.e., the number of
+    /// snapshot chunks loaded in parallel (default:
+    /// [`DEFAULT_MAX_IN_FLIGHT_LOAD_SNAPSHOT_CHUNK`](crate::async_api::DEFAULT_MAX_IN_FLIGHT_LOAD_SNAPSHOT_CHUNK))
+    ///
+    /// `LoadSnapshotChunk` requests on snapshot connection are executed concurrently while all the other requests are
+    /// executed in order. Responses are written in the order in which requests were received.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn with_max_in_flight_load_snapshot_chunk(mut self, limit: usize) -> Self {
+        self.async_server = self
+            .async_server
+            .with_max_in_flight_load_snapshot_chunk(limit);
+        self
+    }
+
+    /// Returns the current state of in-flight `LoadSnapshotChunk` requests
+    pub fn load_snapshot_chunk_queue_metrics(&self) -> QueueMetrics {
+        self.async_server.load_snapshot_chunk_queue_metrics()
+    }
+
+    /// Starts serving Prometheus metrics of ABCI traffic over HTTP at `GET /metrics` on given address (see
+    /// [`async_api::Server::serve_metrics`](crate::async_api::Server::serve_metrics))
+    ///
+    /// This function returns once the listener is bound. Call it before [`run`](Self::run).
+    #[cfg(feature = "metrics")]
+    #[cfg_attr(feature = "doc", doc(cfg(feature = "metrics")))]
+    pub fn serve_metrics(&self, addr: SocketAddr) -> Result<()> {
+        self.runtime.block_on(Box::pin(async {
+            self.async_server.serve_metrics(addr).await
+        }))
+    }
+
+    /// Returns the current health of server (see [`async_api::Server::health`](crate::async_api::Server::health))
+    pub fn health(&self) -> Health {
+        self.async_server.health()
+    }
+
+    /// Starts serving health and readiness of server over HTTP at `GET /health` and `GET /ready` on given address (see
+    /// [`async_api::Server::serve_health`](crate::async_api::Server::serve_health))
+    ///
+    /// This function returns once the listener is bound. Call it before [`run`](Self::run).
+    pub fn serve_health(&self, addr: SocketAddr) -> Result<()> {
+        self.runtime.block_on(Box::pin(async {
+            self.async_server.serve_health(addr).await
+        }))
+    }
+
     /// Starts ABCI server
     pub fn run<T>(&self, addr: T) -> Result<()>
     where
         T: Into<Address>,
     {
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "use-async-std")] {
-                async_std::task::block_on(async { self.async_server.run(addr).await })
-            } else if #[cfg(feature = "use-smol")] {
-                smol::block_on(async { self.async_server.run(addr).await })
-            } else if #[cfg(feature = "use-tokio")] {
-                let runtime = tokio::runtime::Runtime::new()?;
-                runtime.block_on(async { self.async_server.run(addr).await })
-            } else {
-                unreachable!()
-            }
-        }
+        let addr = addr.into();
+
+        self.runtime
+            .block_on(Box::pin(async { self.async_server.run(addr).await }))
+    }
+
+    /// Starts ABCI server listening on all the given addresses at once (see
+    /// [`async_api::Server::run_all`](crate::async_api::Server::run_all))
+    pub fn run_all<A, T>(&self, addrs: A) -> Result<()>
+    where
+        A: IntoIterator<Item = T>,
+        T: Into<Address>,
+    {
+        let addrs: Vec<Address> = addrs.into_iter().map(Into::into).collect();
+
+        self.runtime
+            .block_on(Box::pin(async { self.async_server.run_all(addrs).await }))
+    }
+
+    /// Binds ABCI server to given address without accepting any connection (see
+    /// [`async_api::Server::bind`](crate::async_api::Server::bind))
+    pub fn bind<T>(&self, addr: T) -> Result<BoundServer<C, M, I, S>>
+    where
+        T: Into<Address>,
+    {
+        self.bind_all([addr])
+    }
+
+    /// Binds ABCI server to all the given addresses without accepting any connection (see
+    /// [`async_api::Server::bind_all`](crate::async_api::Server::bind_all))
+    pub fn bind_all<A, T>(&self, addrs: A) -> Result<BoundServer<C, M, I, S>>
+    where
+        A: IntoIterator<Item = T>,
+        T: Into<Address>,
+    {
+        let addrs: Vec<Address> = addrs.into_iter().map(Into::into).collect();
+        let mut bound = None;
+
+        self.runtime.block_on(Box::pin(async {
+            bound = Some(self.async_server.bind_all(addrs).await?);
+            Ok(())
+        }))?;
+
+        Ok(BoundServer {
+            bound: bound.expect("Bound server is set before `block_on` returns"),
+            runtime: self.runtime.clone(),
+        })
+    }
+}
+
+/// ABCI server bound to its listeners (see [`Server::bind`](self::Server::bind))
+pub struct BoundServer<C, M, I, S>
+where
+    C: ConsensusMut + Send + 'static,
+    M: Mempool + Send + Sync + 'static,
+    I: Info + Send + Sync + 'static,
+    S: Snapshot + Send + Sync + 'static,
+{
+    bound: AsyncBoundServer<
+        AsyncConsensusImpl<C>,
+        AsyncMempoolImpl<M>,
+        AsyncInfoImpl<I>,
+        AsyncSnapshotImpl<S>,
+    >,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl<C, M, I, S> BoundServer<C, M, I, S>
+where
+    C: ConsensusMut + Send + 'static,
+    M: Mempool + Send + Sync + 'static,
+    I: Info + Send + Sync + 'static,
+    S: Snapshot + Send + Sync + 'static,
+{
+    /// Returns the local address of server (see
+    /// [`async_api::BoundServer::local_addr`](crate::async_api::BoundServer::local_addr))
+    pub fn local_addr(&self) -> &Address {
+        self.bound.local_addr()
+    }
+
+    /// Returns the local addresses of server in the order in which they were given to
+    /// [`Server::bind_all`](self::Server::bind_all)
+    pub fn local_addrs(&self) -> impl Iterator<Item = &Address> {
+        self.bound.local_addrs()
+    }
+
+    /// Starts accepting connections on all the listeners (see [`Server::run_all`](self::Server::run_all))
+    pub fn serve(self) -> Result<()> {
+        self.runtime.block_on(Box::pin(self.bound.serve()))
+    }
+
+    /// Starts accepting connections on all the listeners on a new thread. Returned handle can be joined to get the
+    /// result of [`serve`](Self::serve).
+    pub fn spawn(self) -> thread::JoinHandle<Result<()>> {
+        thread::spawn(move || self.serve())
     }
 }
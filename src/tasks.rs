@@ -1,105 +1,181 @@
-use std::sync::Arc;
-
-#[cfg(feature = "use-async-std")]
-use async_std::{
-    channel::{unbounded as unbounded_channel, Receiver, Sender},
-    io::{Read, Write},
-    sync::Mutex,
-    task::{spawn, JoinHandle},
-};
-#[cfg(feature = "use-smol")]
-use smol::{
-    channel::{unbounded as unbounded_channel, Receiver, Sender},
-    io::{AsyncRead as Read, AsyncWrite as Write},
-    lock::Mutex,
-    spawn, Task as JoinHandle,
-};
-use tendermint_proto::abci::{Request, Response};
-#[cfg(feature = "use-tokio")]
-use tokio::{
-    io::{AsyncRead as Read, AsyncWrite as Write},
-    spawn,
-    sync::{
-        mpsc::{unbounded_channel, UnboundedReceiver as Receiver, UnboundedSender as Sender},
-        Mutex,
-    },
-    task::JoinHandle,
+use std::{io::ErrorKind, sync::Arc};
+
+use async_channel::{unbounded, Receiver, Sender};
+use async_lock::Mutex;
+use futures_lite::io::{AsyncRead as Read, AsyncWrite as Write};
+use tendermint_proto::abci::{
+    request::Value as RequestValue, response::Value as ResponseValue, Request, Response,
 };
 use tracing::{debug, error, info, instrument};
 
 use crate::{
     async_api::{Consensus, Info, Mempool, Snapshot},
     handler::*,
+    metrics::Metrics,
+    runtime::{BoxFuture, JoinHandle, Runtime},
     state::ConsensusStateValidator,
-    utils::{StreamReader, StreamWriter},
+    types::CheckTxType,
+    utils::{
+        CheckTxTicket, CommitBarrier, InFlightLimiter, InFlightPermit, RecheckTracker,
+        StreamReader, StreamWriter,
+    },
+    watchdog::Watchdog,
 };
 
-macro_rules! spawn {
-    ($expr: expr) => {
-        cfg_if::cfg_if! {
-            if #[cfg(any(feature = "use-async-std", feature = "use-tokio"))] {
-                spawn($expr)
-            } else if #[cfg(feature = "use-smol")] {
-                spawn($expr)
-                    .detach()
-            } else {
-                unreachable!()
-            }
-        }
-    };
-}
-
-#[instrument(skip(stream_reader, stream_writer, consensus))]
-pub fn spawn_consensus_task<R, W, C>(
+#[instrument(skip(
+    runtime,
+    stream_reader,
+    stream_writer,
+    consensus,
+    mempool,
+    commit_barrier,
+    watchdog,
+    metrics
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_consensus_task<R, W, C, M>(
+    runtime: Arc<dyn Runtime>,
     stream_reader: StreamReader<R>,
     stream_writer: StreamWriter<W>,
     peer_addr: String,
     consensus: Arc<C>,
     validator: Arc<Mutex<ConsensusStateValidator>>,
+    parallel_deliver_tx: bool,
+    mempool: Arc<M>,
+    commit_barrier: Option<Arc<CommitBarrier>>,
+    watchdog: Watchdog,
+    metrics: Arc<Metrics>,
 ) where
     R: Read + Unpin + Send + 'static,
     W: Write + Unpin + Send + 'static,
     C: Consensus + 'static,
+    M: Mempool + 'static,
 {
     info!(message = "Spawning consensus task");
 
-    spawn!(async move {
+    let runtime_clone = runtime.clone();
+
+    runtime.spawn(Box::pin(async move {
         consensus_task(
+            runtime_clone.as_ref(),
             stream_reader,
             stream_writer,
             peer_addr,
-            consensus.as_ref(),
+            consensus,
             validator,
+            parallel_deliver_tx,
+            mempool,
+            commit_barrier,
+            watchdog,
+            metrics,
         )
         .await
-    });
+    }));
 }
 
-#[instrument(skip(stream_reader, stream_writer, consensus))]
-async fn consensus_task<R, W, C>(
+#[instrument(skip(
+    runtime,
+    stream_reader,
+    stream_writer,
+    consensus,
+    mempool,
+    commit_barrier,
+    watchdog,
+    metrics
+))]
+#[allow(clippy::too_many_arguments)]
+async fn consensus_task<R, W, C, M>(
+    runtime: &dyn Runtime,
     mut stream_reader: StreamReader<R>,
     mut stream_writer: StreamWriter<W>,
     peer_addr: String,
-    consensus: &C,
+    consensus: Arc<C>,
     validator: Arc<Mutex<ConsensusStateValidator>>,
+    parallel_deliver_tx: bool,
+    mempool: Arc<M>,
+    commit_barrier: Option<Arc<CommitBarrier>>,
+    watchdog: Watchdog,
+    metrics: Arc<Metrics>,
 ) where
     R: Read + Unpin,
     W: Write + Unpin,
-    C: Consensus,
+    C: Consensus + 'static,
+    M: Mempool + 'static,
 {
+    // `DeliverTx` requests buffered for parallel execution
+    let mut deliver_tx_requests = Vec::new();
+
     while let Ok(request) = stream_reader.read().await {
         match request {
             None => debug!(message = "Received empty request"),
             Some(request) => {
                 let request: Request = request;
 
-                let response = match request.value {
+                let request_value = match request.value {
+                    Some(RequestValue::DeliverTx(deliver_tx_request)) if parallel_deliver_tx => {
+                        // Responses are written once a request other than `DeliverTx` (e.g., `EndBlock` or `Flush`)
+                        // is received
+                        deliver_tx_requests.push(deliver_tx_request);
+                        continue;
+                    }
+                    request_value => request_value,
+                };
+
+                if !deliver_tx_requests.is_empty() {
+                    let responses = handle_deliver_tx_batch(
+                        runtime,
+                        &consensus,
+                        validator.clone(),
+                        &watchdog,
+                        &metrics,
+                        std::mem::take(&mut deliver_tx_requests),
+                    )
+                    .await;
+
+                    for response in responses {
+                        if let Err(err) = stream_writer.write(response).await {
+                            error!(message = "Error while writing to stream", %err);
+                        }
+                    }
+                }
+
+                let response = match request_value {
                     None => {
-                        debug!(message = "Received empty value in request", ?request);
+                        debug!(message = "Received empty value in request", ?request_value);
                         Response::default()
                     }
+                    Some(request_value @ RequestValue::Commit(_)) => {
+                        // With commit barrier, `CheckTx` requests are not executed until commit is done and mempool
+                        // is updated
+                        let _commit_guard = match commit_barrier {
+                            Some(ref commit_barrier) => Some(commit_barrier.pause().await),
+                            None => None,
+                        };
+
+                        let response = handle_consensus_request(
+                            consensus.as_ref(),
+                            validator.clone(),
+                            &watchdog,
+                            &metrics,
+                            request_value,
+                        )
+                        .await;
+
+                        if let Some(ResponseValue::Commit(ref commit_response)) = response.value {
+                            mempool.on_commit(commit_response).await;
+                        }
+
+                        response
+                    }
                     Some(request_value) => {
-                        handle_consensus_request(consensus, validator.clone(), request_value).await
+                        handle_consensus_request(
+                            consensus.as_ref(),
+                            validator.clone(),
+                            &watchdog,
+                            &metrics,
+                            request_value,
+                        )
+                        .await
                     }
                 };
 
@@ -111,12 +187,30 @@ async fn consensus_task<R, W, C>(
     }
 }
 
-#[instrument(skip(stream_reader, stream_writer, mempool))]
+#[instrument(skip(
+    runtime,
+    stream_reader,
+    stream_writer,
+    mempool,
+    limiter,
+    commit_barrier,
+    recheck_tracker,
+    watchdog,
+    metrics
+))]
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_mempool_task<R, W, M>(
+    runtime: Arc<dyn Runtime>,
     stream_reader: StreamReader<R>,
     stream_writer: StreamWriter<W>,
     peer_addr: String,
     mempool: Arc<M>,
+    limiter: Arc<InFlightLimiter>,
+    batch_limit: usize,
+    commit_barrier: Option<Arc<CommitBarrier>>,
+    recheck_tracker: Arc<RecheckTracker>,
+    watchdog: Watchdog,
+    metrics: Arc<Metrics>,
 ) where
     R: Read + Unpin + Send + 'static,
     W: Write + Unpin + Send + 'static,
@@ -124,202 +218,281 @@ pub fn spawn_mempool_task<R, W, M>(
 {
     info!(message = "Spawning mempool tasks");
 
-    let (handle_sender, handle_receiver) = unbounded_channel();
-    let peer_addr_clone = peer_addr.clone();
+    if batch_limit > 1 {
+        let handle_sender =
+            spawn_pipelined_writer_task(runtime.as_ref(), stream_writer, peer_addr.clone());
+        let runtime_clone = runtime.clone();
 
-    spawn!(
-        async move { mempool_writer_task(stream_writer, peer_addr_clone, handle_receiver).await }
-    );
+        runtime.spawn(Box::pin(async move {
+            batched_mempool_reader_task(
+                runtime_clone.as_ref(),
+                stream_reader,
+                peer_addr,
+                mempool,
+                limiter,
+                batch_limit,
+                commit_barrier,
+                recheck_tracker,
+                watchdog,
+                metrics,
+                handle_sender,
+            )
+            .await
+        }));
+
+        return;
+    }
+
+    // All the mempool requests are executed concurrently
+    spawn_pipelined_task(
+        runtime,
+        stream_reader,
+        stream_writer,
+        peer_addr,
+        limiter,
+        move |request_value| {
+            let mempool = mempool.clone();
+            let recheck_tracker = recheck_tracker.clone();
+            let watchdog = watchdog.clone();
+            let metrics = metrics.clone();
+            let ticket = register_check_tx(commit_barrier.as_ref(), &request_value);
+
+            recheck_tracker.on_request(&request_value);
 
-    spawn!(
-        async move { mempool_reader_task(stream_reader, peer_addr, mempool, handle_sender).await }
+            Execution::Concurrent(Box::pin(async move {
+                let _guard = match ticket {
+                    Some(ref ticket) => Some(ticket.wait().await),
+                    None => None,
+                };
+
+                handle_mempool_request(
+                    mempool.as_ref(),
+                    recheck_tracker.as_ref(),
+                    &watchdog,
+                    &metrics,
+                    request_value,
+                )
+                .await
+            }))
+        },
     );
 }
 
-#[instrument(skip(stream_writer, handle_receiver))]
-async fn mempool_writer_task<W>(
-    mut stream_writer: StreamWriter<W>,
+#[instrument(skip(
+    runtime,
+    stream_reader,
+    stream_writer,
+    info,
+    validator,
+    limiter,
+    watchdog,
+    metrics
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_info_task<R, W, I>(
+    runtime: Arc<dyn Runtime>,
+    stream_reader: StreamReader<R>,
+    stream_writer: StreamWriter<W>,
     peer_addr: String,
-    handle_receiver: Receiver<JoinHandle<Response>>,
+    info: Arc<I>,
+    validator: Arc<Mutex<ConsensusStateValidator>>,
+    limiter: Arc<InFlightLimiter>,
+    watchdog: Watchdog,
+    metrics: Arc<Metrics>,
 ) where
-    W: Write + Unpin,
+    R: Read + Unpin + Send + 'static,
+    W: Write + Unpin + Send + 'static,
+    I: Info + 'static,
 {
-    cfg_if::cfg_if! {
-        if #[cfg(any(feature = "use-async-std", feature = "use-smol"))] {
-            while let Ok(handle) = handle_receiver.recv().await {
-                let response = handle.await;
+    info!(message = "Spawning info tasks");
 
-                if let Err(err) = stream_writer.write(response).await {
-                    error!(message = "Error while writing to stream", %err);
-                }
-            }
-        } else if #[cfg(feature = "use-tokio")] {
-            let mut handle_receiver = handle_receiver;
+    // `Query` requests are executed concurrently while all the other requests (`Info`, `SetOption`, etc.) are
+    // executed in order
+    spawn_pipelined_task(
+        runtime,
+        stream_reader,
+        stream_writer,
+        peer_addr,
+        limiter,
+        move |request_value| {
+            let info = info.clone();
+            let validator = validator.clone();
+            let watchdog = watchdog.clone();
+            let metrics = metrics.clone();
+            let is_query = matches!(request_value, RequestValue::Query(_));
 
-            while let Some(handle) = handle_receiver.recv().await {
-                let response = handle.await;
+            let future = Box::pin(async move {
+                handle_info_request(info.as_ref(), validator, &watchdog, &metrics, request_value)
+                    .await
+            });
 
-                match response {
-                    Ok(response) => {
-                        if let Err(err) = stream_writer.write(response).await {
-                            error!(message = "Error while writing to stream", %err);
-                        }
-                    }
-                    Err(err) => error!(message = "Mempool request execution not completed", ?err),
-                }
+            if is_query {
+                Execution::Concurrent(future)
+            } else {
+                Execution::Sequential(future)
             }
-        } else {
-            unreachable!()
-        }
-    }
+        },
+    );
 }
 
-#[instrument(skip(stream_reader, mempool, handle_sender))]
-async fn mempool_reader_task<R, M>(
-    mut stream_reader: StreamReader<R>,
+#[instrument(skip(
+    runtime,
+    stream_reader,
+    stream_writer,
+    snapshot,
+    limiter,
+    watchdog,
+    metrics
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_snapshot_task<R, W, S>(
+    runtime: Arc<dyn Runtime>,
+    stream_reader: StreamReader<R>,
+    stream_writer: StreamWriter<W>,
     peer_addr: String,
-    mempool: Arc<M>,
-    handle_sender: Sender<JoinHandle<Response>>,
+    snapshot: Arc<S>,
+    limiter: Arc<InFlightLimiter>,
+    watchdog: Watchdog,
+    metrics: Arc<Metrics>,
 ) where
-    R: Read + Unpin,
-    M: Mempool + 'static,
+    R: Read + Unpin + Send + 'static,
+    W: Write + Unpin + Send + 'static,
+    S: Snapshot + 'static,
 {
-    while let Ok(request) = stream_reader.read().await {
-        match request {
-            None => {
-                debug!(message = "Received empty request")
-            }
-            Some(request) => {
-                let peer_addr = peer_addr.clone();
-                let mempool = mempool.clone();
+    info!(message = "Spawning snapshot tasks");
 
-                let handle = spawn(async move {
-                    let request: Request = request;
+    // `LoadSnapshotChunk` requests are executed concurrently while all the other requests (`ListSnapshots`,
+    // `OfferSnapshot`, `ApplySnapshotChunk`, etc.) are executed in order
+    spawn_pipelined_task(
+        runtime,
+        stream_reader,
+        stream_writer,
+        peer_addr,
+        limiter,
+        move |request_value| {
+            let snapshot = snapshot.clone();
+            let watchdog = watchdog.clone();
+            let metrics = metrics.clone();
+            let is_load_snapshot_chunk =
+                matches!(request_value, RequestValue::LoadSnapshotChunk(_));
 
-                    match request.value {
-                        None => {
-                            debug!(
-                                message = "Received empty value in request",
-                                ?peer_addr,
-                                ?request
-                            );
-                            Response::default()
-                        }
-                        Some(request_value) => {
-                            handle_mempool_request(mempool.as_ref(), request_value).await
-                        }
-                    }
-                });
-
-                cfg_if::cfg_if! {
-                    if #[cfg(any(feature = "use-async-std", feature = "use-smol"))] {
-                        handle_sender
-                            .send(handle)
-                            .await
-                            .expect("Channel receiver dropped");
-                    } else if #[cfg(feature = "use-tokio")] {
-                        handle_sender
-                            .send(handle)
-                            .expect("Channel receiver dropped");
-                    } else {
-                        unreachable!()
-                    }
-                }
+            let future = Box::pin(async move {
+                handle_snapshot_request(snapshot.as_ref(), &watchdog, &metrics, request_value).await
+            });
+
+            if is_load_snapshot_chunk {
+                Execution::Concurrent(future)
+            } else {
+                Execution::Sequential(future)
             }
+        },
+    );
+}
+
+/// Registers a `CheckTx` request with commit barrier (if enabled)
+fn register_check_tx(
+    commit_barrier: Option<&Arc<CommitBarrier>>,
+    request_value: &RequestValue,
+) -> Option<CheckTxTicket> {
+    match (commit_barrier, request_value) {
+        (Some(barrier), RequestValue::CheckTx(check_tx_request)) => {
+            Some(barrier.register(check_tx_request.r#type() == CheckTxType::Recheck))
         }
+        _ => None,
     }
 }
 
-#[instrument(skip(stream_reader, stream_writer, info))]
-pub fn spawn_info_task<R, W, I>(
+/// Execution strategy of a request on a pipelined connection
+enum Execution {
+    /// Request is executed concurrently with other requests
+    Concurrent(BoxFuture<'static, Response>),
+    /// Request is executed before reading the next request from connection
+    Sequential(BoxFuture<'static, Response>),
+}
+
+/// Spawns reader and writer tasks for a connection which executes requests based on their [`Execution`] strategy.
+/// Responses are always written in the order in which requests were received.
+fn spawn_pipelined_task<R, W, F>(
+    runtime: Arc<dyn Runtime>,
     stream_reader: StreamReader<R>,
     stream_writer: StreamWriter<W>,
     peer_addr: String,
-    info: Arc<I>,
-    validator: Arc<Mutex<ConsensusStateValidator>>,
+    limiter: Arc<InFlightLimiter>,
+    dispatch: F,
 ) where
     R: Read + Unpin + Send + 'static,
     W: Write + Unpin + Send + 'static,
-    I: Info + 'static,
+    F: FnMut(RequestValue) -> Execution + Send + 'static,
 {
-    info!(message = "Spawning info task");
+    let handle_sender =
+        spawn_pipelined_writer_task(runtime.as_ref(), stream_writer, peer_addr.clone());
+    let runtime_clone = runtime.clone();
 
-    spawn!(async move {
-        info_task(
+    runtime.spawn(Box::pin(async move {
+        pipelined_reader_task(
+            runtime_clone.as_ref(),
             stream_reader,
-            stream_writer,
             peer_addr,
-            info.as_ref(),
-            validator,
+            limiter,
+            handle_sender,
+            dispatch,
         )
         .await
-    });
+    }));
 }
 
-#[instrument(skip(stream_reader, stream_writer, info))]
-async fn info_task<R, W, I>(
-    mut stream_reader: StreamReader<R>,
+/// Spawns a writer task which writes responses in the order in which their handles are sent to returned sender
+fn spawn_pipelined_writer_task<W>(
+    runtime: &dyn Runtime,
+    stream_writer: StreamWriter<W>,
+    peer_addr: String,
+) -> Sender<(JoinHandle<Response>, Option<InFlightPermit>)>
+where
+    W: Write + Unpin + Send + 'static,
+{
+    let (handle_sender, handle_receiver) = unbounded();
+
+    runtime.spawn(Box::pin(async move {
+        pipelined_writer_task(stream_writer, peer_addr, handle_receiver).await
+    }));
+
+    handle_sender
+}
+
+#[instrument(skip(stream_writer, handle_receiver))]
+async fn pipelined_writer_task<W>(
     mut stream_writer: StreamWriter<W>,
     peer_addr: String,
-    info: &I,
-    validator: Arc<Mutex<ConsensusStateValidator>>,
+    handle_receiver: Receiver<(JoinHandle<Response>, Option<InFlightPermit>)>,
 ) where
-    R: Read + Unpin,
     W: Write + Unpin,
-    I: Info,
 {
-    while let Ok(request) = stream_reader.read().await {
-        match request {
-            None => debug!(message = "Received empty request"),
-            Some(request) => {
-                let request: Request = request;
-
-                let response = match request.value {
-                    None => {
-                        debug!(message = "Received empty value in request", ?request);
-                        Response::default()
-                    }
-                    Some(request_value) => {
-                        handle_info_request(info, validator.clone(), request_value).await
-                    }
-                };
-
+    while let Ok((handle, permit)) = handle_receiver.recv().await {
+        match handle.await {
+            Some(response) => {
                 if let Err(err) = stream_writer.write(response).await {
                     error!(message = "Error while writing to stream", %err);
                 }
             }
+            None => error!(message = "Request execution not completed"),
         }
-    }
-}
-
-#[instrument(skip(stream_reader, stream_writer, snapshot))]
-pub fn spawn_snapshot_task<R, W, S>(
-    stream_reader: StreamReader<R>,
-    stream_writer: StreamWriter<W>,
-    peer_addr: String,
-    snapshot: Arc<S>,
-) where
-    R: Read + Unpin + Send + 'static,
-    W: Write + Unpin + Send + 'static,
-    S: Snapshot + 'static,
-{
-    info!(message = "Spawning snapshot task");
 
-    spawn!(async move {
-        snapshot_task(stream_reader, stream_writer, peer_addr, snapshot.as_ref()).await
-    });
+        // Request is in-flight until its response is written
+        drop(permit);
+    }
 }
 
-#[instrument(skip(stream_reader, stream_writer, snapshot))]
-async fn snapshot_task<R, W, S>(
+#[instrument(skip(runtime, stream_reader, limiter, handle_sender, dispatch))]
+async fn pipelined_reader_task<R, F>(
+    runtime: &dyn Runtime,
     mut stream_reader: StreamReader<R>,
-    mut stream_writer: StreamWriter<W>,
     peer_addr: String,
-    snapshot: &S,
+    limiter: Arc<InFlightLimiter>,
+    handle_sender: Sender<(JoinHandle<Response>, Option<InFlightPermit>)>,
+    mut dispatch: F,
 ) where
     R: Read + Unpin,
-    W: Write + Unpin,
-    S: Snapshot,
+    F: FnMut(RequestValue) -> Execution,
 {
     while let Ok(request) = stream_reader.read().await {
         match request {
@@ -327,18 +500,214 @@ async fn snapshot_task<R, W, S>(
             Some(request) => {
                 let request: Request = request;
 
-                let response = match request.value {
+                let pending = match request.value {
                     None => {
                         debug!(message = "Received empty value in request", ?request);
-                        Response::default()
+                        (JoinHandle::ready(Response::default()), None)
                     }
-                    Some(request_value) => handle_snapshot_request(snapshot, request_value).await,
+                    Some(request_value) => match dispatch(request_value) {
+                        Execution::Concurrent(future) => {
+                            // Stops reading new requests from socket when too many requests are in-flight
+                            let permit = limiter.acquire().await;
+                            debug!(
+                                message = "Queued request",
+                                queue_depth = permit.queue_depth()
+                            );
+
+                            (runtime.spawn_with_handle(future), Some(permit))
+                        }
+                        Execution::Sequential(future) => (JoinHandle::ready(future.await), None),
+                    },
                 };
 
-                if let Err(err) = stream_writer.write(response).await {
-                    error!(message = "Error while writing to stream", %err);
+                handle_sender
+                    .send(pending)
+                    .await
+                    .expect("Channel receiver dropped");
+            }
+        }
+    }
+}
+
+/// Reads requests from mempool connection and executes all the `CheckTx` requests which are already buffered (up to
+/// `batch_limit`) as a single batch. Responses are still written one per request, in order.
+#[instrument(skip(
+    runtime,
+    stream_reader,
+    mempool,
+    limiter,
+    commit_barrier,
+    recheck_tracker,
+    watchdog,
+    metrics,
+    handle_sender
+))]
+#[allow(clippy::too_many_arguments)]
+async fn batched_mempool_reader_task<R, M>(
+    runtime: &dyn Runtime,
+    mut stream_reader: StreamReader<R>,
+    peer_addr: String,
+    mempool: Arc<M>,
+    limiter: Arc<InFlightLimiter>,
+    batch_limit: usize,
+    commit_barrier: Option<Arc<CommitBarrier>>,
+    recheck_tracker: Arc<RecheckTracker>,
+    watchdog: Watchdog,
+    metrics: Arc<Metrics>,
+    handle_sender: Sender<(JoinHandle<Response>, Option<InFlightPermit>)>,
+) where
+    R: Read + Unpin,
+    M: Mempool + 'static,
+{
+    // Request read while collecting a batch which could not be added to it
+    let mut pending_request: Option<Request> = None;
+    let mut read_failed = false;
+
+    while !read_failed {
+        let request = match pending_request.take() {
+            Some(request) => request,
+            None => match stream_reader.read().await {
+                Ok(Some(request)) => request,
+                Ok(None) => {
+                    debug!(message = "Received empty request");
+                    continue;
+                }
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                    info!(message = "Peer connection closed");
+                    break;
                 }
+                Err(err) => {
+                    error!(message = "Error while receiving ABCI request from socket", %err);
+                    break;
+                }
+            },
+        };
+
+        if let Some(ref request_value) = request.value {
+            recheck_tracker.on_request(request_value);
+        }
+
+        let pending = match request.value {
+            None => {
+                debug!(message = "Received empty value in request", ?request);
+                vec![(JoinHandle::ready(Response::default()), None)]
             }
+            Some(RequestValue::CheckTx(check_tx_request)) => {
+                // Stops reading new requests from socket when too many requests are in-flight
+                let mut permits = vec![limiter.acquire().await];
+                let mut batch = vec![check_tx_request];
+
+                // Only collects requests which are already buffered so that a batch never waits for more requests
+                while batch.len() < batch_limit {
+                    match stream_reader.read_buffered::<Request>() {
+                        // A batch only contains `CheckTx` requests of the same type (new or recheck)
+                        Ok(Some(Request {
+                            value: Some(RequestValue::CheckTx(check_tx_request)),
+                        })) if check_tx_request.r#type == batch[0].r#type => {
+                            match limiter.try_acquire() {
+                                Some(permit) => {
+                                    recheck_tracker.on_check_tx(&check_tx_request);
+                                    permits.push(permit);
+                                    batch.push(check_tx_request);
+                                }
+                                None => {
+                                    pending_request = Some(Request {
+                                        value: Some(RequestValue::CheckTx(check_tx_request)),
+                                    });
+                                    break;
+                                }
+                            }
+                        }
+                        Ok(Some(request)) => {
+                            pending_request = Some(request);
+                            break;
+                        }
+                        Ok(None) => break,
+                        Err(err) => {
+                            // Requests collected so far are still executed before the connection is closed
+                            error!(message = "Error while receiving ABCI request from socket", %err);
+                            read_failed = true;
+                            break;
+                        }
+                    }
+                }
+
+                debug!(
+                    message = "Queued CheckTx batch",
+                    batch_size = batch.len(),
+                    queue_depth = permits[0].queue_depth()
+                );
+
+                let (senders, handles): (Vec<_>, Vec<_>) =
+                    batch.iter().map(|_| JoinHandle::pair()).unzip();
+
+                let mempool = mempool.clone();
+                let recheck_tracker = recheck_tracker.clone();
+                let watchdog = watchdog.clone();
+                let metrics = metrics.clone();
+                let ticket = commit_barrier
+                    .as_ref()
+                    .map(|barrier| barrier.register(batch[0].r#type() == CheckTxType::Recheck));
+
+                runtime.spawn(Box::pin(async move {
+                    let _guard = match ticket {
+                        Some(ref ticket) => Some(ticket.wait().await),
+                        None => None,
+                    };
+
+                    let responses = handle_check_tx_batch(
+                        mempool.as_ref(),
+                        recheck_tracker.as_ref(),
+                        &watchdog,
+                        &metrics,
+                        batch,
+                    )
+                    .await;
+
+                    for (sender, response) in senders.into_iter().zip(responses) {
+                        let _ = sender.try_send(response);
+                    }
+                }));
+
+                handles
+                    .into_iter()
+                    .zip(permits.into_iter().map(Some))
+                    .collect()
+            }
+            Some(request_value) => {
+                let permit = limiter.acquire().await;
+                let mempool = mempool.clone();
+                let recheck_tracker = recheck_tracker.clone();
+                let watchdog = watchdog.clone();
+                let metrics = metrics.clone();
+                let ticket = register_check_tx(commit_barrier.as_ref(), &request_value);
+
+                vec![(
+                    runtime.spawn_with_handle(async move {
+                        let _guard = match ticket {
+                            Some(ref ticket) => Some(ticket.wait().await),
+                            None => None,
+                        };
+
+                        handle_mempool_request(
+                            mempool.as_ref(),
+                            recheck_tracker.as_ref(),
+                            &watchdog,
+                            &metrics,
+                            request_value,
+                        )
+                        .await
+                    }),
+                    Some(permit),
+                )]
+            }
+        };
+
+        for pending in pending {
+            handle_sender
+                .send(pending)
+                .await
+                .expect("Channel receiver dropped");
         }
     }
 }
@@ -3,19 +3,1094 @@ mod request_generator;
 
 use std::time::{Duration, Instant};
 
+use bytes::BytesMut;
+use futures_lite::io::AsyncWriteExt;
 use mock_io::tokio::{MockListener, MockStream};
 use tendermint_proto::abci::{
     response::Value as ResponseValue, Request, Response, ResponseException,
 };
 use tokio::spawn;
 
-use crate::{types::ResponseCheckTx, utils::get_stream_pair, Address};
+use crate::{
+    stream_split::StreamSplit,
+    types::{
+        decode, encode, ResponseCheckTx, ResponseDeliverTx, ResponseLoadSnapshotChunk,
+        ResponseQuery,
+    },
+    utils::{get_stream_pair, StreamReader},
+    Address, ConnectionType, DeadlineAction, DuplicateConnectionPolicy, Method, PeerAllowlist,
+    QueueMetrics,
+};
+
+#[cfg(feature = "tls")]
+mod tls_client {
+    use std::{
+        io::{BufReader, Result},
+        net::{SocketAddr, TcpStream},
+        sync::Arc,
+        time::Duration,
+    };
+
+    use futures_rustls::rustls::{
+        Certificate, ClientConfig, ClientConnection, PrivateKey, RootCertStore, StreamOwned,
+    };
+
+    pub const CA: &[u8] = include_bytes!("tests/tls/ca.pem");
+    pub const SERVER_CERT: &[u8] = include_bytes!("tests/tls/server.pem");
+    pub const SERVER_KEY: &[u8] = include_bytes!("tests/tls/server.key");
+    const CLIENT_CERT: &[u8] = include_bytes!("tests/tls/client.pem");
+    const CLIENT_KEY: &[u8] = include_bytes!("tests/tls/client.key");
+
+    /// Connects to a TLS server (presenting client certificate if `client_auth` is `true`)
+    pub fn connect(
+        addr: SocketAddr,
+        client_auth: bool,
+    ) -> Result<StreamOwned<ClientConnection, TcpStream>> {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(&Certificate(
+                rustls_pemfile::certs(&mut BufReader::new(CA))?.remove(0),
+            ))
+            .unwrap();
+
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+
+        let config = if client_auth {
+            builder
+                .with_client_auth_cert(
+                    vec![Certificate(
+                        rustls_pemfile::certs(&mut BufReader::new(CLIENT_CERT))?.remove(0),
+                    )],
+                    PrivateKey(
+                        rustls_pemfile::pkcs8_private_keys(&mut BufReader::new(CLIENT_KEY))?
+                            .remove(0),
+                    ),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let connection =
+            ClientConnection::new(Arc::new(config), "localhost".try_into().unwrap()).unwrap();
+
+        let stream = super::connect_blocking(|| TcpStream::connect(addr))?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+        Ok(StreamOwned::new(connection, stream))
+    }
+}
+
+async fn initialize_server() -> (MockStream, MockStream) {
+    let server = counter::server();
+
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    (
+        MockStream::connect(&handle).unwrap(),
+        MockStream::connect(&handle).unwrap(),
+    )
+}
+
+/// Connects to a server which may not be listening yet (retries for up to 5 seconds)
+fn connect_blocking<S>(connect: impl Fn() -> std::io::Result<S>) -> std::io::Result<S> {
+    let start = Instant::now();
+
+    loop {
+        match connect() {
+            Ok(stream) => return Ok(stream),
+            Err(_) if start.elapsed() < Duration::from_secs(5) => {
+                std::thread::sleep(Duration::from_millis(10))
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Sends an `Info` request over a blocking stream and reads its response
+fn request_info_blocking<S>(stream: &mut S) -> std::io::Result<Response>
+where
+    S: std::io::Read + std::io::Write,
+{
+    request_blocking(stream, request_generator::info())
+}
+
+/// Sends a request over a blocking stream and reads its response
+fn request_blocking<S>(stream: &mut S, request: Request) -> std::io::Result<Response>
+where
+    S: std::io::Read + std::io::Write,
+{
+    let mut buf = BytesMut::new();
+    encode(request, &mut buf).unwrap();
+    stream.write_all(&buf)?;
+
+    let mut buf = BytesMut::new();
+    let mut read_buf = [0; 1024];
+
+    loop {
+        if let Some(response) = decode(&mut buf)? {
+            return Ok(response);
+        }
+
+        let len = stream.read(&mut read_buf)?;
+
+        if len == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        buf.extend_from_slice(&read_buf[..len]);
+    }
+}
+
+async fn initialize_server_with_state(counter: u64, block_height: i64) -> (MockStream, MockStream) {
+    let server = counter::server_with_state(counter, block_height);
+
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    (
+        MockStream::connect(&handle).unwrap(),
+        MockStream::connect(&handle).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn check_concurrent_check_tx_requests() {
+    let (info_stream, mempool_stream) = initialize_server().await;
+
+    let (mut info_stream_reader, mut info_stream_writer) = get_stream_pair(info_stream);
+    let (mut mempool_stream_reader, mut mempool_stream_writer) = get_stream_pair(mempool_stream);
+
+    // First, tendermint calls `info` to get information about ABCI application
+    let request = request_generator::info();
+    info_stream_writer.write(request).await.unwrap();
+    let response: Response = info_stream_reader.read().await.unwrap().unwrap();
+    assert!(response.value.is_some());
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+
+    // Send one `check_tx` for mempool task scheduling
+    let request = request_generator::check_tx(1, false);
+    mempool_stream_writer.write(request).await.unwrap();
+    let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    assert!(response.value.is_some());
+    assert!(matches!(response.value.unwrap(), ResponseValue::CheckTx(_)));
+
+    // Sent three `check_tx` requests and check if all run concurrently and responses are received
+    // in order
+    let start_time = Instant::now();
+
+    // This request will take 2 seconds to execute (see `check_tx` implementation in `counter.rs`)
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, true))
+        .await
+        .unwrap();
+    // This request will take 2 seconds to execute (see `check_tx` implementation in `counter.rs`)
+    mempool_stream_writer
+        .write(request_generator::check_tx(2, true))
+        .await
+        .unwrap();
+    // This request will get executed immediately (see `check_tx` implementation in `counter.rs`)
+    mempool_stream_writer
+        .write(request_generator::check_tx(3, true))
+        .await
+        .unwrap();
+    let response1: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    let response2: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    let response3: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    let duration = Instant::now() - start_time;
+
+    // To check if all the requests executed concurrently, we check if all the responses were
+    // returned within 4 seconds and in order.
+    assert!(duration < Duration::from_secs(4));
+
+    assert!(response1.value.is_some());
+    assert!(matches!(
+        response1.value.unwrap(),
+        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 1u64.to_be_bytes().to_vec()
+    ));
+    assert!(response2.value.is_some());
+    assert!(matches!(
+        response2.value.unwrap(),
+        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 2u64.to_be_bytes().to_vec()
+    ));
+    assert!(response3.value.is_some());
+    assert!(matches!(
+        response3.value.unwrap(),
+        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 3u64.to_be_bytes().to_vec()
+    ));
+}
+
+#[tokio::test]
+async fn check_in_flight_check_tx_limit() {
+    let server = counter::server().with_max_in_flight_check_tx(1);
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    let (mempool_stream, server) = {
+        let server = std::sync::Arc::new(server);
+        let server_clone = server.clone();
+
+        spawn(async move {
+            server_clone
+                .run(address)
+                .await
+                .expect("Unable to start ABCI server");
+        });
+
+        (MockStream::connect(&handle).unwrap(), server)
+    };
+
+    let (mut mempool_stream_reader, mut mempool_stream_writer) = get_stream_pair(mempool_stream);
+
+    // Send one `check_tx` for mempool task scheduling
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, false))
+        .await
+        .unwrap();
+    let _: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    let start_time = Instant::now();
+
+    // This request will take 2 seconds to execute (see `check_tx` implementation in `counter.rs`)
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, true))
+        .await
+        .unwrap();
+    // This request would get executed immediately but has to wait for the first one to complete
+    mempool_stream_writer
+        .write(request_generator::check_tx(2, false))
+        .await
+        .unwrap();
+
+    let response1: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    let response2: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    assert!(Instant::now() - start_time >= Duration::from_secs(2));
+
+    assert!(matches!(
+        response1.value.unwrap(),
+        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 1u64.to_be_bytes().to_vec()
+    ));
+    assert!(matches!(
+        response2.value.unwrap(),
+        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 2u64.to_be_bytes().to_vec()
+    ));
+
+    assert_eq!(
+        QueueMetrics {
+            limit: 1,
+            in_flight: 0,
+            peak_in_flight: 1,
+            saturated_count: 1,
+        },
+        server.check_tx_queue_metrics()
+    );
+}
+
+#[cfg(feature = "use-std")]
+#[test]
+fn check_thread_per_task_in_flight_check_tx_limit() {
+    use crate::{
+        async_api::{
+            DEFAULT_MAX_IN_FLIGHT_CHECK_TX, DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX,
+        },
+        runtime::StdRuntime,
+    };
+
+    assert_eq!(
+        DEFAULT_MAX_IN_FLIGHT_CHECK_TX,
+        counter::server().check_tx_queue_metrics().limit
+    );
+    assert_eq!(
+        DEFAULT_THREAD_PER_TASK_MAX_IN_FLIGHT_CHECK_TX,
+        counter::server_with_runtime(StdRuntime)
+            .check_tx_queue_metrics()
+            .limit
+    );
+}
+
+#[tokio::test]
+async fn check_check_tx_batch() {
+    let server = counter::server().with_check_tx_batch_limit(2);
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let (mempool_reader, mut mempool_writer) = MockStream::connect(&handle).unwrap().split_stream();
+    let mut mempool_stream_reader = StreamReader::new(mempool_reader);
+
+    // Send one `check_tx` for mempool task scheduling
+    let mut buf = BytesMut::new();
+    encode(request_generator::check_tx(1, false), &mut buf).unwrap();
+    mempool_writer.write_all(&buf).await.unwrap();
+    let _: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    // Send three `check_tx` requests in a single write so that they are buffered together. First two requests are
+    // executed as one batch (because of batch limit) and third one in a separate batch.
+    let mut buf = BytesMut::new();
+    for counter in 1..=3 {
+        encode(request_generator::check_tx(counter, false), &mut buf).unwrap();
+    }
+    mempool_writer.write_all(&buf).await.unwrap();
+
+    for (counter, batch_size) in [(1u64, 2), (2, 2), (3, 1)] {
+        let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+        assert!(matches!(
+            response.value.unwrap(),
+            ResponseValue::CheckTx(ResponseCheckTx { data, info, .. })
+                if data == counter.to_be_bytes().to_vec() && info == format!("batch size: {}", batch_size)
+        ));
+    }
+}
+
+#[tokio::test]
+async fn check_recheck_cache() {
+    let server = counter::server().with_recheck_cache(10);
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    let (mempool_stream, server) = {
+        let server = std::sync::Arc::new(server);
+        let server_clone = server.clone();
+
+        spawn(async move {
+            server_clone
+                .run(address)
+                .await
+                .expect("Unable to start ABCI server");
+        });
+
+        (MockStream::connect(&handle).unwrap(), server)
+    };
+
+    let (mut mempool_stream_reader, mut mempool_stream_writer) = get_stream_pair(mempool_stream);
+
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, false))
+        .await
+        .unwrap();
+    let _: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    let start_time = Instant::now();
+
+    // Recheck would take 2 seconds to execute (see `check_tx` implementation in `counter.rs`) but the response is
+    // cached when transaction was first checked
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, true))
+        .await
+        .unwrap();
+    let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    assert!(Instant::now() - start_time < Duration::from_secs(2));
+    assert!(matches!(
+        response.value.unwrap(),
+        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 1u64.to_be_bytes().to_vec()
+    ));
+
+    // Recheck round completes when a request other than recheck is received
+    mempool_stream_writer
+        .write(request_generator::flush())
+        .await
+        .unwrap();
+    let _: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    let stats = server.recheck_stats();
+    assert_eq!(1, stats.rounds);
+    assert_eq!(1, stats.rechecks);
+    assert_eq!(1, stats.cache_hits);
+}
+
+#[tokio::test]
+async fn check_hard_deadline() {
+    let server = counter::server()
+        .with_soft_deadline(Method::CheckTx, Duration::from_millis(100))
+        .with_hard_deadline(
+            Method::CheckTx,
+            Duration::from_millis(300),
+            DeadlineAction::Exception,
+        );
+
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let mempool_stream = MockStream::connect(&handle).unwrap();
+    let (mut mempool_stream_reader, mut mempool_stream_writer) = get_stream_pair(mempool_stream);
+
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, false))
+        .await
+        .unwrap();
+    let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::CheckTx(_)));
+
+    let start_time = Instant::now();
+
+    // Recheck takes 2 seconds to execute (see `check_tx` implementation in `counter.rs`)
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, true))
+        .await
+        .unwrap();
+    let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    assert!(Instant::now() - start_time < Duration::from_secs(2));
+    assert!(matches!(
+        response.value.unwrap(),
+        ResponseValue::Exception(ResponseException { error }) if error.contains("hard deadline")
+    ));
+}
+
+#[cfg(all(feature = "sync-api", feature = "use-std"))]
+#[test]
+fn check_sync_hard_deadline_on_thread_per_task_runtime() {
+    use std::net::{SocketAddr, TcpStream};
+
+    use crate::{
+        runtime::StdRuntime,
+        sync_api::{self, ExecutionMode},
+        types::{
+            RequestBeginBlock, RequestCheckTx, RequestCommit, RequestDeliverTx, RequestEndBlock,
+            RequestInfo, RequestInitChain, ResponseBeginBlock, ResponseCheckTx, ResponseCommit,
+            ResponseDeliverTx, ResponseEndBlock, ResponseInfo, ResponseInitChain,
+        },
+    };
+
+    struct NoopConsensus;
+
+    impl sync_api::Consensus for NoopConsensus {
+        fn init_chain(&self, _init_chain_request: RequestInitChain) -> ResponseInitChain {
+            Default::default()
+        }
+
+        fn begin_block(&self, _begin_block_request: RequestBeginBlock) -> ResponseBeginBlock {
+            Default::default()
+        }
+
+        fn deliver_tx(&self, _deliver_tx_request: RequestDeliverTx) -> ResponseDeliverTx {
+            Default::default()
+        }
+
+        fn end_block(&self, _end_block_request: RequestEndBlock) -> ResponseEndBlock {
+            Default::default()
+        }
+
+        fn commit(&self, _commit_request: RequestCommit) -> ResponseCommit {
+            Default::default()
+        }
+    }
+
+    struct NoopMempool;
+
+    impl sync_api::Mempool for NoopMempool {
+        fn check_tx(&self, _check_tx_request: RequestCheckTx) -> ResponseCheckTx {
+            Default::default()
+        }
+    }
+
+    struct SlowInfo;
+
+    impl sync_api::Info for SlowInfo {
+        fn info(&self, _info_request: RequestInfo) -> ResponseInfo {
+            std::thread::sleep(Duration::from_secs(2));
+            Default::default()
+        }
+    }
+
+    struct NoopSnapshot;
+
+    impl sync_api::Snapshot for NoopSnapshot {}
+
+    let server = sync_api::Server::with_runtime(
+        NoopConsensus,
+        NoopMempool,
+        SlowInfo,
+        NoopSnapshot,
+        ExecutionMode::ThreadPool,
+        StdRuntime,
+    )
+    .with_hard_deadline(
+        Method::Info,
+        Duration::from_millis(300),
+        DeadlineAction::Exception,
+    );
+
+    let bound = server
+        .bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+        .unwrap();
+    let addr = match bound.local_addr() {
+        Address::Tcp(addr) => *addr,
+        addr => panic!("Unexpected local address: {}", addr),
+    };
+
+    std::thread::spawn(move || bound.serve());
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let start_time = Instant::now();
+    let response = request_info_blocking(&mut stream).unwrap();
+
+    assert!(Instant::now() - start_time < Duration::from_secs(2));
+    assert!(matches!(
+        response.value.unwrap(),
+        ResponseValue::Exception(ResponseException { error }) if error.contains("hard deadline")
+    ));
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn check_metrics_endpoint() {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+    };
+
+    let server = counter::server();
+
+    let metrics_addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    server.serve_metrics(metrics_addr).await.unwrap();
+
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let mempool_stream = MockStream::connect(&handle).unwrap();
+    let (mut mempool_stream_reader, mut mempool_stream_writer) = get_stream_pair(mempool_stream);
+
+    for _ in 0..2 {
+        mempool_stream_writer
+            .write(request_generator::check_tx(1, false))
+            .await
+            .unwrap();
+        let _: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    }
+
+    let get = |path: &'static str| {
+        tokio::task::spawn_blocking(move || {
+            let mut stream = TcpStream::connect(metrics_addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        })
+    };
+
+    let response = get("/metrics").await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("abci_requests_total{connection=\"mempool\",method=\"check_tx\"}"));
+    assert!(response.contains("abci_request_bytes_total{connection=\"mempool\"}"));
+    assert!(response.contains("abci_check_tx_in_flight 0"));
+
+    let response = get("/unknown").await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn check_mutual_tls() {
+    use std::{io::Result, net::TcpListener};
+
+    use crate::{
+        tests::tls_client::{CA, SERVER_CERT, SERVER_KEY},
+        types::ResponseEcho,
+        TlsConfig,
+    };
+
+    let tls_config = TlsConfig::new(SERVER_CERT, SERVER_KEY)
+        .unwrap()
+        .with_client_ca(CA)
+        .unwrap();
+    let server = counter::server().with_tls(tls_config);
+
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    spawn(async move {
+        server.run(addr).await.expect("Unable to start ABCI server");
+    });
+
+    let send = move |client_auth: bool, request: Request| -> Result<Response> {
+        request_blocking(&mut tls_client::connect(addr, client_auth)?, request)
+    };
+
+    let response = tokio::task::spawn_blocking(move || send(true, request_generator::info()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+
+    // Responses larger than a TLS record and than socket buffers (so that writes are partial) are written completely
+    let message = "a".repeat(16 * 1024 * 1024);
+    let request = request_generator::echo(message.clone());
+
+    let response = tokio::task::spawn_blocking(move || send(true, request))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        response.value.unwrap(),
+        ResponseValue::Echo(ResponseEcho { message: echoed }) if echoed == message
+    ));
+
+    // Clients without a certificate signed by client CA are rejected
+    assert!(
+        tokio::task::spawn_blocking(move || send(false, request_generator::info()))
+            .await
+            .unwrap()
+            .is_err()
+    );
+}
+
+#[cfg(all(feature = "tls", feature = "use-std"))]
+#[test]
+fn check_tls_on_thread_per_task_runtime() {
+    use std::net::SocketAddr;
+
+    use crate::{
+        runtime::{Runtime, StdRuntime},
+        tests::tls_client::{SERVER_CERT, SERVER_KEY},
+        TlsConfig,
+    };
+
+    let tls_config = TlsConfig::new(SERVER_CERT, SERVER_KEY)
+        .unwrap()
+        .with_handshake_timeout(Duration::from_millis(200));
+    let server = counter::server_with_runtime(StdRuntime).with_tls(tls_config);
+    let mut bound = None;
+
+    StdRuntime
+        .block_on(Box::pin(async {
+            bound = Some(
+                server
+                    .bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+                    .await?,
+            );
+            Ok(())
+        }))
+        .unwrap();
+
+    let bound = bound.unwrap();
+    let addr = match bound.local_addr() {
+        Address::Tcp(addr) => *addr,
+        addr => panic!("Unexpected local address: {}", addr),
+    };
+
+    let _handle = bound.spawn();
+
+    // Mempool connection reads next request while the response of `CheckTx` is being written
+    let mut stream = tls_client::connect(addr, false).unwrap();
+
+    let response = request_blocking(&mut stream, request_generator::check_tx(1, false)).unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::CheckTx(_)));
+
+    let response = request_blocking(&mut stream, request_generator::flush()).unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Flush(_)));
+
+    // Connection stays usable after the handshake timeout
+    std::thread::sleep(Duration::from_millis(300));
+
+    let response = request_blocking(&mut stream, request_generator::flush()).unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Flush(_)));
+
+    // Blocked handshakes time out as well
+    check_tls_handshake_timeout(addr);
+}
+
+/// Connects to a TLS server without starting the handshake and checks that server closes the connection
+#[cfg(feature = "tls")]
+fn check_tls_handshake_timeout(addr: std::net::SocketAddr) {
+    use std::{io::Read, net::TcpStream};
+
+    let mut stream = connect_blocking(|| TcpStream::connect(addr)).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let start_time = Instant::now();
+
+    match stream.read(&mut [0; 16]) {
+        Ok(len) => assert_eq!(0, len),
+        Err(err) => assert_eq!(std::io::ErrorKind::ConnectionReset, err.kind()),
+    }
+
+    assert!(Instant::now() - start_time < Duration::from_secs(2));
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn check_tls_handshake_timeout_on_async_runtime() {
+    use std::net::SocketAddr;
+
+    use crate::{
+        tests::tls_client::{SERVER_CERT, SERVER_KEY},
+        TlsConfig,
+    };
+
+    let tls_config = TlsConfig::new(SERVER_CERT, SERVER_KEY)
+        .unwrap()
+        .with_handshake_timeout(Duration::from_millis(200));
+    let server = counter::server().with_tls(tls_config);
+
+    let bound = server
+        .bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+        .await
+        .unwrap();
+    let addr = match bound.local_addr() {
+        Address::Tcp(addr) => *addr,
+        addr => panic!("Unexpected local address: {}", addr),
+    };
+
+    let _handle = bound.spawn();
+
+    tokio::task::spawn_blocking(move || check_tls_handshake_timeout(addr))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn check_single_connection_per_type() {
+    let server = counter::server()
+        .with_peer_allowlist(PeerAllowlist::new().with_single_connection_per_type(true));
+
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let (mut first_reader, mut first_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+    let (mut second_reader, mut second_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+
+    first_writer.write(request_generator::info()).await.unwrap();
+    let response: Response = first_reader.read().await.unwrap().unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+
+    // Second info connection is closed without executing its request
+    second_writer
+        .write(request_generator::info())
+        .await
+        .unwrap();
+    assert!(second_reader.read::<Response>().await.is_err());
+
+    // Connections of other types are not affected
+    let (mut mempool_reader, mut mempool_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+
+    mempool_writer
+        .write(request_generator::check_tx(1, false))
+        .await
+        .unwrap();
+    let response: Response = mempool_reader.read().await.unwrap().unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::CheckTx(_)));
+}
+
+#[tokio::test]
+async fn check_replace_duplicate_connection() {
+    let server = counter::server().with_peer_allowlist(
+        PeerAllowlist::new().with_duplicate_connection_policy(
+            ConnectionType::Info,
+            DuplicateConnectionPolicy::Replace,
+        ),
+    );
+
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let (mut first_reader, mut first_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+    let (mut second_reader, mut second_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+
+    for (reader, writer) in [
+        (&mut first_reader, &mut first_writer),
+        (&mut second_reader, &mut second_writer),
+    ] {
+        writer.write(request_generator::info()).await.unwrap();
+        let response: Response = reader.read().await.unwrap().unwrap();
+        assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+    }
+
+    // First connection is closed once it is replaced by second connection
+    let _ = first_writer.write(request_generator::info()).await;
+    assert!(first_reader.read::<Response>().await.is_err());
+
+    second_writer
+        .write(request_generator::info())
+        .await
+        .unwrap();
+    let response: Response = second_reader.read().await.unwrap().unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+}
+
+#[tokio::test]
+async fn check_peer_allowlist() {
+    use std::net::{SocketAddr, TcpStream};
+
+    let server = counter::server()
+        .with_peer_allowlist(PeerAllowlist::new().allow_ip_range("10.0.0.0/8".parse().unwrap()));
+
+    let bound = server
+        .bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+        .await
+        .unwrap();
+    let addr = match bound.local_addr() {
+        Address::Tcp(addr) => *addr,
+        addr => panic!("Unexpected local address: {}", addr),
+    };
+
+    let _handle = bound.spawn();
+
+    let result =
+        tokio::task::spawn_blocking(move || request_info_blocking(&mut TcpStream::connect(addr)?))
+            .await
+            .unwrap();
+    assert!(result.is_err());
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        use std::os::unix::net::UnixStream;
+
+        use crate::runtime::peer_uid;
+
+        let uid = peer_uid(&UnixStream::pair().unwrap().0).unwrap();
+
+        for (allowed_uid, allowed) in [(uid, true), (uid.wrapping_add(1), false)] {
+            let path = std::env::temp_dir().join(format!(
+                "abci-allowlist-{}-{}.sock",
+                std::process::id(),
+                allowed
+            ));
+            let _ = std::fs::remove_file(&path);
+
+            let server =
+                counter::server().with_peer_allowlist(PeerAllowlist::new().allow_uid(allowed_uid));
+
+            let server_path = path.clone();
+            spawn(async move {
+                server
+                    .run(server_path)
+                    .await
+                    .expect("Unable to start ABCI server");
+            });
+
+            let client_path = path.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                request_info_blocking(&mut connect_blocking(|| UnixStream::connect(&client_path))?)
+            })
+            .await
+            .unwrap();
+            assert_eq!(allowed, result.is_ok());
+
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+}
+
+#[tokio::test]
+async fn check_host_address() {
+    use std::net::{TcpListener, TcpStream};
+
+    let server = counter::server();
+
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let address: Address = format!("tcp://localhost:{}", port).parse().unwrap();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let response = tokio::task::spawn_blocking(move || {
+        request_info_blocking(&mut connect_blocking(|| {
+            TcpStream::connect(("localhost", port))
+        })?)
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+}
+
+#[tokio::test]
+async fn check_pre_bound_listeners() {
+    use std::net::{TcpListener, TcpStream};
+
+    let server = std::sync::Arc::new(counter::server());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let tcp_server = server.clone();
+    spawn(async move {
+        tcp_server
+            .run(listener)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let response = tokio::task::spawn_blocking(move || {
+        request_info_blocking(&mut connect_blocking(|| TcpStream::connect(addr))?)
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        let path = std::env::temp_dir().join(format!("abci-pre-bound-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        spawn(async move {
+            server
+                .run(listener)
+                .await
+                .expect("Unable to start ABCI server");
+        });
+
+        let response = tokio::task::spawn_blocking(move || {
+            request_info_blocking(&mut connect_blocking(|| UnixStream::connect(&path))?)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn check_unix_socket_lifecycle() {
+    use std::os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    };
+
+    use crate::UnixSocketConfig;
+
+    let path = std::env::temp_dir().join(format!("abci-lifecycle-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    // Leaves a stale socket file behind
+    drop(UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let config = UnixSocketConfig::new()
+        .with_remove_stale(true)
+        .with_remove_on_shutdown(true)
+        .with_mode(0o600);
+
+    let server = counter::server().with_unix_socket_config(config.clone());
+    let server_path = path.clone();
+    let handle = spawn(async move { server.run(server_path).await });
+
+    let client_path = path.clone();
+    let response = tokio::task::spawn_blocking(move || {
+        request_info_blocking(&mut connect_blocking(|| UnixStream::connect(&client_path))?)
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+
+    assert_eq!(
+        0o600,
+        std::fs::metadata(&path).unwrap().permissions().mode() & 0o777
+    );
+
+    // A socket with a listening server is not removed
+    let err = counter::server()
+        .with_unix_socket_config(config)
+        .run(path.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(std::io::ErrorKind::AddrInUse, err.kind());
+
+    handle.abort();
+    assert!(handle.await.unwrap_err().is_cancelled());
+    assert!(!path.exists());
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[tokio::test]
+async fn check_abstract_unix_socket() {
+    #[cfg(target_os = "android")]
+    use std::os::android::net::SocketAddrExt;
+    #[cfg(target_os = "linux")]
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixStream};
 
-async fn initialize_server() -> (MockStream, MockStream) {
     let server = counter::server();
 
-    let (listener, handle) = MockListener::new();
-    let address: Address = listener.into();
+    let name = format!("abci-abstract-{}", std::process::id());
+    let address: Address = format!("unix://@{}", name).parse().unwrap();
 
     spawn(async move {
         server
@@ -24,96 +1099,310 @@ async fn initialize_server() -> (MockStream, MockStream) {
             .expect("Unable to start ABCI server");
     });
 
-    (
-        MockStream::connect(&handle).unwrap(),
-        MockStream::connect(&handle).unwrap(),
-    )
+    let response = tokio::task::spawn_blocking(move || {
+        let addr = SocketAddr::from_abstract_name(name.as_bytes())?;
+        request_info_blocking(&mut connect_blocking(|| UnixStream::connect_addr(&addr))?)
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
 }
 
-async fn initialize_server_with_state(counter: u64, block_height: i64) -> (MockStream, MockStream) {
-    let server = counter::server_with_state(counter, block_height);
+#[tokio::test]
+async fn check_multiple_addresses() {
+    use std::net::{TcpListener, TcpStream};
+
+    let server = std::sync::Arc::new(counter::server());
+
+    let err = server
+        .run_all(Vec::<Address>::new())
+        .await
+        .expect_err("Server cannot run without addresses");
+    assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+
+    let tcp_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let tcp_addr = tcp_listener.local_addr().unwrap();
+
+    let mut addrs = vec![Address::from(tcp_listener)];
+
+    #[cfg(unix)]
+    let path = std::env::temp_dir().join(format!("abci-multiple-{}.sock", std::process::id()));
+    #[cfg(unix)]
+    {
+        let _ = std::fs::remove_file(&path);
+        addrs.push(Address::from(path.clone()));
+    }
+
+    let run_server = server.clone();
+    spawn(async move {
+        run_server
+            .run_all(addrs)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    // Keeps the connections open to check that they are tracked by the same server
+    let (response, _tcp_stream) = tokio::task::spawn_blocking(move || {
+        let mut stream = connect_blocking(|| TcpStream::connect(tcp_addr))?;
+        request_info_blocking(&mut stream).map(|response| (response, stream))
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+    assert_eq!(1, server.health().info_connections);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::UnixStream;
+
+        let (response, _unix_stream) = tokio::task::spawn_blocking(move || {
+            let mut stream = connect_blocking(|| UnixStream::connect(&path))?;
+            request_info_blocking(&mut stream).map(|response| (response, stream))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+
+        assert_eq!(2, server.health().info_connections);
+    }
+}
+
+#[tokio::test]
+async fn check_bind_and_spawn() {
+    use std::net::{SocketAddr, TcpStream};
+
+    let server = counter::server();
+
+    let bound = server
+        .bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+        .await
+        .unwrap();
+
+    let addr = match bound.local_addr() {
+        Address::Tcp(addr) => *addr,
+        addr => panic!("Unexpected local address: {}", addr),
+    };
+    assert_ne!(0, addr.port());
+    assert_eq!(1, bound.local_addrs().count());
+
+    let handle = bound.spawn();
+
+    let response =
+        tokio::task::spawn_blocking(move || request_info_blocking(&mut TcpStream::connect(addr)?))
+            .await
+            .unwrap()
+            .unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+
+    // Server keeps serving until accepting a connection fails
+    assert!(tokio::time::timeout(Duration::from_millis(100), handle)
+        .await
+        .is_err());
+}
+
+#[cfg(feature = "use-std")]
+#[test]
+fn check_thread_per_task_multiple_addresses() {
+    use std::net::{SocketAddr, TcpStream};
+
+    use crate::runtime::{Runtime, StdRuntime};
+
+    let server = counter::server_with_runtime(StdRuntime);
+    let mut bound = None;
+
+    StdRuntime
+        .block_on(Box::pin(async {
+            let addr = "127.0.0.1:0".parse::<SocketAddr>().unwrap();
+            bound = Some(server.bind_all([addr, addr]).await?);
+            Ok(())
+        }))
+        .unwrap();
+
+    let bound = bound.unwrap();
+    let addrs: Vec<SocketAddr> = bound
+        .local_addrs()
+        .map(|addr| match addr {
+            Address::Tcp(addr) => *addr,
+            addr => panic!("Unexpected local address: {}", addr),
+        })
+        .collect();
+
+    let _handle = bound.spawn();
+
+    // Accepting a connection blocks a thread on `StdRuntime`, so, every listener should be served on its own thread
+    for addr in addrs.into_iter().rev() {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let response = request_info_blocking(&mut stream).unwrap();
+        assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
+    }
+}
+
+#[tokio::test]
+async fn check_health_endpoint() {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::Arc,
+    };
+
+    let server = Arc::new(counter::server());
+
+    let health_addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    server.serve_health(health_addr).await.unwrap();
 
     let (listener, handle) = MockListener::new();
     let address: Address = listener.into();
 
+    let running_server = server.clone();
     spawn(async move {
-        server
+        running_server
             .run(address)
             .await
             .expect("Unable to start ABCI server");
     });
 
-    (
-        MockStream::connect(&handle).unwrap(),
-        MockStream::connect(&handle).unwrap(),
-    )
+    let info_stream = MockStream::connect(&handle).unwrap();
+    let (mut info_stream_reader, mut info_stream_writer) = get_stream_pair(info_stream);
+
+    info_stream_writer
+        .write(request_generator::info())
+        .await
+        .unwrap();
+    let _: Response = info_stream_reader.read().await.unwrap().unwrap();
+
+    let health = server.health();
+    assert_eq!(1, health.info_connections);
+    assert_eq!(0, health.consensus_connections);
+    assert_eq!("not_initialized", health.consensus_state);
+    assert!(health.is_healthy());
+    assert!(!health.is_ready());
+
+    let get = |path: &'static str| {
+        tokio::task::spawn_blocking(move || {
+            let mut stream = TcpStream::connect(health_addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        })
+    };
+
+    let response = get("/health").await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("\"info\":1"));
+    assert!(response.contains("\"consensus_state\":\"not_initialized\""));
+
+    let response = get("/ready").await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 503 Service Unavailable"));
+
+    // Connection is no longer counted once the peer closes it
+    drop(info_stream_reader);
+    drop(info_stream_writer);
+
+    let start = Instant::now();
+
+    while server.health().info_connections != 0 {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
 }
 
 #[tokio::test]
-async fn check_concurrent_check_tx_requests() {
-    let (info_stream, mempool_stream) = initialize_server().await;
+async fn check_concurrent_query_requests() {
+    let (info_stream, _) = initialize_server().await;
 
     let (mut info_stream_reader, mut info_stream_writer) = get_stream_pair(info_stream);
-    let (mut mempool_stream_reader, mut mempool_stream_writer) = get_stream_pair(mempool_stream);
 
     // First, tendermint calls `info` to get information about ABCI application
-    let request = request_generator::info();
-    info_stream_writer.write(request).await.unwrap();
+    info_stream_writer
+        .write(request_generator::info())
+        .await
+        .unwrap();
     let response: Response = info_stream_reader.read().await.unwrap().unwrap();
-    assert!(response.value.is_some());
     assert!(matches!(response.value.unwrap(), ResponseValue::Info(_)));
 
-    // Send one `check_tx` for mempool task scheduling
-    let request = request_generator::check_tx(1, false);
-    mempool_stream_writer.write(request).await.unwrap();
-    let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
-    assert!(response.value.is_some());
-    assert!(matches!(response.value.unwrap(), ResponseValue::CheckTx(_)));
-
-    // Sent three `check_tx` requests and check if all run concurrently and responses are received
-    // in order
+    // Send two slow `query` requests followed by `info` and check if queries run concurrently and responses are
+    // received in order
     let start_time = Instant::now();
 
-    // This request will take 2 seconds to execute (see `check_tx` implementation in `counter.rs`)
-    mempool_stream_writer
-        .write(request_generator::check_tx(1, true))
+    info_stream_writer
+        .write(request_generator::query(1, true))
         .await
         .unwrap();
-    // This request will take 2 seconds to execute (see `check_tx` implementation in `counter.rs`)
-    mempool_stream_writer
-        .write(request_generator::check_tx(2, true))
+    info_stream_writer
+        .write(request_generator::query(2, true))
         .await
         .unwrap();
-    // This request will get executed immediately (see `check_tx` implementation in `counter.rs`)
-    mempool_stream_writer
-        .write(request_generator::check_tx(3, true))
+    info_stream_writer
+        .write(request_generator::info())
         .await
         .unwrap();
-    let response1: Response = mempool_stream_reader.read().await.unwrap().unwrap();
-    let response2: Response = mempool_stream_reader.read().await.unwrap().unwrap();
-    let response3: Response = mempool_stream_reader.read().await.unwrap().unwrap();
 
-    let duration = Instant::now() - start_time;
+    let response1: Response = info_stream_reader.read().await.unwrap().unwrap();
+    let response2: Response = info_stream_reader.read().await.unwrap().unwrap();
+    let response3: Response = info_stream_reader.read().await.unwrap().unwrap();
 
-    // To check if all the requests executed concurrently, we check if all the responses were
-    // returned within 4 seconds and in order.
-    assert!(duration < Duration::from_secs(4));
+    assert!(Instant::now() - start_time < Duration::from_secs(4));
 
-    assert!(response1.value.is_some());
     assert!(matches!(
         response1.value.unwrap(),
-        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 1u64.to_be_bytes().to_vec()
+        ResponseValue::Query(ResponseQuery { key, .. }) if key == 1u64.to_be_bytes().to_vec()
     ));
-    assert!(response2.value.is_some());
     assert!(matches!(
         response2.value.unwrap(),
-        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 2u64.to_be_bytes().to_vec()
+        ResponseValue::Query(ResponseQuery { key, .. }) if key == 2u64.to_be_bytes().to_vec()
     ));
-    assert!(response3.value.is_some());
+    assert!(matches!(response3.value.unwrap(), ResponseValue::Info(_)));
+}
+
+#[tokio::test]
+async fn check_concurrent_load_snapshot_chunk_requests() {
+    let (snapshot_stream, _) = initialize_server().await;
+
+    let (mut snapshot_stream_reader, mut snapshot_stream_writer) = get_stream_pair(snapshot_stream);
+
+    // Send one `list_snapshots` for snapshot task scheduling
+    snapshot_stream_writer
+        .write(request_generator::list_snapshots())
+        .await
+        .unwrap();
+    let response: Response = snapshot_stream_reader.read().await.unwrap().unwrap();
     assert!(matches!(
-        response3.value.unwrap(),
-        ResponseValue::CheckTx(ResponseCheckTx { data, .. }) if data == 3u64.to_be_bytes().to_vec()
+        response.value.unwrap(),
+        ResponseValue::ListSnapshots(_)
     ));
+
+    // Send three `load_snapshot_chunk` requests (each takes 2 seconds to execute, see `load_snapshot_chunk`
+    // implementation in `counter.rs`) and check if all run concurrently and responses are received in order
+    let start_time = Instant::now();
+
+    for chunk in 0..3 {
+        snapshot_stream_writer
+            .write(request_generator::load_snapshot_chunk(chunk))
+            .await
+            .unwrap();
+    }
+
+    for chunk in 0..3u32 {
+        let response: Response = snapshot_stream_reader.read().await.unwrap().unwrap();
+        assert!(matches!(
+            response.value.unwrap(),
+            ResponseValue::LoadSnapshotChunk(ResponseLoadSnapshotChunk { chunk: data }) if data == chunk.to_be_bytes().to_vec()
+        ));
+    }
+
+    assert!(Instant::now() - start_time < Duration::from_secs(4));
 }
 
 #[tokio::test]
@@ -265,6 +1554,199 @@ async fn check_valid_abci_flow() {
     assert!(matches!(response.value.unwrap(), ResponseValue::Commit(_)));
 }
 
+#[tokio::test]
+async fn check_parallel_deliver_tx() {
+    let server = counter::server().with_parallel_deliver_tx(true);
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let (mut info_stream_reader, mut info_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+    let (mut consensus_stream_reader, mut consensus_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+
+    info_stream_writer
+        .write(request_generator::info())
+        .await
+        .unwrap();
+    let _: Response = info_stream_reader.read().await.unwrap().unwrap();
+
+    consensus_stream_writer
+        .write(request_generator::init_chain())
+        .await
+        .unwrap();
+    let _: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+
+    consensus_stream_writer
+        .write(request_generator::begin_block(1, Default::default()))
+        .await
+        .unwrap();
+    let response: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+    assert!(matches!(
+        response.value.unwrap(),
+        ResponseValue::BeginBlock(_)
+    ));
+
+    // `deliver_tx` responses are only written after `end_block` request is received
+    for counter in 1..=3 {
+        consensus_stream_writer
+            .write(request_generator::deliver_tx(counter))
+            .await
+            .unwrap();
+    }
+    consensus_stream_writer
+        .write(request_generator::end_block(1))
+        .await
+        .unwrap();
+
+    // Counter only accepts consecutive integers. So, successful responses mean that transactions were executed in order.
+    for _ in 1..=3 {
+        let response: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+        assert!(matches!(
+            response.value.unwrap(),
+            ResponseValue::DeliverTx(ResponseDeliverTx { code: 0, .. })
+        ));
+    }
+
+    let response: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+    assert!(matches!(
+        response.value.unwrap(),
+        ResponseValue::EndBlock(_)
+    ));
+}
+
+#[tokio::test]
+async fn check_commit_barrier() {
+    let server = counter::server().with_commit_barrier(true);
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let (mut info_stream_reader, mut info_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+    let (mut consensus_stream_reader, mut consensus_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+    let (mut mempool_stream_reader, mut mempool_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+
+    info_stream_writer
+        .write(request_generator::info())
+        .await
+        .unwrap();
+    let _: Response = info_stream_reader.read().await.unwrap().unwrap();
+
+    // Send one `check_tx` for mempool task scheduling
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, false))
+        .await
+        .unwrap();
+    let _: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+
+    for request in [
+        request_generator::init_chain(),
+        request_generator::begin_block(1, Default::default()),
+        request_generator::deliver_tx(1),
+        request_generator::end_block(1),
+    ] {
+        consensus_stream_writer.write(request).await.unwrap();
+        let _: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+    }
+
+    let start_time = Instant::now();
+
+    // This request will take 2 seconds to execute (see `check_tx` implementation in `counter.rs`)
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, true))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // Commit waits for running `check_tx` to complete
+    consensus_stream_writer
+        .write(request_generator::commit())
+        .await
+        .unwrap();
+    let response: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+
+    assert!(Instant::now() - start_time >= Duration::from_secs(2));
+    assert!(matches!(response.value.unwrap(), ResponseValue::Commit(_)));
+
+    let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::CheckTx(_)));
+}
+
+#[tokio::test]
+async fn check_commit_barrier_for_first_request() {
+    let server = counter::server().with_commit_barrier(true);
+    let (listener, handle) = MockListener::new();
+    let address: Address = listener.into();
+
+    spawn(async move {
+        server
+            .run(address)
+            .await
+            .expect("Unable to start ABCI server");
+    });
+
+    let (mut info_stream_reader, mut info_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+    let (mut consensus_stream_reader, mut consensus_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+
+    info_stream_writer
+        .write(request_generator::info())
+        .await
+        .unwrap();
+    let _: Response = info_stream_reader.read().await.unwrap().unwrap();
+
+    for request in [
+        request_generator::init_chain(),
+        request_generator::begin_block(1, Default::default()),
+        request_generator::deliver_tx(1),
+        request_generator::end_block(1),
+    ] {
+        consensus_stream_writer.write(request).await.unwrap();
+        let _: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+    }
+
+    let (mut mempool_stream_reader, mut mempool_stream_writer) =
+        get_stream_pair(MockStream::connect(&handle).unwrap());
+
+    let start_time = Instant::now();
+
+    // First request on mempool connection takes 2 seconds to execute and should also be waited for by commit
+    mempool_stream_writer
+        .write(request_generator::check_tx(1, true))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    consensus_stream_writer
+        .write(request_generator::commit())
+        .await
+        .unwrap();
+    let response: Response = consensus_stream_reader.read().await.unwrap().unwrap();
+
+    assert!(Instant::now() - start_time >= Duration::from_secs(2));
+    assert!(matches!(response.value.unwrap(), ResponseValue::Commit(_)));
+
+    let response: Response = mempool_stream_reader.read().await.unwrap().unwrap();
+    assert!(matches!(response.value.unwrap(), ResponseValue::CheckTx(_)));
+}
+
 #[tokio::test]
 async fn check_valid_abci_flow_with_init_state() {
     let (info_stream, consensus_stream) = initialize_server_with_state(4, 2).await;
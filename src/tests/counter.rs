@@ -5,6 +5,7 @@ use tokio::{sync::Mutex, time::sleep};
 use crate::{
     async_api::{Consensus, Info, Mempool, Server, Snapshot},
     async_trait,
+    runtime::{DefaultRuntime, Runtime},
     types::*,
 };
 
@@ -65,7 +66,7 @@ impl Consensus for ConsensusConnection {
         let new_counter = new_counter.unwrap();
 
         let mut current_state_lock = self.current_state.lock().await;
-        let mut current_state = current_state_lock.as_mut().unwrap();
+        let current_state = current_state_lock.as_mut().unwrap();
 
         if current_state.counter + 1 != new_counter {
             return ResponseDeliverTx {
@@ -82,9 +83,14 @@ impl Consensus for ConsensusConnection {
         Default::default()
     }
 
+    fn deliver_tx_access(&self, _deliver_tx_request: &RequestDeliverTx) -> Option<TxAccess> {
+        // Every transaction updates the counter
+        Some(TxAccess::new().read("counter").write("counter"))
+    }
+
     async fn end_block(&self, end_block_request: RequestEndBlock) -> ResponseEndBlock {
         let mut current_state_lock = self.current_state.lock().await;
-        let mut current_state = current_state_lock.as_mut().unwrap();
+        let current_state = current_state_lock.as_mut().unwrap();
 
         current_state.block_height = end_block_request.height;
         current_state.app_hash = current_state.counter.to_be_bytes().to_vec();
@@ -98,7 +104,7 @@ impl Consensus for ConsensusConnection {
         *committed_state = current_state;
 
         ResponseCommit {
-            data: (*committed_state).app_hash.clone(),
+            data: committed_state.app_hash.clone(),
             retain_height: 0,
         }
     }
@@ -121,6 +127,25 @@ impl Mempool for MempoolConnection {
             ..Default::default()
         }
     }
+
+    async fn recheck_fingerprint(&self, _check_tx_request: &RequestCheckTx) -> Option<Vec<u8>> {
+        // Validity of a transaction does not depend on state
+        Some(Vec::new())
+    }
+
+    async fn check_tx_batch(&self, check_tx_requests: Vec<RequestCheckTx>) -> Vec<ResponseCheckTx> {
+        let batch_size = check_tx_requests.len();
+        let mut responses = Vec::with_capacity(batch_size);
+
+        for check_tx_request in check_tx_requests {
+            let mut response = self.check_tx(check_tx_request).await;
+            response.info = format!("batch size: {}", batch_size);
+
+            responses.push(response);
+        }
+
+        responses
+    }
 }
 
 pub struct InfoConnection {
@@ -142,8 +167,19 @@ impl Info for InfoConnection {
             data: Default::default(),
             version: Default::default(),
             app_version: Default::default(),
-            last_block_height: (*state).block_height,
-            last_block_app_hash: (*state).app_hash.clone(),
+            last_block_height: state.block_height,
+            last_block_app_hash: state.app_hash.clone(),
+        }
+    }
+
+    async fn query(&self, query_request: RequestQuery) -> ResponseQuery {
+        if query_request.path == "slow" {
+            sleep(Duration::from_secs(2)).await;
+        }
+
+        ResponseQuery {
+            key: query_request.data,
+            ..Default::default()
         }
     }
 }
@@ -151,7 +187,18 @@ impl Info for InfoConnection {
 pub struct SnapshotConnection;
 
 #[async_trait]
-impl Snapshot for SnapshotConnection {}
+impl Snapshot for SnapshotConnection {
+    async fn load_snapshot_chunk(
+        &self,
+        load_snapshot_chunk_request: RequestLoadSnapshotChunk,
+    ) -> ResponseLoadSnapshotChunk {
+        sleep(Duration::from_secs(2)).await;
+
+        ResponseLoadSnapshotChunk {
+            chunk: load_snapshot_chunk_request.chunk.to_be_bytes().to_vec(),
+        }
+    }
+}
 
 fn parse_bytes_to_counter(bytes: &[u8]) -> Result<u64, ()> {
     if bytes.len() != 8 {
@@ -166,6 +213,12 @@ fn parse_bytes_to_counter(bytes: &[u8]) -> Result<u64, ()> {
 
 pub fn server() -> Server<ConsensusConnection, MempoolConnection, InfoConnection, SnapshotConnection>
 {
+    server_with_runtime(DefaultRuntime::default())
+}
+
+pub fn server_with_runtime(
+    runtime: impl Runtime,
+) -> Server<ConsensusConnection, MempoolConnection, InfoConnection, SnapshotConnection> {
     let committed_state: Arc<Mutex<CounterState>> = Default::default();
     let current_state: Arc<Mutex<Option<CounterState>>> = Default::default();
 
@@ -174,7 +227,7 @@ pub fn server() -> Server<ConsensusConnection, MempoolConnection, InfoConnection
     let info = InfoConnection::new(committed_state);
     let snapshot = SnapshotConnection;
 
-    Server::new(consensus, mempool, info, snapshot)
+    Server::with_runtime(consensus, mempool, info, snapshot, runtime)
 }
 
 pub fn server_with_state(
@@ -8,6 +8,38 @@ pub fn info() -> Request {
     }
 }
 
+#[cfg(feature = "tls")]
+pub fn echo(message: String) -> Request {
+    Request {
+        value: Some(RequestValue::Echo(RequestEcho { message })),
+    }
+}
+
+pub fn query(key: u64, slow: bool) -> Request {
+    Request {
+        value: Some(RequestValue::Query(RequestQuery {
+            data: key.to_be_bytes().to_vec(),
+            path: if slow { "slow" } else { "fast" }.to_string(),
+            ..Default::default()
+        })),
+    }
+}
+
+pub fn list_snapshots() -> Request {
+    Request {
+        value: Some(RequestValue::ListSnapshots(Default::default())),
+    }
+}
+
+pub fn load_snapshot_chunk(chunk: u32) -> Request {
+    Request {
+        value: Some(RequestValue::LoadSnapshotChunk(RequestLoadSnapshotChunk {
+            chunk,
+            ..Default::default()
+        })),
+    }
+}
+
 pub fn init_chain() -> Request {
     Request {
         value: Some(RequestValue::InitChain(Default::default())),
@@ -46,6 +78,12 @@ pub fn check_tx(counter: u64, recheck: bool) -> Request {
     }
 }
 
+pub fn flush() -> Request {
+    Request {
+        value: Some(RequestValue::Flush(RequestFlush::default())),
+    }
+}
+
 pub fn deliver_tx(counter: u64) -> Request {
     let deliver_tx_request = RequestDeliverTx {
         tx: counter.to_be_bytes().to_vec(),
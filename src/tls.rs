@@ -0,0 +1,148 @@
+use std::{
+    fs,
+    io::{BufReader, Error, ErrorKind, Result},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+
+use futures_rustls::{
+    rustls::{
+        server::AllowAnyAuthenticatedClient, Certificate, PrivateKey, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor,
+};
+use rustls_pemfile::Item;
+
+/// Default timeout of TLS handshakes (see [`TlsConfig::with_handshake_timeout`])
+const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS configuration of ABCI server (used for TCP connections)
+///
+/// Certificates and keys are PEM encoded. Private key can be in PKCS#8, PKCS#1 (RSA) or SEC1 (EC) format. When a
+/// client CA is configured (see [`with_client_ca`](Self::with_client_ca)), Tendermint node should present a client
+/// certificate signed by it (mutual TLS).
+#[derive(Clone)]
+pub struct TlsConfig {
+    cert_chain: Vec<Certificate>,
+    private_key: PrivateKey,
+    server_config: Arc<ServerConfig>,
+    handshake_timeout: Duration,
+}
+
+impl TlsConfig {
+    /// Creates a new instance of [`TlsConfig`] from PEM encoded certificate chain and private key
+    pub fn new(cert_chain: &[u8], private_key: &[u8]) -> Result<Self> {
+        let cert_chain = read_certs(cert_chain)?;
+        let private_key = read_private_key(private_key)?;
+        let server_config = server_config(cert_chain.clone(), private_key.clone(), None)?;
+
+        Ok(Self {
+            cert_chain,
+            private_key,
+            server_config,
+            handshake_timeout: DEFAULT_HANDSHAKE_TIMEOUT,
+        })
+    }
+
+    /// Creates a new instance of [`TlsConfig`] from PEM files containing certificate chain and private key
+    pub fn from_files<P, K>(cert_chain: P, private_key: K) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        K: AsRef<Path>,
+    {
+        Self::new(&fs::read(cert_chain)?, &fs::read(private_key)?)
+    }
+
+    /// Requires clients to present a certificate signed by one of the CA certificates in given PEM bundle (mutual TLS)
+    pub fn with_client_ca(mut self, client_ca: &[u8]) -> Result<Self> {
+        let mut client_roots = RootCertStore::empty();
+
+        for cert in read_certs(client_ca)? {
+            client_roots
+                .add(&cert)
+                .map_err(|err| Error::new(ErrorKind::InvalidInput, err))?;
+        }
+
+        self.server_config = server_config(
+            self.cert_chain.clone(),
+            self.private_key.clone(),
+            Some(client_roots),
+        )?;
+
+        Ok(self)
+    }
+
+    /// Requires clients to present a certificate signed by one of the CA certificates in given PEM file (mutual TLS)
+    pub fn with_client_ca_file<P>(self, client_ca: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        self.with_client_ca(&fs::read(client_ca)?)
+    }
+
+    /// Sets the maximum duration of TLS handshake of a new connection (default: 10 seconds). Connections which do not
+    /// complete the handshake in time are closed.
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = timeout;
+        self
+    }
+
+    pub(crate) fn handshake_timeout(&self) -> Duration {
+        self.handshake_timeout
+    }
+
+    pub(crate) fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.server_config.clone())
+    }
+}
+
+fn server_config(
+    cert_chain: Vec<Certificate>,
+    private_key: PrivateKey,
+    client_roots: Option<RootCertStore>,
+) -> Result<Arc<ServerConfig>> {
+    let builder = ServerConfig::builder().with_safe_defaults();
+
+    let builder = match client_roots {
+        Some(client_roots) => builder
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(client_roots).boxed()),
+        None => builder.with_no_client_auth(),
+    };
+
+    builder
+        .with_single_cert(cert_chain, private_key)
+        .map(Arc::new)
+        .map_err(|err| Error::new(ErrorKind::InvalidInput, err))
+}
+
+fn read_certs(pem: &[u8]) -> Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(pem))?;
+
+    if certs.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "No certificate found in PEM",
+        ));
+    }
+
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn read_private_key(pem: &[u8]) -> Result<PrivateKey> {
+    let mut reader = BufReader::new(pem);
+
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => {
+                return Ok(PrivateKey(key))
+            }
+            _ => continue,
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::InvalidInput,
+        "No private key found in PEM",
+    ))
+}
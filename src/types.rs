@@ -1,4 +1,13 @@
 //! Types used in ABCI
+mod error_code;
+mod event;
+mod tx_access;
+
+pub use self::{
+    error_code::{AppError, ErrorCode, ErrorRegistry, RegisteredErrorCode, RegistryError},
+    event::{composite_key, EventBuilder, ToEvent},
+    tx_access::TxAccess,
+};
 pub use prost_types::{Duration, Timestamp};
 pub use tendermint_proto::{
     abci::{
@@ -69,14 +78,12 @@ where
     M: Message,
     B: BufMut,
 {
-    let len = i64::try_from(message.encoded_len()).map_err(|e| Error::new(ErrorKind::Other, e))?;
+    let len = i64::try_from(message.encoded_len()).map_err(Error::other)?;
     let len_bytes = len.encode_var_vec();
 
     buf.put(len_bytes.as_ref());
 
-    message
-        .encode(buf)
-        .map_err(|e| Error::new(ErrorKind::Other, e))?;
+    message.encode(buf).map_err(Error::other)?;
 
     Ok(())
 }
@@ -0,0 +1,445 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    error::Error,
+    fmt,
+};
+
+use super::{ResponseCheckTx, ResponseDeliverTx, ResponseQuery};
+
+/// An application error identified by a `(codespace, code)` pair.
+///
+/// Error codes are usually declared as constants in the modules that return them and registered in an
+/// [`ErrorRegistry`] at startup so that the `(codespace, code)` pairs are guaranteed to be unique. Registering an error
+/// code returns a [`RegisteredErrorCode`] which can be converted into a response without any further checks.
+///
+/// # Example
+///
+/// ```rust
+/// use abci::types::{ErrorCode, ErrorRegistry, ResponseCheckTx};
+///
+/// pub const ERR_PARSING: ErrorCode = ErrorCode::new("counter", 1, "Invalid transaction encoding");
+/// pub const ERR_VALIDATION: ErrorCode = ErrorCode::new("counter", 2, "Invalid counter value");
+///
+/// let mut registry = ErrorRegistry::new();
+/// let err_parsing = registry.register(ERR_PARSING).unwrap();
+/// let _err_validation = registry.register(ERR_VALIDATION).unwrap();
+///
+/// let response: ResponseCheckTx = err_parsing
+///     .with_details("Transaction should be 8 bytes long")
+///     .into();
+///
+/// assert_eq!(1, response.code);
+/// assert_eq!("counter", response.codespace);
+/// assert_eq!("Invalid transaction encoding: Transaction should be 8 bytes long", response.log);
+/// assert_eq!("codespace: counter, code: 1", response.info);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorCode {
+    codespace: &'static str,
+    code: u32,
+    description: &'static str,
+}
+
+impl ErrorCode {
+    /// Creates a new instance of [`ErrorCode`]
+    ///
+    /// Note that code `0` is reserved for successful responses and cannot be registered in an [`ErrorRegistry`].
+    pub const fn new(codespace: &'static str, code: u32, description: &'static str) -> Self {
+        Self {
+            codespace,
+            code,
+            description,
+        }
+    }
+
+    /// Returns the codespace of error
+    pub fn codespace(&self) -> &'static str {
+        self.codespace
+    }
+
+    /// Returns the code of error
+    pub fn code(&self) -> u32 {
+        self.code
+    }
+
+    /// Returns the description of error
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// Returns an [`AppError`] with additional details about the failure (appended to `log` in the response)
+    pub fn with_details<D: fmt::Display>(self, details: D) -> AppError {
+        AppError {
+            error_code: self,
+            details: Some(details.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (codespace: {}, code: {})",
+            self.description, self.codespace, self.code
+        )
+    }
+}
+
+/// An [`ErrorCode`] which is registered in an [`ErrorRegistry`] (returned by [`ErrorRegistry::register`])
+///
+/// Unlike [`ErrorRegistry::response`], converting a registered error code into a response cannot fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegisteredErrorCode {
+    error_code: ErrorCode,
+}
+
+impl RegisteredErrorCode {
+    /// Returns the error code
+    pub fn error_code(&self) -> ErrorCode {
+        self.error_code
+    }
+
+    /// Returns an [`AppError`] with additional details about the failure (appended to `log` in the response)
+    pub fn with_details<D: fmt::Display>(self, details: D) -> AppError {
+        self.error_code.with_details(details)
+    }
+}
+
+impl fmt::Display for RegisteredErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error_code.fmt(f)
+    }
+}
+
+/// An occurrence of an [`ErrorCode`], optionally with details about the failure.
+///
+/// It can be converted into [`ResponseCheckTx`], [`ResponseDeliverTx`] and [`ResponseQuery`] using `From`/`Into`
+/// (as can [`RegisteredErrorCode`]). Unregistered [`ErrorCode`]s cannot be converted into responses directly, use
+/// [`ErrorRegistry::register`] or [`ErrorRegistry::response`] instead. The converted response contains:
+///
+/// - `code` and `codespace` of the error code
+/// - `log`: `"<description>"` or `"<description>: <details>"` when details are present
+/// - `info`: `"codespace: <codespace>, code: <code>"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppError {
+    error_code: ErrorCode,
+    details: Option<String>,
+}
+
+impl AppError {
+    /// Returns the error code
+    pub fn error_code(&self) -> ErrorCode {
+        self.error_code
+    }
+
+    /// Returns the details of error (if any)
+    pub fn details(&self) -> Option<&str> {
+        self.details.as_deref()
+    }
+
+    fn log(&self) -> String {
+        match self.details {
+            Some(ref details) => format!("{}: {}", self.error_code.description, details),
+            None => self.error_code.description.to_string(),
+        }
+    }
+
+    fn info(&self) -> String {
+        format!(
+            "codespace: {}, code: {}",
+            self.error_code.codespace, self.error_code.code
+        )
+    }
+}
+
+impl From<ErrorCode> for AppError {
+    fn from(error_code: ErrorCode) -> Self {
+        Self {
+            error_code,
+            details: None,
+        }
+    }
+}
+
+impl From<RegisteredErrorCode> for AppError {
+    fn from(registered: RegisteredErrorCode) -> Self {
+        registered.error_code.into()
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.log(), self.info())
+    }
+}
+
+impl Error for AppError {}
+
+macro_rules! impl_into_response {
+    ($($response: ident),+) => {
+        $(
+            impl From<AppError> for $response {
+                fn from(error: AppError) -> Self {
+                    Self {
+                        code: error.error_code.code,
+                        codespace: error.error_code.codespace.to_string(),
+                        log: error.log(),
+                        info: error.info(),
+                        ..Default::default()
+                    }
+                }
+            }
+
+            impl From<RegisteredErrorCode> for $response {
+                fn from(registered: RegisteredErrorCode) -> Self {
+                    AppError::from(registered).into()
+                }
+            }
+        )+
+    };
+}
+
+impl_into_response!(ResponseCheckTx, ResponseDeliverTx, ResponseQuery);
+
+/// Registry of all the [`ErrorCode`]s returned by an application.
+///
+/// Registering all the error codes at startup guarantees that no two errors share the same `(codespace, code)` pair.
+/// Converting [`RegisteredErrorCode`]s returned by [`register`](ErrorRegistry::register) (or errors checked by
+/// [`response`](ErrorRegistry::response)) into responses guarantees that only registered errors are returned to
+/// Tendermint.
+#[derive(Debug, Default, Clone)]
+pub struct ErrorRegistry {
+    errors: BTreeMap<&'static str, BTreeMap<u32, ErrorCode>>,
+}
+
+impl ErrorRegistry {
+    /// Creates a new, empty, instance of [`ErrorRegistry`]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers an error code and returns a [`RegisteredErrorCode`] for it. Returns an error if the code is `0` or if
+    /// another error with the same `(codespace, code)` is already registered.
+    pub fn register(
+        &mut self,
+        error_code: ErrorCode,
+    ) -> Result<RegisteredErrorCode, RegistryError> {
+        if error_code.code == 0 {
+            return Err(RegistryError::ReservedCode(error_code));
+        }
+
+        match self
+            .errors
+            .entry(error_code.codespace)
+            .or_default()
+            .entry(error_code.code)
+        {
+            Entry::Vacant(entry) => {
+                entry.insert(error_code);
+                Ok(RegisteredErrorCode { error_code })
+            }
+            Entry::Occupied(entry) => Err(RegistryError::Duplicate {
+                existing: *entry.get(),
+                new: error_code,
+            }),
+        }
+    }
+
+    /// Registers all the given error codes (see [`register`](ErrorRegistry::register))
+    ///
+    /// Either all or none of the error codes are registered, i.e., registry is left unchanged if any of the error codes
+    /// cannot be registered.
+    pub fn register_all(&mut self, error_codes: &[ErrorCode]) -> Result<(), RegistryError> {
+        let mut registry = self.clone();
+
+        for error_code in error_codes {
+            registry.register(*error_code)?;
+        }
+
+        *self = registry;
+        Ok(())
+    }
+
+    /// Returns registered error code for given `(codespace, code)`
+    pub fn get(&self, codespace: &str, code: u32) -> Option<ErrorCode> {
+        self.errors.get(codespace)?.get(&code).copied()
+    }
+
+    /// Converts a registered error into a response (see [`AppError`] for the format of response). Returns an error if
+    /// the error code (with same description) is not registered.
+    ///
+    /// Prefer converting [`RegisteredErrorCode`]s returned by [`register`](ErrorRegistry::register), which cannot
+    /// fail, when error codes are known at startup.
+    pub fn response<E, R>(&self, error: E) -> Result<R, RegistryError>
+    where
+        E: Into<AppError>,
+        R: From<AppError>,
+    {
+        let error = error.into();
+
+        if self.get(error.error_code.codespace, error.error_code.code) != Some(error.error_code) {
+            return Err(RegistryError::Unregistered(error.error_code));
+        }
+
+        Ok(error.into())
+    }
+
+    /// Returns an iterator over all the registered error codes (ordered by `(codespace, code)`)
+    pub fn iter(&self) -> impl Iterator<Item = &ErrorCode> {
+        self.errors.values().flat_map(BTreeMap::values)
+    }
+}
+
+/// Error returned when an [`ErrorCode`] cannot be registered in an [`ErrorRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// Code `0` is reserved for successful responses
+    ReservedCode(ErrorCode),
+    /// Another error with same `(codespace, code)` is already registered
+    Duplicate {
+        /// Error code which is already registered
+        existing: ErrorCode,
+        /// Error code which was being registered
+        new: ErrorCode,
+    },
+    /// Error code is not registered
+    Unregistered(ErrorCode),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReservedCode(error_code) => write!(
+                f,
+                "Code 0 is reserved for successful responses: {}",
+                error_code
+            ),
+            Self::Duplicate { existing, new } => write!(
+                f,
+                "Cannot register {}. Code is already registered for {}",
+                new, existing
+            ),
+            Self::Unregistered(error_code) => write!(f, "Error is not registered: {}", error_code),
+        }
+    }
+}
+
+impl Error for RegistryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERR_PARSING: ErrorCode = ErrorCode::new("counter", 1, "Invalid transaction encoding");
+    const ERR_VALIDATION: ErrorCode = ErrorCode::new("counter", 2, "Invalid counter value");
+
+    #[test]
+    fn check_registry() {
+        let mut registry = ErrorRegistry::new();
+        registry
+            .register_all(&[ERR_PARSING, ERR_VALIDATION])
+            .unwrap();
+
+        // Same code in a different codespace is allowed
+        let other = ErrorCode::new("other", 1, "Other error");
+        registry.register(other).unwrap();
+
+        assert_eq!(Some(ERR_VALIDATION), registry.get("counter", 2));
+        assert_eq!(Some(other), registry.get("other", 1));
+        assert_eq!(None, registry.get("counter", 3));
+        assert_eq!(3, registry.iter().count());
+
+        let duplicate = ErrorCode::new("counter", 2, "Duplicate");
+        assert_eq!(
+            Err(RegistryError::Duplicate {
+                existing: ERR_VALIDATION,
+                new: duplicate
+            }),
+            registry.register(duplicate)
+        );
+
+        let reserved = ErrorCode::new("counter", 0, "Reserved");
+        assert_eq!(
+            Err(RegistryError::ReservedCode(reserved)),
+            registry.register(reserved)
+        );
+
+        // Error codes registered before a failed one are not kept
+        let new = ErrorCode::new("counter", 3, "New error");
+        assert_eq!(
+            Err(RegistryError::Duplicate {
+                existing: ERR_VALIDATION,
+                new: duplicate
+            }),
+            registry.register_all(&[new, duplicate])
+        );
+        assert_eq!(None, registry.get("counter", 3));
+        assert_eq!(3, registry.iter().count());
+    }
+
+    #[test]
+    fn check_registry_response() {
+        let mut registry = ErrorRegistry::new();
+        registry.register(ERR_PARSING).unwrap();
+
+        let response: ResponseCheckTx = registry
+            .response(ERR_PARSING.with_details("Expected 8 bytes"))
+            .unwrap();
+        assert_eq!(1, response.code);
+        assert_eq!(
+            "Invalid transaction encoding: Expected 8 bytes",
+            response.log
+        );
+
+        assert_eq!(
+            Err(RegistryError::Unregistered(ERR_VALIDATION)),
+            registry.response::<_, ResponseCheckTx>(ERR_VALIDATION)
+        );
+
+        // Same `(codespace, code)` with a different description is not the registered error
+        let other = ErrorCode::new("counter", 1, "Other error");
+        assert_eq!(
+            Err(RegistryError::Unregistered(other)),
+            registry.response::<_, ResponseDeliverTx>(other)
+        );
+    }
+
+    #[test]
+    fn check_registered_error_code() {
+        let mut registry = ErrorRegistry::new();
+        let err_parsing = registry.register(ERR_PARSING).unwrap();
+        assert_eq!(ERR_PARSING, err_parsing.error_code());
+
+        let response: ResponseDeliverTx = err_parsing.with_details("Expected 8 bytes").into();
+        assert_eq!(1, response.code);
+        assert_eq!(
+            "Invalid transaction encoding: Expected 8 bytes",
+            response.log
+        );
+
+        let response: ResponseCheckTx = err_parsing.into();
+        assert_eq!(
+            registry.response::<_, ResponseCheckTx>(ERR_PARSING),
+            Ok(response)
+        );
+    }
+
+    #[test]
+    fn check_response_conversion() {
+        let response: ResponseDeliverTx = AppError::from(ERR_VALIDATION).into();
+        assert_eq!(2, response.code);
+        assert_eq!("counter", response.codespace);
+        assert_eq!("Invalid counter value", response.log);
+        assert_eq!("codespace: counter, code: 2", response.info);
+
+        let response: ResponseQuery = ERR_PARSING.with_details("Expected 8 bytes").into();
+        assert_eq!(1, response.code);
+        assert_eq!("counter", response.codespace);
+        assert_eq!(
+            "Invalid transaction encoding: Expected 8 bytes",
+            response.log
+        );
+        assert_eq!("codespace: counter, code: 1", response.info);
+    }
+}
@@ -0,0 +1,281 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::{Event, EventAttribute};
+
+/// Characters which cannot be a part of an event type or attribute key because Tendermint's query language uses them
+/// as delimiters.
+const RESERVED_CHARACTERS: &[char] = &['=', '<', '>', '(', ')', '"', '\'', '\\'];
+
+/// Trait for types that can be converted into an [`Event`].
+///
+/// Implementing this trait for the events emitted by an application keeps the event type and attribute keys in one
+/// place so that they stay consistent with the queries made against the indexer.
+///
+/// # Example
+///
+/// ```rust
+/// use abci::types::{Event, EventBuilder, ToEvent};
+///
+/// struct Transfer {
+///     sender: String,
+///     recipient: String,
+///     amount: u64,
+/// }
+///
+/// impl ToEvent for Transfer {
+///     fn to_event(&self) -> Event {
+///         EventBuilder::new("transfer")
+///             .attribute("sender", self.sender.as_str())
+///             .attribute("recipient", self.recipient.as_str())
+///             .unindexed_attribute("amount", self.amount.to_string())
+///             .build()
+///     }
+/// }
+/// ```
+pub trait ToEvent {
+    /// Converts `self` into an [`Event`]
+    fn to_event(&self) -> Event;
+}
+
+impl ToEvent for Event {
+    fn to_event(&self) -> Event {
+        self.clone()
+    }
+}
+
+/// Builder for [`Event`]s and their [`EventAttribute`]s.
+///
+/// By default, all the attributes added to the builder are indexed by Tendermint. This can be changed for all the
+/// subsequent attributes using [`index`](EventBuilder::index) or for a single attribute using
+/// [`unindexed_attribute`](EventBuilder::unindexed_attribute).
+///
+/// # Panics
+///
+/// Event types and attribute keys are used by Tendermint to build composite keys (`<type>.<key>`) for indexing. So,
+/// they cannot be empty and cannot contain whitespaces or any of the characters reserved by Tendermint's query
+/// language (`=`, `<`, `>`, `(`, `)`, `"`, `'`, `\`). Attribute keys, additionally, cannot contain `.`. The builder
+/// panics if an invalid event type or attribute key is supplied. Use [`try_new`](EventBuilder::try_new) and
+/// [`try_attribute`](EventBuilder::try_attribute) when event types or attribute keys come from application data.
+#[derive(Debug, Clone)]
+pub struct EventBuilder {
+    r#type: String,
+    attributes: Vec<EventAttribute>,
+    index: bool,
+}
+
+impl EventBuilder {
+    /// Creates a new instance of [`EventBuilder`] for given event type
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `event_type` is invalid (see [`try_new`](EventBuilder::try_new)).
+    pub fn new<T: Into<String>>(event_type: T) -> Self {
+        Self::try_new(event_type).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Creates a new instance of [`EventBuilder`] for given event type. Returns an error if `event_type` is empty or
+    /// contains a whitespace or a reserved character.
+    pub fn try_new<T: Into<String>>(event_type: T) -> Result<Self> {
+        let event_type = event_type.into();
+        validate(&event_type, "event type", &[])?;
+
+        Ok(Self {
+            r#type: event_type,
+            attributes: Default::default(),
+            index: true,
+        })
+    }
+
+    /// Sets whether the attributes added after this call should be indexed (default: `true`)
+    pub fn index(mut self, index: bool) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Adds an attribute to the event. The attribute is indexed based on the current [`index`](EventBuilder::index)
+    /// flag of the builder.
+    pub fn attribute<V: Into<Vec<u8>>>(self, key: &str, value: V) -> Self {
+        let index = self.index;
+        self.attribute_with_index(key, value, index)
+    }
+
+    /// Adds an attribute to the event which is always indexed
+    pub fn indexed_attribute<V: Into<Vec<u8>>>(self, key: &str, value: V) -> Self {
+        self.attribute_with_index(key, value, true)
+    }
+
+    /// Adds an attribute to the event which is never indexed
+    pub fn unindexed_attribute<V: Into<Vec<u8>>>(self, key: &str, value: V) -> Self {
+        self.attribute_with_index(key, value, false)
+    }
+
+    /// Adds an attribute to the event with given `index` flag
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `key` is invalid (see
+    /// [`try_attribute_with_index`](EventBuilder::try_attribute_with_index)).
+    pub fn attribute_with_index<V: Into<Vec<u8>>>(self, key: &str, value: V, index: bool) -> Self {
+        self.try_attribute_with_index(key, value, index)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Adds an attribute to the event. Returns an error if `key` is invalid (see
+    /// [`try_attribute_with_index`](EventBuilder::try_attribute_with_index)).
+    pub fn try_attribute<V: Into<Vec<u8>>>(self, key: &str, value: V) -> Result<Self> {
+        let index = self.index;
+        self.try_attribute_with_index(key, value, index)
+    }
+
+    /// Adds an attribute to the event with given `index` flag. Returns an error if `key` is empty or contains a
+    /// whitespace, `.` or a reserved character.
+    pub fn try_attribute_with_index<V: Into<Vec<u8>>>(
+        mut self,
+        key: &str,
+        value: V,
+        index: bool,
+    ) -> Result<Self> {
+        validate(key, "attribute key", &['.'])?;
+
+        self.attributes.push(EventAttribute {
+            key: key.as_bytes().to_vec(),
+            value: value.into(),
+            index,
+        });
+        Ok(self)
+    }
+
+    /// Returns the composite key (`<type>.<key>`) which can be used to query the given attribute of this event
+    pub fn composite_key(&self, key: &str) -> String {
+        composite_key(&self.r#type, key)
+    }
+
+    /// Builds the [`Event`]
+    pub fn build(self) -> Event {
+        Event {
+            r#type: self.r#type,
+            attributes: self.attributes,
+        }
+    }
+}
+
+impl ToEvent for EventBuilder {
+    fn to_event(&self) -> Event {
+        self.clone().build()
+    }
+}
+
+impl From<EventBuilder> for Event {
+    fn from(builder: EventBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Returns the composite key (`<type>.<key>`) used by Tendermint to index and query an event attribute
+pub fn composite_key(event_type: &str, key: &str) -> String {
+    format!("{}.{}", event_type, key)
+}
+
+fn validate(value: &str, name: &str, additional_reserved: &[char]) -> Result<()> {
+    if value.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} cannot be empty", name),
+        ));
+    }
+
+    match value.chars().find(|c| {
+        c.is_whitespace() || RESERVED_CHARACTERS.contains(c) || additional_reserved.contains(c)
+    }) {
+        Some(c) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} `{}` contains invalid character {:?}", name, value, c),
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_event_builder() {
+        let event = EventBuilder::new("transfer")
+            .attribute("sender", "alice")
+            .unindexed_attribute("memo", vec![1, 2, 3])
+            .index(false)
+            .attribute("amount", 10.to_string())
+            .indexed_attribute("recipient", String::from("bob"))
+            .build();
+
+        assert_eq!(
+            event,
+            Event {
+                r#type: "transfer".to_string(),
+                attributes: vec![
+                    EventAttribute {
+                        key: b"sender".to_vec(),
+                        value: b"alice".to_vec(),
+                        index: true,
+                    },
+                    EventAttribute {
+                        key: b"memo".to_vec(),
+                        value: vec![1, 2, 3],
+                        index: false,
+                    },
+                    EventAttribute {
+                        key: b"amount".to_vec(),
+                        value: b"10".to_vec(),
+                        index: false,
+                    },
+                    EventAttribute {
+                        key: b"recipient".to_vec(),
+                        value: b"bob".to_vec(),
+                        index: true,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn check_composite_key() {
+        let builder = EventBuilder::new("transfer");
+        assert_eq!("transfer.sender", builder.composite_key("sender"));
+        assert_eq!("message.action", composite_key("message", "action"));
+    }
+
+    #[test]
+    #[should_panic(expected = "attribute key `sender.name` contains invalid character '.'")]
+    fn check_invalid_attribute_key() {
+        EventBuilder::new("transfer").attribute("sender.name", "alice");
+    }
+
+    #[test]
+    #[should_panic(expected = "event type cannot be empty")]
+    fn check_empty_event_type() {
+        EventBuilder::new("");
+    }
+
+    #[test]
+    fn check_try_event_builder() {
+        assert!(EventBuilder::try_new("invalid type").is_err());
+
+        let builder = EventBuilder::try_new("transfer").unwrap();
+        let err = builder
+            .clone()
+            .try_attribute("sender=alice", "alice")
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+
+        let event = builder
+            .try_attribute("sender", "alice")
+            .unwrap()
+            .try_attribute_with_index("memo", "hello", false)
+            .unwrap()
+            .build();
+        assert_eq!(2, event.attributes.len());
+        assert!(!event.attributes[1].index);
+    }
+}
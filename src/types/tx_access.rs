@@ -0,0 +1,63 @@
+use std::collections::BTreeSet;
+
+/// Set of state keys read and written by a transaction.
+///
+/// Returned by [`Consensus::deliver_tx_access`](crate::async_api::Consensus::deliver_tx_access) so that transactions
+/// which do not conflict with each other can be executed in parallel. Two transactions conflict when one of them writes
+/// a key which the other one reads or writes.
+///
+/// # Example
+///
+/// ```rust
+/// use abci::types::TxAccess;
+///
+/// let access = TxAccess::new()
+///     .read("balance/alice")
+///     .write("balance/alice")
+///     .write("balance/bob");
+///
+/// assert!(access.conflicts_with(&TxAccess::new().read("balance/bob")));
+/// assert!(!access.conflicts_with(&TxAccess::new().read("balance/charlie")));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxAccess {
+    reads: BTreeSet<Vec<u8>>,
+    writes: BTreeSet<Vec<u8>>,
+}
+
+impl TxAccess {
+    /// Creates a new instance of [`TxAccess`] which does not read or write any key
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a key read by the transaction
+    pub fn read<K: Into<Vec<u8>>>(mut self, key: K) -> Self {
+        self.reads.insert(key.into());
+        self
+    }
+
+    /// Adds a key written by the transaction
+    pub fn write<K: Into<Vec<u8>>>(mut self, key: K) -> Self {
+        self.writes.insert(key.into());
+        self
+    }
+
+    /// Returns keys read by the transaction
+    pub fn reads(&self) -> impl Iterator<Item = &[u8]> {
+        self.reads.iter().map(AsRef::as_ref)
+    }
+
+    /// Returns keys written by the transaction
+    pub fn writes(&self) -> impl Iterator<Item = &[u8]> {
+        self.writes.iter().map(AsRef::as_ref)
+    }
+
+    /// Returns `true` if the transaction cannot be executed in parallel with the other transaction
+    pub fn conflicts_with(&self, other: &TxAccess) -> bool {
+        self.writes
+            .iter()
+            .any(|key| other.reads.contains(key) || other.writes.contains(key))
+            || other.writes.iter().any(|key| self.reads.contains(key))
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    fs::{self, Permissions},
+    io::{Error, ErrorKind, Result},
+    os::unix::{
+        fs::{chown, FileTypeExt, MetadataExt, PermissionsExt},
+        net::UnixStream,
+    },
+    path::{Path, PathBuf},
+};
+
+use tracing::{info, warn};
+
+/// Lifecycle of socket files created for [`Address::Uds`](crate::Address::Uds)
+///
+/// By default, server fails to bind if a file already exists at the socket path and leaves the socket file in place
+/// once it stops. Options in this configuration do not apply to pre-bound listeners and Linux abstract-namespace
+/// sockets (which have no file).
+#[derive(Debug, Clone, Default)]
+pub struct UnixSocketConfig {
+    remove_stale: bool,
+    remove_on_shutdown: bool,
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+}
+
+impl UnixSocketConfig {
+    /// Creates a new instance of [`UnixSocketConfig`] with default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes an existing socket file at the socket path before binding if no process is listening on it, e.g., one
+    /// left behind by a crashed server (default: `false`)
+    ///
+    /// Server still fails to bind if another process is listening on the socket or if the existing file is not a
+    /// socket.
+    pub fn with_remove_stale(mut self, enabled: bool) -> Self {
+        self.remove_stale = enabled;
+        self
+    }
+
+    /// Removes the socket file once server stops, i.e., when [`run`](crate::async_api::Server::run) returns with an
+    /// error or its future is dropped (default: `false`)
+    ///
+    /// The file is only removed if it is still the socket created by server (and not, e.g., a socket created by a
+    /// newer server at the same path).
+    pub fn with_remove_on_shutdown(mut self, enabled: bool) -> Self {
+        self.remove_on_shutdown = enabled;
+        self
+    }
+
+    /// Sets the permissions of socket file (e.g., `0o660`) after binding (default: permissions are determined by
+    /// process umask)
+    pub fn with_mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets the owner and/or group of socket file after binding (default: user and group of process). Changing the
+    /// owner usually requires elevated privileges.
+    pub fn with_owner(mut self, uid: Option<u32>, gid: Option<u32>) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
+    }
+
+    /// Prepares socket path for binding
+    pub(crate) fn before_bind(&self, path: &Path) -> Result<()> {
+        if self.remove_stale {
+            remove_stale_socket(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies the configuration to the socket file created by binding. Returns a guard which removes the socket file
+    /// when dropped (if enabled).
+    pub(crate) fn after_bind(&self, path: &Path) -> Result<SocketFileGuard> {
+        let metadata = fs::metadata(path)?;
+
+        let mut guard = SocketFileGuard {
+            path: path.to_path_buf(),
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            // Socket file is not usable by anyone if configuration cannot be applied
+            remove: true,
+        };
+
+        if let Some(mode) = self.mode {
+            fs::set_permissions(path, Permissions::from_mode(mode))?;
+        }
+
+        if self.uid.is_some() || self.gid.is_some() {
+            chown(path, self.uid, self.gid)?;
+        }
+
+        guard.remove = self.remove_on_shutdown;
+
+        Ok(guard)
+    }
+}
+
+/// Removes the socket file created by server when dropped
+#[derive(Debug)]
+pub(crate) struct SocketFileGuard {
+    path: PathBuf,
+    dev: u64,
+    ino: u64,
+    remove: bool,
+}
+
+impl Drop for SocketFileGuard {
+    fn drop(&mut self) {
+        if !self.remove {
+            return;
+        }
+
+        match fs::symlink_metadata(&self.path) {
+            Ok(metadata) if metadata.dev() == self.dev && metadata.ino() == self.ino => {
+                match fs::remove_file(&self.path) {
+                    Ok(()) => info!(message = "Removed socket file", path = %self.path.display()),
+                    Err(err) => {
+                        warn!(message = "Unable to remove socket file", path = %self.path.display(), %err)
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {}
+        Ok(_) => return Ok(()),
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    }
+
+    match UnixStream::connect(path) {
+        Ok(_) => Err(Error::new(
+            ErrorKind::AddrInUse,
+            format!("Another process is listening on {}", path.display()),
+        )),
+        Err(err) if err.kind() == ErrorKind::ConnectionRefused => {
+            fs::remove_file(path)?;
+            info!(message = "Removed stale socket file", path = %path.display());
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
@@ -1,7 +1,23 @@
+mod barrier;
 mod connection_type;
+mod health;
+mod http;
 mod io;
+mod method;
+mod pipeline;
+mod recheck;
+mod schedule;
+mod span;
 
 pub use self::{
+    barrier::{CheckTxTicket, CommitBarrier},
     connection_type::ConnectionType,
+    health::{ConnectionGuard, ConnectionTracker, Health},
+    http::{serve_http, HttpHandler, HttpResponse},
     io::{get_stream_pair, StreamReader, StreamWriter},
+    method::Method,
+    pipeline::{InFlightLimiter, InFlightPermit, QueueMetrics},
+    recheck::{RecheckStats, RecheckTracker},
+    schedule::schedule,
+    span::{record_response, record_tx_hash},
 };
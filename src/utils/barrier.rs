@@ -0,0 +1,206 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use event_listener::Event;
+use tracing::debug;
+
+/// Coordinates `CheckTx` execution on mempool connection with `Commit` on consensus connection.
+///
+/// - `Commit` waits for running `CheckTx` requests to complete and pauses execution of new ones until the commit (and
+///   the `Mempool::on_commit` hook) is done.
+/// - After a commit, new `CheckTx` requests wait for all the recheck requests dispatched before them to complete.
+#[derive(Debug, Default)]
+pub struct CommitBarrier {
+    state: Mutex<BarrierState>,
+    event: Event,
+}
+
+#[derive(Debug, Default)]
+struct BarrierState {
+    /// `true` while a commit is in progress
+    committing: bool,
+    /// Number of `CheckTx` requests currently executing
+    running: usize,
+    /// Number of recheck requests dispatched but not yet completed
+    pending_rechecks: usize,
+}
+
+impl CommitBarrier {
+    /// Registers a `CheckTx` request when it is read from mempool connection. Registration is done in the order in
+    /// which requests are received so that new `CheckTx` requests can wait for earlier recheck requests.
+    pub fn register(self: &Arc<Self>, recheck: bool) -> CheckTxTicket {
+        if recheck {
+            self.state().pending_rechecks += 1;
+        }
+
+        CheckTxTicket {
+            barrier: self.clone(),
+            recheck,
+        }
+    }
+
+    /// Waits for running `CheckTx` requests to complete and pauses execution of new ones until returned guard is
+    /// dropped
+    pub async fn pause(self: &Arc<Self>) -> CommitGuard {
+        self.state().committing = true;
+        debug!(message = "Pausing CheckTx execution for commit");
+
+        // Guard is created before waiting so that execution resumes even if this future is dropped while waiting
+        let guard = CommitGuard {
+            barrier: self.clone(),
+        };
+
+        self.wait_until(|state| state.running == 0).await;
+
+        guard
+    }
+
+    async fn wait_until<F>(&self, condition: F)
+    where
+        F: Fn(&BarrierState) -> bool,
+    {
+        loop {
+            if condition(&self.state()) {
+                return;
+            }
+
+            let listener = self.event.listen();
+
+            if condition(&self.state()) {
+                return;
+            }
+
+            listener.await;
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, BarrierState> {
+        self.state.lock().expect("Lock poisoned")
+    }
+
+    fn update<F>(&self, f: F)
+    where
+        F: FnOnce(&mut BarrierState),
+    {
+        f(&mut self.state());
+        self.event.notify(usize::MAX);
+    }
+}
+
+/// A `CheckTx` request registered with [`CommitBarrier`]
+pub struct CheckTxTicket {
+    barrier: Arc<CommitBarrier>,
+    recheck: bool,
+}
+
+impl CheckTxTicket {
+    /// Waits until the `CheckTx` request can be executed. Request is considered running until the returned guard is
+    /// dropped.
+    pub async fn wait(&self) -> CheckTxGuard<'_> {
+        let recheck = self.recheck;
+
+        loop {
+            self.barrier
+                .wait_until(|state| !state.committing && (recheck || state.pending_rechecks == 0))
+                .await;
+
+            let mut state = self.barrier.state();
+
+            // Commit may have started after the condition was checked
+            if !state.committing && (recheck || state.pending_rechecks == 0) {
+                state.running += 1;
+                break;
+            }
+        }
+
+        CheckTxGuard { ticket: self }
+    }
+}
+
+impl Drop for CheckTxTicket {
+    fn drop(&mut self) {
+        if self.recheck {
+            self.barrier.update(|state| state.pending_rechecks -= 1);
+        }
+    }
+}
+
+/// Guard for a running `CheckTx` request
+pub struct CheckTxGuard<'a> {
+    ticket: &'a CheckTxTicket,
+}
+
+impl Drop for CheckTxGuard<'_> {
+    fn drop(&mut self) {
+        self.ticket.barrier.update(|state| state.running -= 1);
+    }
+}
+
+/// Guard for a commit in progress. `CheckTx` execution resumes when it is dropped.
+pub struct CommitGuard {
+    barrier: Arc<CommitBarrier>,
+}
+
+impl Drop for CommitGuard {
+    fn drop(&mut self) {
+        debug!(message = "Resuming CheckTx execution after commit");
+        self.barrier.update(|state| state.committing = false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn check_commit_barrier() {
+        let barrier = Arc::new(CommitBarrier::default());
+
+        let recheck = barrier.register(true);
+        let check_tx = barrier.register(false);
+
+        // New `CheckTx` waits for pending rechecks
+        assert!(timeout(Duration::from_millis(50), check_tx.wait())
+            .await
+            .is_err());
+
+        let recheck_guard = recheck.wait().await;
+
+        // Commit waits for running `CheckTx` requests
+        let pause = {
+            let barrier = barrier.clone();
+            tokio::spawn(async move {
+                let _commit_guard = barrier.pause().await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!pause.is_finished());
+
+        drop(recheck_guard);
+        drop(recheck);
+        pause.await.unwrap();
+
+        let _check_tx_guard = check_tx.wait().await;
+    }
+
+    #[tokio::test]
+    async fn check_dropped_pause() {
+        let barrier = Arc::new(CommitBarrier::default());
+
+        let check_tx = barrier.register(false);
+        let check_tx_guard = check_tx.wait().await;
+
+        // Pause is dropped while waiting for running `CheckTx` requests
+        assert!(timeout(Duration::from_millis(50), barrier.pause())
+            .await
+            .is_err());
+        drop(check_tx_guard);
+
+        let _check_tx_guard = timeout(Duration::from_millis(50), check_tx.wait())
+            .await
+            .expect("CheckTx execution should not stay paused");
+    }
+}
@@ -1,18 +1,45 @@
 use tendermint_proto::abci::request::Value as RequestValue;
 
 /// Different types of connections created by tendermint
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+///
+/// Type of a connection is determined by its first request other than `Echo` and `Flush`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
 pub enum ConnectionType {
+    /// Connection which has not sent a request specific to a connection type yet
+    #[default]
     Unknown,
+    /// Consensus connection (`InitChain`, `BeginBlock`, `DeliverTx`, `EndBlock` and `Commit`)
     Consensus,
+    /// Mempool connection (`CheckTx`)
     Mempool,
+    /// Info connection (`Info`, `SetOption` and `Query`)
     Info,
+    /// Snapshot connection (`ListSnapshots`, `OfferSnapshot`, `LoadSnapshotChunk` and `ApplySnapshotChunk`)
     Snapshot,
 }
 
-impl Default for ConnectionType {
-    fn default() -> Self {
-        Self::Unknown
+impl ConnectionType {
+    /// Returns the index of connection type among the types which can be established by tendermint (i.e., all except
+    /// `Unknown`)
+    pub(crate) fn index(&self) -> Option<usize> {
+        match self {
+            Self::Unknown => None,
+            Self::Consensus => Some(0),
+            Self::Mempool => Some(1),
+            Self::Info => Some(2),
+            Self::Snapshot => Some(3),
+        }
+    }
+
+    /// Returns the name of connection type (used in logs and as a label of metrics)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Consensus => "consensus",
+            Self::Mempool => "mempool",
+            Self::Info => "info",
+            Self::Snapshot => "snapshot",
+        }
     }
 }
 
@@ -0,0 +1,271 @@
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use event_listener::Event;
+use futures_lite::future::pending;
+
+use crate::{state::StateSnapshot, utils::ConnectionType};
+
+/// Health of ABCI server as seen from its connections with Tendermint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Health {
+    /// Number of established consensus connections
+    pub consensus_connections: usize,
+    /// Number of established mempool connections
+    pub mempool_connections: usize,
+    /// Number of established info connections
+    pub info_connections: usize,
+    /// Number of established snapshot connections
+    pub snapshot_connections: usize,
+    /// Name of current consensus state (`no_info`, `not_initialized`, `init_chain`, `waiting_for_block` or
+    /// `executing_block`)
+    pub consensus_state: &'static str,
+    /// Height of the block which is being executed or expected next (if known)
+    pub block_height: Option<i64>,
+    /// Time of last successful commit
+    pub last_commit: Option<SystemTime>,
+    /// Last protocol violation (i.e., an out of order request or an invalid response) seen by server
+    pub protocol_violation: Option<String>,
+}
+
+impl Health {
+    /// Returns `true` if no protocol violation has been seen
+    pub fn is_healthy(&self) -> bool {
+        self.protocol_violation.is_none()
+    }
+
+    /// Returns `true` if server is healthy and consensus, mempool and info connections are established
+    pub fn is_ready(&self) -> bool {
+        self.is_healthy()
+            && self.consensus_connections > 0
+            && self.mempool_connections > 0
+            && self.info_connections > 0
+    }
+
+    pub(crate) fn new(connections: &ConnectionTracker, state_snapshot: StateSnapshot) -> Self {
+        Self {
+            consensus_connections: connections.count(ConnectionType::Consensus),
+            mempool_connections: connections.count(ConnectionType::Mempool),
+            info_connections: connections.count(ConnectionType::Info),
+            snapshot_connections: connections.count(ConnectionType::Snapshot),
+            consensus_state: state_snapshot.state.name(),
+            block_height: state_snapshot.state.block_height(),
+            last_commit: state_snapshot.last_commit,
+            protocol_violation: state_snapshot.protocol_violation,
+        }
+    }
+
+    /// Serializes health report as JSON
+    pub(crate) fn to_json(&self) -> String {
+        let mut json = String::new();
+
+        let _ = write!(
+            json,
+            "{{\"healthy\":{},\"ready\":{},\"connections\":{{\"consensus\":{},\"mempool\":{},\"info\":{},\"snapshot\":{}}},\"consensus_state\":\"{}\",\"block_height\":",
+            self.is_healthy(),
+            self.is_ready(),
+            self.consensus_connections,
+            self.mempool_connections,
+            self.info_connections,
+            self.snapshot_connections,
+            self.consensus_state,
+        );
+
+        match self.block_height {
+            Some(block_height) => {
+                let _ = write!(json, "{}", block_height);
+            }
+            None => json.push_str("null"),
+        }
+
+        json.push_str(",\"last_commit\":");
+
+        match self
+            .last_commit
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        {
+            Some(duration) => {
+                let _ = write!(json, "{:.3}", duration.as_secs_f64());
+            }
+            None => json.push_str("null"),
+        }
+
+        json.push_str(",\"protocol_violation\":");
+
+        match self.protocol_violation {
+            Some(ref violation) => write_json_string(&mut json, violation),
+            None => json.push_str("null"),
+        }
+
+        json.push('}');
+        json
+    }
+}
+
+/// Tracks the number of established connections of each type
+#[derive(Debug, Default)]
+pub struct ConnectionTracker {
+    counts: [AtomicUsize; 4],
+    /// Incremented whenever established connections of a type are replaced by a new connection
+    generations: [AtomicU64; 4],
+    replaced: Event,
+}
+
+impl ConnectionTracker {
+    /// Records a new connection of given type. Connection is considered established until returned guard is dropped.
+    pub fn connect(self: &Arc<Self>, connection_type: ConnectionType) -> ConnectionGuard {
+        if let Some(count) = self.counter(connection_type) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.guard(connection_type)
+    }
+
+    /// Records a new connection of given type only if less than `limit` connections of that type are established
+    pub fn try_connect(
+        self: &Arc<Self>,
+        connection_type: ConnectionType,
+        limit: usize,
+    ) -> Option<ConnectionGuard> {
+        if let Some(count) = self.counter(connection_type) {
+            count
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                    (count < limit).then_some(count + 1)
+                })
+                .ok()?;
+        }
+
+        Some(self.guard(connection_type))
+    }
+
+    /// Records a new connection of given type and signals all the established connections of that type to close (see
+    /// [`ConnectionGuard::replaced`])
+    pub fn replace(self: &Arc<Self>, connection_type: ConnectionType) -> ConnectionGuard {
+        if let Some(index) = connection_type.index() {
+            self.generations[index].fetch_add(1, Ordering::SeqCst);
+            self.replaced.notify(usize::MAX);
+        }
+
+        self.connect(connection_type)
+    }
+
+    pub fn count(&self, connection_type: ConnectionType) -> usize {
+        self.counter(connection_type)
+            .map(|count| count.load(Ordering::Relaxed))
+            .unwrap_or_default()
+    }
+
+    fn counter(&self, connection_type: ConnectionType) -> Option<&AtomicUsize> {
+        connection_type.index().map(|index| &self.counts[index])
+    }
+
+    fn generation(&self, connection_type: ConnectionType) -> Option<u64> {
+        connection_type
+            .index()
+            .map(|index| self.generations[index].load(Ordering::SeqCst))
+    }
+
+    fn guard(self: &Arc<Self>, connection_type: ConnectionType) -> ConnectionGuard {
+        ConnectionGuard {
+            tracker: self.clone(),
+            connection_type,
+            generation: self.generation(connection_type),
+        }
+    }
+}
+
+/// Guard for an established connection
+pub struct ConnectionGuard {
+    tracker: Arc<ConnectionTracker>,
+    connection_type: ConnectionType,
+    generation: Option<u64>,
+}
+
+impl ConnectionGuard {
+    /// Completes when this connection is replaced by a new connection of same type (never completes for connections of
+    /// unknown type)
+    pub async fn replaced(&self) {
+        if self.generation.is_none() {
+            return pending().await;
+        }
+
+        loop {
+            if self.is_replaced() {
+                return;
+            }
+
+            let listener = self.tracker.replaced.listen();
+
+            if self.is_replaced() {
+                return;
+            }
+
+            listener.await;
+        }
+    }
+
+    /// Returns `true` if this connection is replaced by a new connection of same type
+    pub fn is_replaced(&self) -> bool {
+        self.tracker.generation(self.connection_type) > self.generation
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        if let Some(count) = self.tracker.counter(self.connection_type) {
+            count.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn write_json_string(json: &mut String, value: &str) {
+    json.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_health_json() {
+        let tracker = Arc::new(ConnectionTracker::default());
+        let _consensus = tracker.connect(ConnectionType::Consensus);
+        let mempool = tracker.connect(ConnectionType::Mempool);
+        drop(mempool);
+
+        let mut health = Health::new(&tracker, Default::default());
+        assert_eq!(1, health.consensus_connections);
+        assert_eq!(0, health.mempool_connections);
+        assert!(health.is_healthy());
+        assert!(!health.is_ready());
+
+        health.protocol_violation = Some("Unexpected \"Commit\"".to_string());
+
+        assert_eq!(
+            "{\"healthy\":false,\"ready\":false,\"connections\":{\"consensus\":1,\"mempool\":0,\"info\":0,\"snapshot\":0},\"consensus_state\":\"no_info\",\"block_height\":null,\"last_commit\":null,\"protocol_violation\":\"Unexpected \\\"Commit\\\"\"}",
+            health.to_json()
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    io::{Error, ErrorKind, Result},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures_lite::{
+    future::or,
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+use tracing::{debug, error};
+
+use crate::runtime::{BoxFuture, Connection, Listener, Runtime};
+
+/// Maximum size of HTTP request head accepted by local HTTP endpoints
+const MAX_HTTP_REQUEST_LEN: usize = 8 * 1024;
+
+/// Time given to a client of local HTTP endpoint to send its request and read the response
+const HTTP_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time to wait before accepting a new connection after an error (e.g., when the process runs out of file
+/// descriptors)
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Response of a local HTTP endpoint
+pub struct HttpResponse {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Creates a `404 Not Found` response
+    pub fn not_found() -> Self {
+        Self {
+            status: "404 Not Found",
+            content_type: "text/plain",
+            body: String::new(),
+        }
+    }
+}
+
+/// Handles `GET` requests of a local HTTP endpoint. Receives the path of request.
+pub type HttpHandler = Arc<dyn Fn(&str) -> BoxFuture<'static, HttpResponse> + Send + Sync>;
+
+/// Serves `GET` requests on connections accepted by given listener (used for metrics and health endpoints)
+///
+/// This is intentionally minimal: it reads the request head, answers once and closes the connection. Connections which
+/// are not done within [`HTTP_CONNECTION_TIMEOUT`] are closed.
+pub async fn serve_http(
+    runtime: Arc<dyn Runtime>,
+    mut listener: Box<dyn Listener>,
+    handler: HttpHandler,
+) {
+    listener.set_read_timeout(Some(HTTP_CONNECTION_TIMEOUT));
+
+    loop {
+        let connection = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                error!(message = "Error while accepting HTTP connection", %err);
+                runtime.sleep(ACCEPT_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+
+        let handler = handler.clone();
+        let timeout = runtime.sleep(HTTP_CONNECTION_TIMEOUT);
+
+        runtime.spawn(Box::pin(async move {
+            let peer_addr = connection.peer_addr.clone();
+
+            let result = or(handle_http_connection(connection, handler), async {
+                timeout.await;
+                Err(Error::new(ErrorKind::TimedOut, "HTTP connection timed out"))
+            })
+            .await;
+
+            if let Err(err) = result {
+                debug!(message = "Error while serving HTTP request", %peer_addr, %err);
+            }
+        }));
+    }
+}
+
+async fn handle_http_connection(mut connection: Connection, handler: HttpHandler) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        if request.len() >= MAX_HTTP_REQUEST_LEN {
+            let response = HttpResponse {
+                status: "431 Request Header Fields Too Large",
+                content_type: "text/plain",
+                body: String::new(),
+            };
+
+            return write_http_response(&mut connection, response).await;
+        }
+
+        let len = connection.reader.read(&mut buf).await?;
+
+        if len == 0 {
+            return Ok(());
+        }
+
+        request.extend_from_slice(&buf[..len]);
+    }
+
+    let request_line = request
+        .split(|byte| *byte == b'\n')
+        .next()
+        .unwrap_or_default();
+    let mut parts = request_line.split(|byte| *byte == b' ');
+
+    let response = match (parts.next(), parts.next().map(std::str::from_utf8)) {
+        (Some(b"GET"), Some(Ok(path))) => handler(path).await,
+        (Some(b"GET"), _) => HttpResponse::not_found(),
+        _ => HttpResponse {
+            status: "405 Method Not Allowed",
+            content_type: "text/plain",
+            body: String::new(),
+        },
+    };
+
+    write_http_response(&mut connection, response).await
+}
+
+async fn write_http_response(connection: &mut Connection, response: HttpResponse) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+
+    connection.writer.write_all(head.as_bytes()).await?;
+    connection
+        .writer
+        .write_all(response.body.as_bytes())
+        .await?;
+    connection.writer.flush().await
+}
@@ -1,20 +1,16 @@
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result};
 
-#[cfg(feature = "use-async-std")]
-use async_std::{
-    io::{Read, Write},
-    prelude::*,
-};
 use bytes::BytesMut;
+use futures_lite::{
+    future::or,
+    io::{AsyncRead as Read, AsyncReadExt, AsyncWrite as Write, AsyncWriteExt},
+};
 use prost::Message;
-#[cfg(feature = "use-smol")]
-use smol::io::{AsyncRead as Read, AsyncReadExt, AsyncWrite as Write, AsyncWriteExt};
-#[cfg(feature = "use-tokio")]
-use tokio::io::{AsyncRead as Read, AsyncReadExt, AsyncWrite as Write, AsyncWriteExt};
 
 use crate::{
     stream_split::StreamSplit,
     types::{decode, encode},
+    utils::ConnectionGuard,
 };
 
 const DEFAULT_BUFFER_SIZE: usize = 4096;
@@ -26,6 +22,7 @@ where
     stream: S,
     read_buf: [u8; DEFAULT_BUFFER_SIZE],
     buf: BytesMut,
+    guard: Option<ConnectionGuard>,
 }
 
 impl<S> StreamReader<S>
@@ -37,9 +34,15 @@ where
             stream,
             read_buf: [0; DEFAULT_BUFFER_SIZE],
             buf: BytesMut::new(),
+            guard: None,
         }
     }
 
+    /// Attaches a connection guard to this reader. Connection is considered established until the reader is dropped.
+    pub fn set_guard(&mut self, guard: ConnectionGuard) {
+        self.guard = Some(guard);
+    }
+
     pub async fn read<M: Message + Default>(&mut self) -> Result<Option<M>> {
         let current_value = decode::<M, _>(&mut self.buf)?;
 
@@ -54,11 +57,49 @@ where
         Ok(value)
     }
 
+    /// Puts a message back in front of buffered data so that it is returned by next read
+    pub fn unread<M: Message>(&mut self, message: M) -> Result<()> {
+        let mut buf = BytesMut::new();
+        encode(message, &mut buf)?;
+
+        buf.extend_from_slice(&self.buf);
+        self.buf = buf;
+
+        Ok(())
+    }
+
+    /// Decodes a message which is already buffered, without reading from the stream
+    pub fn read_buffered<M: Message + Default>(&mut self) -> Result<Option<M>> {
+        decode::<M, _>(&mut self.buf)
+    }
+
     async fn fill_buf(&mut self) -> Result<usize> {
-        let bytes_read = self.stream.read(&mut self.read_buf).await?;
+        let bytes_read = match self.guard {
+            Some(ref guard) => {
+                let bytes_read = or(self.stream.read(&mut self.read_buf), async {
+                    guard.replaced().await;
+
+                    Err(replaced_error())
+                })
+                .await?;
+
+                // With blocking sockets, a pending read is not interrupted by replacement and completes only once
+                // more data is received
+                if guard.is_replaced() {
+                    return Err(replaced_error());
+                }
+
+                bytes_read
+            }
+            None => self.stream.read(&mut self.read_buf).await?,
+        };
 
         if bytes_read == 0 {
-            return Ok(0);
+            // Returning `Ok(None)` from `read()` here would make callers poll a closed stream forever
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "Peer closed the connection",
+            ));
         }
 
         self.buf.extend_from_slice(&self.read_buf[0..bytes_read]);
@@ -85,13 +126,70 @@ where
         let mut buf = BytesMut::new();
         encode(message, &mut buf)?;
 
-        self.stream.write(&buf).await.map(|_| ())
+        // Streams like TLS buffer written data (and may accept only a part of it in a single write), so, the whole
+        // message is written and flushed before the response is considered sent
+        self.stream.write_all(&buf).await?;
+        self.stream.flush().await
     }
 }
 
+fn replaced_error() -> Error {
+    Error::new(
+        ErrorKind::ConnectionAborted,
+        "Connection was replaced by a new connection of same type",
+    )
+}
+
 pub fn get_stream_pair<S: StreamSplit>(
     stream: S,
 ) -> (StreamReader<S::Reader>, StreamWriter<S::Writer>) {
     let (reader, writer) = stream.split_stream();
     (StreamReader::new(reader), StreamWriter::new(writer))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures_lite::{future::block_on, io::Cursor};
+
+    use super::*;
+    use crate::{
+        types::{RequestEcho, RequestFlush},
+        utils::{ConnectionTracker, ConnectionType},
+    };
+
+    #[test]
+    fn check_replaced_connection_discards_read_data() {
+        let tracker = Arc::new(ConnectionTracker::default());
+
+        let mut buf = BytesMut::new();
+        encode(RequestFlush::default(), &mut buf).unwrap();
+
+        // A read which completes after replacement (like a blocking read does) does not return data
+        let mut reader = StreamReader::new(Cursor::new(buf.to_vec()));
+        reader.set_guard(tracker.connect(ConnectionType::Info));
+        let _new_guard = tracker.replace(ConnectionType::Info);
+
+        let err = block_on(reader.read::<RequestFlush>()).unwrap_err();
+        assert_eq!(ErrorKind::ConnectionAborted, err.kind());
+    }
+
+    #[test]
+    fn check_unread_message_is_read_first() {
+        let echo = |message: &str| RequestEcho {
+            message: message.to_owned(),
+        };
+
+        let mut buf = BytesMut::new();
+        encode(echo("second"), &mut buf).unwrap();
+
+        let mut reader = StreamReader::new(Cursor::new(buf.to_vec()));
+
+        block_on(async {
+            reader.unread(echo("first")).unwrap();
+            assert_eq!(Some(echo("first")), reader.read().await.unwrap());
+            assert_eq!(Some(echo("second")), reader.read().await.unwrap());
+        });
+    }
+}
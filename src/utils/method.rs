@@ -0,0 +1,117 @@
+use tendermint_proto::abci::request::Value as RequestValue;
+
+/// ABCI methods (used to configure per-method settings of server, e.g., deadlines)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    /// `Echo` (available on all connections)
+    Echo,
+    /// `Flush` (available on all connections)
+    Flush,
+    /// `Info` on info connection
+    Info,
+    /// `SetOption` on info connection
+    SetOption,
+    /// `InitChain` on consensus connection
+    InitChain,
+    /// `Query` on info connection
+    Query,
+    /// `BeginBlock` on consensus connection
+    BeginBlock,
+    /// `CheckTx` on mempool connection
+    CheckTx,
+    /// `DeliverTx` on consensus connection
+    DeliverTx,
+    /// `EndBlock` on consensus connection
+    EndBlock,
+    /// `Commit` on consensus connection
+    Commit,
+    /// `ListSnapshots` on snapshot connection
+    ListSnapshots,
+    /// `OfferSnapshot` on snapshot connection
+    OfferSnapshot,
+    /// `LoadSnapshotChunk` on snapshot connection
+    LoadSnapshotChunk,
+    /// `ApplySnapshotChunk` on snapshot connection
+    ApplySnapshotChunk,
+}
+
+impl Method {
+    /// All the ABCI methods
+    pub const ALL: [Self; 15] = [
+        Self::Echo,
+        Self::Flush,
+        Self::Info,
+        Self::SetOption,
+        Self::InitChain,
+        Self::Query,
+        Self::BeginBlock,
+        Self::CheckTx,
+        Self::DeliverTx,
+        Self::EndBlock,
+        Self::Commit,
+        Self::ListSnapshots,
+        Self::OfferSnapshot,
+        Self::LoadSnapshotChunk,
+        Self::ApplySnapshotChunk,
+    ];
+
+    /// Returns the index of method in [`ALL`](Self::ALL)
+    pub(crate) fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Returns the name of method (used in logs, spans and as a label of metrics)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Echo => "echo",
+            Self::Flush => "flush",
+            Self::Info => "info",
+            Self::SetOption => "set_option",
+            Self::InitChain => "init_chain",
+            Self::Query => "query",
+            Self::BeginBlock => "begin_block",
+            Self::CheckTx => "check_tx",
+            Self::DeliverTx => "deliver_tx",
+            Self::EndBlock => "end_block",
+            Self::Commit => "commit",
+            Self::ListSnapshots => "list_snapshots",
+            Self::OfferSnapshot => "offer_snapshot",
+            Self::LoadSnapshotChunk => "load_snapshot_chunk",
+            Self::ApplySnapshotChunk => "apply_snapshot_chunk",
+        }
+    }
+}
+
+impl From<&RequestValue> for Method {
+    fn from(request_value: &RequestValue) -> Self {
+        match request_value {
+            RequestValue::Echo(_) => Self::Echo,
+            RequestValue::Flush(_) => Self::Flush,
+            RequestValue::Info(_) => Self::Info,
+            RequestValue::SetOption(_) => Self::SetOption,
+            RequestValue::InitChain(_) => Self::InitChain,
+            RequestValue::Query(_) => Self::Query,
+            RequestValue::BeginBlock(_) => Self::BeginBlock,
+            RequestValue::CheckTx(_) => Self::CheckTx,
+            RequestValue::DeliverTx(_) => Self::DeliverTx,
+            RequestValue::EndBlock(_) => Self::EndBlock,
+            RequestValue::Commit(_) => Self::Commit,
+            RequestValue::ListSnapshots(_) => Self::ListSnapshots,
+            RequestValue::OfferSnapshot(_) => Self::OfferSnapshot,
+            RequestValue::LoadSnapshotChunk(_) => Self::LoadSnapshotChunk,
+            RequestValue::ApplySnapshotChunk(_) => Self::ApplySnapshotChunk,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_method_index() {
+        for (index, method) in Method::ALL.iter().enumerate() {
+            assert_eq!(index, method.index());
+        }
+    }
+}
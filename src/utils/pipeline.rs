@@ -0,0 +1,143 @@
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_lock::{Semaphore, SemaphoreGuardArc};
+use tracing::debug;
+
+/// Snapshot of the state of a request queue with bounded concurrency (e.g., in-flight `CheckTx` requests)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueMetrics {
+    /// Maximum number of requests which can be in-flight at once
+    pub limit: usize,
+    /// Number of requests currently in-flight (being executed or waiting for their response to be written)
+    pub in_flight: usize,
+    /// Maximum number of requests observed in-flight at once
+    pub peak_in_flight: usize,
+    /// Number of times a request had to wait because the queue was full
+    pub saturated_count: u64,
+}
+
+/// Limits the number of in-flight requests of a pipelined connection. Once the limit is reached, reader stops reading
+/// new requests from socket until responses of earlier requests are written.
+#[derive(Debug)]
+pub struct InFlightLimiter {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+    saturated_count: AtomicU64,
+}
+
+impl InFlightLimiter {
+    pub fn new(limit: usize) -> Self {
+        assert!(limit > 0, "Limit of in-flight requests should be non-zero");
+
+        Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit)),
+            in_flight: Default::default(),
+            peak_in_flight: Default::default(),
+            saturated_count: Default::default(),
+        }
+    }
+
+    /// Waits until a new request can be put in-flight. Request is considered in-flight until the returned permit is
+    /// dropped.
+    pub async fn acquire(self: &Arc<Self>) -> InFlightPermit {
+        if let Some(permit) = self.try_acquire() {
+            return permit;
+        }
+
+        self.saturated_count.fetch_add(1, Ordering::Relaxed);
+        debug!(
+            message = "Queue saturated, waiting for in-flight requests to complete",
+            limit = self.limit
+        );
+
+        let guard = self.semaphore.acquire_arc().await;
+        self.permit(guard)
+    }
+
+    /// Puts a new request in-flight if the limit is not reached yet
+    pub fn try_acquire(self: &Arc<Self>) -> Option<InFlightPermit> {
+        let guard = self.semaphore.try_acquire_arc()?;
+        Some(self.permit(guard))
+    }
+
+    fn permit(self: &Arc<Self>, guard: SemaphoreGuardArc) -> InFlightPermit {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+
+        InFlightPermit {
+            _guard: guard,
+            limiter: self.clone(),
+        }
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        QueueMetrics {
+            limit: self.limit,
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            peak_in_flight: self.peak_in_flight.load(Ordering::Relaxed),
+            saturated_count: self.saturated_count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Permit for one in-flight request
+pub struct InFlightPermit {
+    _guard: SemaphoreGuardArc,
+    limiter: Arc<InFlightLimiter>,
+}
+
+impl InFlightPermit {
+    /// Returns the number of requests currently in-flight
+    pub fn queue_depth(&self) -> usize {
+        self.limiter.in_flight.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for InFlightPermit {
+    fn drop(&mut self) {
+        self.limiter.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn check_in_flight_limit() {
+        let limiter = Arc::new(InFlightLimiter::new(2));
+
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+        assert_eq!(2, second.queue_depth());
+
+        let waiting = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await.queue_depth() })
+        };
+
+        tokio::task::yield_now().await;
+        assert_eq!(1, limiter.metrics().saturated_count);
+        assert_eq!(2, limiter.metrics().in_flight);
+
+        drop(first);
+        assert_eq!(2, waiting.await.unwrap());
+        drop(second);
+
+        assert_eq!(
+            QueueMetrics {
+                limit: 2,
+                in_flight: 0,
+                peak_in_flight: 2,
+                saturated_count: 1,
+            },
+            limiter.metrics()
+        );
+    }
+}
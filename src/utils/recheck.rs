@@ -0,0 +1,363 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use tendermint_proto::abci::request::Value as RequestValue;
+use tracing::{debug, info};
+
+use crate::types::{CheckTxType, RequestCheckTx, ResponseCheckTx};
+
+/// Statistics of recheck rounds on mempool connection. A recheck round is a sequence of consecutive `CheckTx` requests
+/// of type [`Recheck`](crate::types::CheckTxType::Recheck) which Tendermint sends after each commit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecheckStats {
+    /// Number of completed recheck rounds
+    pub rounds: u64,
+    /// Total number of rechecked transactions in completed rounds
+    pub rechecks: u64,
+    /// Total number of rechecks answered from recheck cache in completed rounds
+    pub cache_hits: u64,
+    /// Number of rechecked transactions in last completed round
+    pub last_round_rechecks: u64,
+    /// Time taken by last completed round (from receiving its first recheck to completing its last recheck)
+    pub last_round_duration: Option<Duration>,
+}
+
+/// Tracks recheck rounds on mempool connection and optionally caches `CheckTx` responses so that transactions whose
+/// relevant state is unchanged are not validated again on recheck
+#[derive(Debug, Default)]
+pub struct RecheckTracker {
+    state: Mutex<TrackerState>,
+    cache: Option<Mutex<RecheckCache>>,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    stats: RecheckStats,
+    round: Option<Round>,
+}
+
+#[derive(Debug)]
+struct Round {
+    start: Instant,
+    last_completed: Instant,
+    dispatched: u64,
+    completed: u64,
+    cache_hits: u64,
+    /// `true` once a request other than recheck is received after this round
+    closing: bool,
+}
+
+impl Round {
+    fn start() -> Self {
+        let now = Instant::now();
+
+        Self {
+            start: now,
+            last_completed: now,
+            dispatched: 0,
+            completed: 0,
+            cache_hits: 0,
+            closing: false,
+        }
+    }
+}
+
+impl RecheckTracker {
+    /// Creates a new instance of [`RecheckTracker`] with a cache of given capacity
+    pub fn with_cache(capacity: usize) -> Self {
+        Self {
+            state: Default::default(),
+            cache: Some(Mutex::new(RecheckCache::new(capacity))),
+        }
+    }
+
+    /// Returns `true` if responses of `CheckTx` requests are cached
+    pub fn is_caching(&self) -> bool {
+        self.cache.is_some()
+    }
+
+    /// Records a request read from mempool connection. Must be called in the order in which requests are received.
+    pub fn on_request(&self, request_value: &RequestValue) {
+        match request_value {
+            RequestValue::CheckTx(check_tx_request) => self.on_check_tx(check_tx_request),
+            _ => self.record(false),
+        }
+    }
+
+    /// Records a `CheckTx` request read from mempool connection
+    pub fn on_check_tx(&self, check_tx_request: &RequestCheckTx) {
+        self.record(check_tx_request.r#type() == CheckTxType::Recheck)
+    }
+
+    fn record(&self, recheck: bool) {
+        let mut state = self.state();
+
+        if recheck {
+            if matches!(state.round, Some(ref round) if round.closing) {
+                // Previous round is still executing but a new one has started
+                self.finish_round(&mut state);
+            }
+
+            state.round.get_or_insert_with(Round::start).dispatched += 1;
+        } else if let Some(ref mut round) = state.round {
+            round.closing = true;
+
+            if round.completed == round.dispatched {
+                self.finish_round(&mut state);
+            }
+        }
+    }
+
+    /// Records completion of a recheck
+    pub fn on_recheck_complete(&self, cache_hit: bool) {
+        let mut state = self.state();
+
+        if let Some(ref mut round) = state.round {
+            round.completed += 1;
+            round.last_completed = Instant::now();
+
+            if cache_hit {
+                round.cache_hits += 1;
+            }
+
+            if round.closing && round.completed == round.dispatched {
+                self.finish_round(&mut state);
+            }
+        }
+    }
+
+    /// Returns cached response of a rechecked transaction if it was checked with the same state fingerprint before
+    pub fn cached(&self, tx: &[u8], fingerprint: &[u8]) -> Option<ResponseCheckTx> {
+        let round_id = self.next_round_id(true);
+        self.cache
+            .as_ref()?
+            .lock()
+            .expect("Lock poisoned")
+            .get(tx, fingerprint, round_id)
+    }
+
+    /// Caches response of a transaction checked with given state fingerprint
+    pub fn store(
+        &self,
+        tx: Vec<u8>,
+        fingerprint: Vec<u8>,
+        response: ResponseCheckTx,
+        recheck: bool,
+    ) {
+        let round_id = self.next_round_id(recheck);
+
+        if let Some(ref cache) = self.cache {
+            cache
+                .lock()
+                .expect("Lock poisoned")
+                .insert(tx, fingerprint, response, round_id);
+        }
+    }
+
+    pub fn stats(&self) -> RecheckStats {
+        self.state().stats
+    }
+
+    /// Returns the recheck round in which a transaction checked now is expected to be rechecked next. Rounds are
+    /// numbered from `1`.
+    fn next_round_id(&self, recheck: bool) -> u64 {
+        // A recheck belongs to the round after the last completed one
+        let completed_rounds = self.state().stats.rounds;
+
+        if recheck {
+            completed_rounds + 2
+        } else {
+            completed_rounds + 1
+        }
+    }
+
+    fn finish_round(&self, state: &mut TrackerState) {
+        let round = match state.round.take() {
+            Some(round) => round,
+            None => return,
+        };
+
+        let duration = round.last_completed - round.start;
+
+        state.stats.rounds += 1;
+        state.stats.rechecks += round.completed;
+        state.stats.cache_hits += round.cache_hits;
+        state.stats.last_round_rechecks = round.completed;
+        state.stats.last_round_duration = Some(duration);
+
+        info!(
+            message = "Recheck round completed",
+            round = state.stats.rounds,
+            rechecks = round.completed,
+            cache_hits = round.cache_hits,
+            ?duration
+        );
+
+        if let Some(ref cache) = self.cache {
+            // Transactions which were not rechecked in this round are not in mempool anymore
+            cache
+                .lock()
+                .expect("Lock poisoned")
+                .retain_since(state.stats.rounds + 1);
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, TrackerState> {
+        self.state.lock().expect("Lock poisoned")
+    }
+}
+
+/// Cache of `CheckTx` responses keyed by transaction
+#[derive(Debug)]
+struct RecheckCache {
+    capacity: usize,
+    entries: HashMap<Vec<u8>, CacheEntry>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    fingerprint: Vec<u8>,
+    response: ResponseCheckTx,
+    /// Recheck round in which this entry is expected to be used next
+    round_id: u64,
+}
+
+impl RecheckCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+        }
+    }
+
+    fn get(&mut self, tx: &[u8], fingerprint: &[u8], round_id: u64) -> Option<ResponseCheckTx> {
+        let entry = self.entries.get_mut(tx)?;
+
+        if entry.fingerprint != fingerprint {
+            return None;
+        }
+
+        entry.round_id = round_id;
+        Some(entry.response.clone())
+    }
+
+    fn insert(
+        &mut self,
+        tx: Vec<u8>,
+        fingerprint: Vec<u8>,
+        response: ResponseCheckTx,
+        round_id: u64,
+    ) {
+        if self.entries.len() >= self.capacity
+            && !self.entries.contains_key(&tx)
+            && !self.evict_oldest_round(round_id)
+        {
+            debug!(
+                message = "Recheck cache is full, not caching response",
+                capacity = self.capacity
+            );
+            return;
+        }
+
+        self.entries.insert(
+            tx,
+            CacheEntry {
+                fingerprint,
+                response,
+                round_id,
+            },
+        );
+    }
+
+    /// Removes entries of the oldest recheck round if it is older than given round. Returns `true` if any entry was
+    /// removed.
+    fn evict_oldest_round(&mut self, round_id: u64) -> bool {
+        let oldest_round_id = match self.entries.values().map(|entry| entry.round_id).min() {
+            Some(oldest_round_id) if oldest_round_id < round_id => oldest_round_id,
+            _ => return false,
+        };
+
+        self.entries
+            .retain(|_, entry| entry.round_id != oldest_round_id);
+
+        debug!(
+            message = "Recheck cache is full, evicted entries of oldest round",
+            capacity = self.capacity,
+            round = oldest_round_id
+        );
+
+        true
+    }
+
+    fn retain_since(&mut self, round_id: u64) {
+        self.entries.retain(|_, entry| entry.round_id >= round_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_tx(tx: u8, r#type: CheckTxType) -> RequestValue {
+        RequestValue::CheckTx(RequestCheckTx {
+            tx: vec![tx],
+            r#type: r#type.into(),
+        })
+    }
+
+    #[test]
+    fn check_recheck_rounds_and_cache() {
+        let tracker = RecheckTracker::with_cache(10);
+
+        tracker.on_request(&check_tx(1, CheckTxType::New));
+        tracker.store(vec![1], vec![0], Default::default(), false);
+        tracker.on_request(&check_tx(2, CheckTxType::New));
+        tracker.store(vec![2], vec![0], Default::default(), false);
+
+        // First round: tx `1` is unchanged and tx `2` is not rechecked (e.g., because it got committed)
+        tracker.on_request(&check_tx(1, CheckTxType::Recheck));
+        assert!(tracker.cached(&[1], &[0]).is_some());
+        tracker.on_recheck_complete(true);
+
+        tracker.on_request(&check_tx(3, CheckTxType::New));
+
+        // Second round: tx `1` has a different fingerprint and tx `2` was removed from cache
+        tracker.on_request(&check_tx(1, CheckTxType::Recheck));
+        assert!(tracker.cached(&[1], &[1]).is_none());
+        assert!(tracker.cached(&[2], &[0]).is_none());
+        tracker.on_recheck_complete(false);
+
+        // Round is not completed until a request other than recheck is received
+        assert_eq!(1, tracker.stats().rounds);
+        tracker.on_request(&RequestValue::Flush(Default::default()));
+
+        let stats = tracker.stats();
+        assert_eq!(2, stats.rounds);
+        assert_eq!(2, stats.rechecks);
+        assert_eq!(1, stats.cache_hits);
+        assert_eq!(1, stats.last_round_rechecks);
+        assert!(stats.last_round_duration.is_some());
+    }
+
+    #[test]
+    fn check_full_cache_evicts_oldest_round() {
+        let mut cache = RecheckCache::new(2);
+
+        cache.insert(vec![1], vec![0], Default::default(), 1);
+        cache.insert(vec![2], vec![0], Default::default(), 2);
+
+        // Entries of round `1` are evicted to make space for the new entry
+        cache.insert(vec![3], vec![0], Default::default(), 2);
+        assert!(cache.get(&[1], &[0], 2).is_none());
+        assert!(cache.get(&[2], &[0], 2).is_some());
+        assert!(cache.get(&[3], &[0], 2).is_some());
+
+        // No entry is older than the new one, so it is not cached
+        cache.insert(vec![4], vec![0], Default::default(), 2);
+        assert!(cache.get(&[4], &[0], 2).is_none());
+        assert_eq!(2, cache.entries.len());
+    }
+}
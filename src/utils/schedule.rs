@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use crate::types::TxAccess;
+
+/// Groups transactions into waves such that transactions in the same wave do not conflict with each other and every
+/// transaction is placed in a later wave than all the earlier transactions it conflicts with. Transactions without an
+/// access set conflict with every other transaction.
+///
+/// Returned waves contain indices of transactions in ascending order. The output only depends on the input, so, the
+/// results of executing waves one after another are the same on every node.
+pub fn schedule(accesses: &[Option<TxAccess>]) -> Vec<Vec<usize>> {
+    let mut waves: Vec<Vec<usize>> = Vec::new();
+
+    // Every transaction after a transaction without access set is placed after its wave
+    let mut barrier: Option<usize> = None;
+    // Last wave in which a key was written or read
+    let mut last_write: HashMap<&[u8], usize> = HashMap::new();
+    let mut last_read: HashMap<&[u8], usize> = HashMap::new();
+
+    for (index, access) in accesses.iter().enumerate() {
+        let wave = match access {
+            None => {
+                let wave = waves.len();
+                barrier = Some(wave);
+                wave
+            }
+            Some(access) => {
+                let after = |wave: Option<&usize>| wave.map(|wave| wave + 1).unwrap_or_default();
+
+                let mut wave = after(barrier.as_ref());
+
+                for key in access.reads() {
+                    wave = wave.max(after(last_write.get(key)));
+                }
+
+                for key in access.writes() {
+                    wave = wave
+                        .max(after(last_write.get(key)))
+                        .max(after(last_read.get(key)));
+                }
+
+                for key in access.reads() {
+                    let last = last_read.entry(key).or_default();
+                    *last = (*last).max(wave);
+                }
+
+                for key in access.writes() {
+                    last_write.insert(key, wave);
+                }
+
+                wave
+            }
+        };
+
+        if wave == waves.len() {
+            waves.push(Vec::new());
+        }
+
+        waves[wave].push(index);
+    }
+
+    waves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_schedule() {
+        let accesses = vec![
+            Some(TxAccess::new().read("a").write("b")),
+            Some(TxAccess::new().read("c").write("d")),
+            // Reads `b` written by first transaction
+            Some(TxAccess::new().read("b")),
+            // Writes `c` read by second transaction
+            Some(TxAccess::new().write("c")),
+            Some(TxAccess::new().read("a")),
+            None,
+            Some(TxAccess::new().read("x")),
+            Some(TxAccess::new().read("y")),
+        ];
+
+        assert_eq!(
+            vec![vec![0, 1, 4], vec![2, 3], vec![5], vec![6, 7]],
+            schedule(&accesses)
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt::Write;
+
+use sha2::{Digest, Sha256};
+use tendermint_proto::abci::{response::Value as ResponseValue, Response};
+use tracing::{debug, Span};
+
+/// Returns the hash of a transaction as displayed by Tendermint (upper case hex encoded SHA-256)
+pub fn tx_hash(tx: &[u8]) -> String {
+    let mut hash = String::with_capacity(64);
+
+    for byte in Sha256::digest(tx) {
+        let _ = write!(hash, "{:02X}", byte);
+    }
+
+    hash
+}
+
+/// Records hash of a transaction in `tx_hash` field of given span. Transaction is hashed only if the span is enabled.
+pub fn record_tx_hash(span: &Span, tx: &[u8]) {
+    if !span.is_disabled() {
+        span.record("tx_hash", tx_hash(tx).as_str());
+    }
+}
+
+/// Records response code (for responses which have one) in `code` field of given span and logs exceptions
+pub fn record_response(span: &Span, response: &Response) {
+    let code = match response.value {
+        Some(ResponseValue::CheckTx(ref response)) => response.code,
+        Some(ResponseValue::DeliverTx(ref response)) => response.code,
+        Some(ResponseValue::Query(ref response)) => response.code,
+        Some(ResponseValue::Exception(ref exception)) => {
+            debug!(message = "Request resulted in an exception", error = %exception.error);
+            return;
+        }
+        _ => return,
+    };
+
+    span.record("code", code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_tx_hash() {
+        assert_eq!(
+            "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+            tx_hash(&[])
+        );
+    }
+}
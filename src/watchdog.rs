@@ -0,0 +1,142 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use futures_lite::future::or;
+use tracing::{error, warn};
+
+use crate::{runtime::Runtime, state::StateSnapshotCell, utils::Method};
+
+/// Action taken when an ABCI method does not complete before its hard deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineAction {
+    /// Stops waiting for the method and returns a `ResponseException` to Tendermint
+    ///
+    /// Note that the application's future is dropped (or, for `sync_api`, its result is ignored) and the application
+    /// may be left in an inconsistent state. Tendermint treats an exception as a fatal error on consensus connection.
+    Exception,
+    /// Logs an error and aborts the process
+    Abort,
+}
+
+/// Watches execution of ABCI methods against configured soft and hard deadlines
+#[derive(Clone)]
+pub struct Watchdog {
+    runtime: Arc<dyn Runtime>,
+    state_snapshot: Arc<StateSnapshotCell>,
+    /// Deadlines indexed by method (see [`Method::ALL`])
+    deadlines: Arc<[Deadline; Method::ALL.len()]>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Deadline {
+    soft: Option<Duration>,
+    hard: Option<(Duration, DeadlineAction)>,
+}
+
+impl Watchdog {
+    /// Creates a new instance of [`Watchdog`] without any deadlines. Consensus state logged when a request exceeds its
+    /// deadline is read from given snapshot.
+    pub fn new(runtime: Arc<dyn Runtime>, state_snapshot: Arc<StateSnapshotCell>) -> Self {
+        Self {
+            runtime,
+            state_snapshot,
+            deadlines: Default::default(),
+        }
+    }
+
+    /// Sets soft deadline of given method. A warning is logged when a request takes longer than soft deadline.
+    pub fn set_soft_deadline(&mut self, method: Method, deadline: Duration) {
+        self.deadline_mut(method).soft = Some(deadline);
+    }
+
+    /// Sets hard deadline of given method and the action to take when it is exceeded
+    pub fn set_hard_deadline(
+        &mut self,
+        method: Method,
+        deadline: Duration,
+        action: DeadlineAction,
+    ) {
+        self.deadline_mut(method).hard = Some((deadline, action));
+    }
+
+    /// Runs given future of an ABCI method. Returns an error message if the hard deadline of method is exceeded (and
+    /// the action is [`DeadlineAction::Exception`]).
+    ///
+    /// Warnings and errors are logged in current span, which is expected to carry the details of request. Deadlines are
+    /// measured from the call of this function, so, the future should include any waiting (e.g., for locks) done by
+    /// the request.
+    pub async fn watch<F>(&self, method: Method, future: F) -> Result<F::Output, String>
+    where
+        F: Future,
+    {
+        let deadline = self.deadlines[method.index()];
+
+        if deadline.soft.is_none() && deadline.hard.is_none() {
+            return Ok(future.await);
+        }
+
+        let mut future = Box::pin(future);
+        let mut elapsed = Duration::ZERO;
+
+        if let Some(soft) = deadline.soft {
+            // Soft deadline is ignored if it is not earlier than hard deadline
+            if !matches!(deadline.hard, Some((hard, _)) if hard <= soft) {
+                match self.run_until(future.as_mut(), soft).await {
+                    Some(output) => return Ok(output),
+                    None => {
+                        warn!(
+                            message = "Request exceeded soft deadline",
+                            method = method.as_str(),
+                            deadline = ?soft,
+                            consensus_state = ?self.state_snapshot.get().state
+                        );
+                        elapsed = soft;
+                    }
+                }
+            }
+        }
+
+        let (hard, action) = match deadline.hard {
+            Some(hard) => hard,
+            None => return Ok(future.await),
+        };
+
+        match self.run_until(future.as_mut(), hard - elapsed).await {
+            Some(output) => Ok(output),
+            None => {
+                error!(
+                    message = "Request exceeded hard deadline",
+                    method = method.as_str(),
+                    deadline = ?hard,
+                    ?action,
+                    consensus_state = ?self.state_snapshot.get().state
+                );
+
+                match action {
+                    DeadlineAction::Exception => Err(format!(
+                        "`{}` did not complete within hard deadline of {:?}",
+                        method.as_str(),
+                        hard
+                    )),
+                    DeadlineAction::Abort => std::process::abort(),
+                }
+            }
+        }
+    }
+
+    async fn run_until<F>(&self, future: F, duration: Duration) -> Option<F::Output>
+    where
+        F: Future,
+    {
+        let sleep = self.runtime.sleep(duration);
+
+        or(async { Some(future.await) }, async {
+            sleep.await;
+            None
+        })
+        .await
+    }
+
+    fn deadline_mut(&mut self, method: Method) -> &mut Deadline {
+        &mut Arc::make_mut(&mut self.deadlines)[method.index()]
+    }
+}